@@ -0,0 +1,203 @@
+use std::fmt;
+
+use numpy::ndarray::{Array, ArrayView, Ix1, Ix2, s};
+
+use crate::strategies::StrategyType;
+use crate::utils::fd_jacobian;
+
+// weight on the squared distance between a player's strategy and its projection onto the constraints;
+// payoffs are evaluated at the projection, so any positive weight makes the feasible point itself optimal
+pub(crate) const DISTANCE_WEIGHT: f64 = 1.;
+// strategy values are kept at least this large when projecting, since they must stay positive
+const FLOOR: f64 = 1e-10;
+const MAX_SWEEPS: usize = 1000;
+
+// which periods a constraint sums over
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConstraintScope {
+    // applies separately in every period
+    EachPeriod,
+    // applies only in the given period
+    Period(usize),
+    // applies to the sum over all periods
+    Cumulative,
+}
+
+// bounds on a weighted sum of a player's strategy values, e.g. with coefs [1, 1] and an upper
+// bound in each period, a cap on xs + xp; with coefs [0, 0, 1, 1] and a cumulative upper bound,
+// a cap on total investment
+#[derive(Clone, Debug)]
+pub struct LinearConstraint {
+    // one weight for each of the strategy params (xs, xp, and for investment, inv_s, inv_p)
+    pub coefs: Array<f64, Ix1>,
+    pub scope: ConstraintScope,
+    pub lower: Option<f64>,
+    pub upper: Option<f64>,
+    // players the constraint applies to; None means every player
+    pub players: Option<Vec<usize>>,
+}
+
+impl LinearConstraint {
+    pub fn new(
+        coefs: Array<f64, Ix1>, scope: ConstraintScope, lower: Option<f64>, upper: Option<f64>
+    ) -> Result<Self, &'static str> {
+        if lower.is_none() && upper.is_none() {
+            return Err("Constraint needs a lower bound, an upper bound, or both");
+        }
+        if let (Some(lo), Some(hi)) = (lower, upper) {
+            if lo > hi {
+                return Err("Constraint lower bound must not exceed its upper bound");
+            }
+        }
+        if coefs.iter().all(|&c| c == 0.) {
+            return Err("Constraint needs at least one nonzero coefficient");
+        }
+        Ok(LinearConstraint { coefs, scope, lower, upper, players: None })
+    }
+
+    pub fn upper(coefs: Array<f64, Ix1>, scope: ConstraintScope, bound: f64) -> Result<Self, &'static str> {
+        Self::new(coefs, scope, None, Some(bound))
+    }
+
+    pub fn lower(coefs: Array<f64, Ix1>, scope: ConstraintScope, bound: f64) -> Result<Self, &'static str> {
+        Self::new(coefs, scope, Some(bound), None)
+    }
+
+    pub fn for_players(self, players: Vec<usize>) -> Self {
+        LinearConstraint { players: Some(players), ..self }
+    }
+
+    pub fn applies_to(&self, i: usize) -> bool {
+        self.players.as_ref().is_none_or(|players| players.contains(&i))
+    }
+
+    pub fn validate(&self, t: usize, n: usize, nparams: usize) -> Result<(), String> {
+        if self.coefs.len() != nparams {
+            return Err(format!("Constraint has {} coefs but strategies have {} params", self.coefs.len(), nparams));
+        }
+        if let ConstraintScope::Period(k) = self.scope {
+            if k >= t {
+                return Err(format!("Constraint applies to period {} but strategies only have {}", k, t));
+            }
+        }
+        if let Some(players) = &self.players {
+            if players.iter().any(|&i| i >= n) {
+                return Err(format!("Constraint applies to players {:?} but there are only {}", players, n));
+            }
+        }
+        Ok(())
+    }
+
+    // the periods summed over for each inequality this constraint imposes
+    fn periods(&self, t: usize) -> Vec<(usize, usize)> {
+        match self.scope {
+            ConstraintScope::EachPeriod => (0..t).map(|k| (k, k + 1)).collect(),
+            ConstraintScope::Period(k) => vec![(k, k + 1)],
+            ConstraintScope::Cumulative => vec![(0, t)],
+        }
+    }
+
+    fn value(&self, x: ArrayView<f64, Ix2>, (start, end): (usize, usize)) -> f64 {
+        x.slice(s![start..end, ..]).dot(&self.coefs).sum()
+    }
+
+    // amount by which value is above the upper bound (positive) or below the lower bound (negative)
+    fn excess(&self, value: f64) -> f64 {
+        let lo = self.lower.unwrap_or(f64::NEG_INFINITY);
+        let hi = self.upper.unwrap_or(f64::INFINITY);
+        value - value.clamp(lo, hi)
+    }
+
+    fn tol(&self) -> f64 {
+        let scale = f64::max(self.lower.unwrap_or(0.).abs(), self.upper.unwrap_or(0.).abs());
+        1e-9 * (1. + scale)
+    }
+}
+
+impl fmt::Display for LinearConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bound = |b: Option<f64>| b.map_or("_".to_string(), |b| b.to_string());
+        write!(f, "{} <= {:.4} . x <= {} ({:?})", bound(self.lower), self.coefs, bound(self.upper), self.scope)?;
+        if let Some(players) = &self.players {
+            write!(f, " for players {:?}", players)?;
+        }
+        Ok(())
+    }
+}
+
+// whether any of the constraints apply to player i
+pub(crate) fn constrains(i: usize, constraints: &[LinearConstraint]) -> bool {
+    constraints.iter().any(|c| c.applies_to(i))
+}
+
+// moves player i's strategy onto the set satisfying their constraints,
+// by projecting onto each violated constraint in turn
+pub(crate) fn project(x: ArrayView<f64, Ix2>, i: usize, constraints: &[LinearConstraint]) -> Result<Array<f64, Ix2>, String> {
+    let mut x = x.to_owned();
+    let applicable = constraints.iter().filter(|c| c.applies_to(i)).collect::<Vec<_>>();
+    if applicable.is_empty() {
+        return Ok(x);
+    }
+    for _ in 0..MAX_SWEEPS {
+        let mut feasible = true;
+        for c in applicable.iter() {
+            for p in c.periods(x.nrows()) {
+                let excess = c.excess(c.value(x.view(), p));
+                if excess.abs() <= c.tol() {
+                    continue;
+                }
+                feasible = false;
+                let norm_sq = (p.1 - p.0) as f64 * c.coefs.dot(&c.coefs);
+                for mut row in x.slice_mut(s![p.0..p.1, ..]).rows_mut() {
+                    row.scaled_add(-excess / norm_sq, &c.coefs);
+                }
+                x.mapv_inplace(|v| v.max(FLOOR));
+            }
+        }
+        if feasible {
+            return Ok(x);
+        }
+    }
+    Err(format!("Could not satisfy the constraints on player {}", i))
+}
+
+// jacobian of project w.r.t. player i's flattened strategy, indexed as [j, k]
+pub(crate) fn project_jacobian(x: ArrayView<f64, Ix2>, i: usize, constraints: &[LinearConstraint]) -> Array<f64, Ix2> {
+    let shape = x.dim();
+    fd_jacobian(|y| {
+        let y = y.to_owned().into_shape(shape).unwrap();
+        let z = project(y.view(), i, constraints).unwrap_or(y);
+        Array::from_iter(z.iter().cloned())
+    }, Array::from_iter(x.iter().cloned()).view())
+}
+
+// checks that every constraint is well formed for the strategies and satisfied by them
+pub fn check_constraints<S: StrategyType>(strategies: &S, constraints: &[LinearConstraint]) -> Result<(), String> {
+    for c in constraints.iter() {
+        c.validate(strategies.t(), strategies.n(), S::nparams())?;
+        for i in (0..strategies.n()).filter(|&i| c.applies_to(i)) {
+            let x = strategies.data().slice_move(s![.., i, ..]);
+            for p in c.periods(strategies.t()) {
+                let value = c.value(x, p);
+                if c.excess(value).abs() > c.tol() {
+                    return Err(format!(
+                        "Strategies violate constraint {}: player {} has value {} over periods {}..{}",
+                        c, i, value, p.0, p.1
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// constraints for the game among a subset of players, reindexed to match
+pub(crate) fn subgame_constraints(constraints: &[LinearConstraint], players: &[usize]) -> Vec<LinearConstraint> {
+    constraints.iter().filter_map(|c| match &c.players {
+        None => Some(c.clone()),
+        Some(ps) => {
+            let sub = (0..players.len()).filter(|&k| ps.contains(&players[k])).collect::<Vec<_>>();
+            if sub.is_empty() { None } else { Some(c.clone().for_players(sub)) }
+        },
+    }).collect()
+}
@@ -0,0 +1,249 @@
+use std::fmt;
+use std::time::Instant;
+
+use numpy::ndarray::{Array, Ix1, Ix2};
+
+use crate::multistart::log_distance;
+use crate::params::Params;
+use crate::solve::{InitGuess, SolveResult, SolverOptions, StopReason, remaining, solve};
+use crate::states::PayoffAggregator;
+use crate::strategies::*;
+
+// payoffs interpolated linearly between two aggregators, which gives intermediate
+// points between consecutive entries of a scenario
+struct Homotopy<'a, T> {
+    from: &'a T,
+    to: &'a T,
+    lambda: f64,
+}
+
+impl<A, S, T> PayoffAggregator<A, S> for Homotopy<'_, T>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    fn n(&self) -> usize {
+        self.from.n()
+    }
+    fn u_i(&self, i: usize, strategies: &S) -> f64 {
+        if self.lambda == 1. {
+            return self.to.u_i(i, strategies);
+        }
+        (1. - self.lambda) * self.from.u_i(i, strategies) + self.lambda * self.to.u_i(i, strategies)
+    }
+    fn u(&self, strategies: &S) -> Array<f64, Ix1> {
+        if self.lambda == 1. {
+            return self.to.u(strategies);
+        }
+        (1. - self.lambda) * self.from.u(strategies) + self.lambda * self.to.u(strategies)
+    }
+    fn grad_i(&self, i: usize, strategies: &S) -> Array<f64, Ix2> {
+        if self.lambda == 1. {
+            return self.to.grad_i(i, strategies);
+        }
+        (1. - self.lambda) * self.from.grad_i(i, strategies) + self.lambda * self.to.grad_i(i, strategies)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ContinuationOptions<S: StrategyType> {
+    // init_guess is only used for the first point; later points start from the previous solution;
+    // timeout applies to the whole path rather than to each solve
+    pub solver_options: SolverOptions<S>,
+    // largest change in any log strategy value accepted between consecutive points on the branch
+    pub max_jump: f64,
+    // smallest step, as a fraction of the distance between consecutive requested points;
+    // if a step this small still fails, the branch is treated as having disappeared
+    pub min_step: f64,
+    // strategy values are clamped below at this when measuring jumps, so corner solutions don't count
+    pub floor: f64,
+}
+
+impl<S: StrategyType> ContinuationOptions<S> {
+    pub fn new(t: usize) -> Self {
+        ContinuationOptions {
+            solver_options: SolverOptions::random_init(t),
+            max_jump: 0.5,
+            min_step: 1. / 64.,
+            floor: 1e-8,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BranchPoint<S: StrategyType> {
+    // parameter value, or for a sequence of aggregators, the (fractional) index along it
+    pub position: f64,
+    pub strategies: S,
+    pub payoffs: Array<f64, Ix1>,
+    pub converged: bool,
+    // the tracked branch couldn't be followed to this point, so it lies on a different branch
+    pub fold: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct ContinuationResult<S: StrategyType> {
+    // one result per requested point, in order; if the path was cut short by a timeout
+    // or cancellation, the last result is the interrupted solve and later points are missing
+    pub results: Vec<SolveResult<S>>,
+    // every point visited along the branch, including intermediate steps
+    pub trace: Vec<BranchPoint<S>>,
+}
+
+impl<S: StrategyType> ContinuationResult<S> {
+    pub fn positions(&self) -> Vec<f64> {
+        self.trace.iter().map(|p| p.position).collect()
+    }
+    // positions at which the tracked branch disappeared
+    pub fn folds(&self) -> Vec<f64> {
+        self.trace.iter().filter(|p| p.fold).map(|p| p.position).collect()
+    }
+}
+
+impl<S: StrategyType> fmt::Display for ContinuationResult<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} requested points, {} points on the branch", self.results.len(), self.trace.len())?;
+        for p in self.trace.iter() {
+            write!(f, "{:.4}: payoffs = {:.4}", p.position, p.payoffs)?;
+            if !p.converged {
+                write!(f, " (did not converge)")?;
+            }
+            if p.fold {
+                write!(f, " (fold)")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn interrupted<S: StrategyType>(res: &SolveResult<S>) -> bool {
+    matches!(res.stop_reason, StopReason::Timeout | StopReason::Cancelled)
+}
+
+fn branch_point<S: StrategyType>(position: f64, res: &SolveResult<S>, fold: bool) -> BranchPoint<S> {
+    BranchPoint {
+        position,
+        strategies: res.strategies.clone(),
+        payoffs: res.payoffs.clone(),
+        converged: res.converged,
+        fold,
+    }
+}
+
+// follows the branch through start from lambda = 0 to lambda = 1, where agg_at gives the
+// aggregator at each lambda; the step is halved whenever a solve fails or jumps,
+// unless the last point wasn't converged, in which case there's no branch to follow;
+// a timeout or cancellation ends the segment at the interrupted solve
+fn trace_segment<A, S, U, F>(
+    agg_at: F, start: &S, positions: (f64, f64), options: &ContinuationOptions<S>,
+    deadline: Option<Instant>, trace: &mut Vec<BranchPoint<S>>
+) -> Result<SolveResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, U: PayoffAggregator<A, S>, F: Fn(f64) -> Result<U, argmin::core::Error>
+{
+    let mut current = start.clone();
+    let mut tracking = trace.last().is_some_and(|p| p.converged);
+    let mut lambda = 0.;
+    let mut step = 1.;
+    loop {
+        let next = f64::min(lambda + step, 1.);
+        let solver_options = SolverOptions {
+            init_guess: InitGuess::Fixed(current.clone()),
+            trace: false,
+            timeout: remaining(deadline),
+            ..options.solver_options.clone()
+        };
+        // solver errors are treated like failing to converge, since a smaller step may avoid them
+        let agg = agg_at(next)?;
+        let res = solve(&agg, &solver_options);
+        let position = positions.0 + next * (positions.1 - positions.0);
+        // an interrupted solve says nothing about the branch, so it isn't retried or marked as a fold
+        if matches!(&res, Ok(res) if interrupted(res)) {
+            let res = res?;
+            trace.push(branch_point(position, &res, false));
+            return Ok(res);
+        }
+        let ok = matches!(
+            &res, Ok(res) if res.converged && log_distance(&res.strategies, &current, options.floor) <= options.max_jump
+        );
+        if !ok && tracking && step / 2. >= options.min_step {
+            step /= 2.;
+            continue;
+        }
+        // either the step worked, or it can't shrink any further and we take whatever was found;
+        // if the solver failed outright, that's the point we started from, marked as not converged
+        let res = res.unwrap_or_else(|e| SolveResult {
+            payoffs: agg.u(&current),
+            strategies: current.clone(),
+            converged: false,
+            iterations: 0,
+            max_changes: Vec::new(),
+            termination_reasons: Vec::new(),
+            trace: Vec::new(),
+            stop_reason: StopReason::MaxItersReached,
+            warnings: vec![format!("the solver failed: {}", e)],
+            symmetric: false,
+        });
+        trace.push(branch_point(position, &res, !ok && tracking));
+        tracking = res.converged;
+        if next >= 1. {
+            return Ok(res);
+        }
+        current = res.strategies.clone();
+        lambda = next;
+        step = if ok { f64::min(2. * step, 1.) } else { 1. };
+    }
+}
+
+// solves each aggregator in turn, starting from the solution for the previous one;
+// intermediate steps blend the payoffs of consecutive aggregators
+pub fn solve_sequence<A, S, T>(aggs: &[T], options: &ContinuationOptions<S>) -> Result<ContinuationResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    if aggs.is_empty() {
+        return Err(argmin::core::Error::msg("Continuation needs at least one aggregator"));
+    }
+    let deadline = options.solver_options.timeout.map(|timeout| Instant::now() + timeout);
+    let first = solve(&aggs[0], &options.solver_options)?;
+    let mut trace = vec![branch_point(0., &first, false)];
+    let mut results = vec![first];
+    for k in 1..aggs.len() {
+        if interrupted(&results[k - 1]) {
+            break;
+        }
+        let start = results[k - 1].strategies.clone();
+        let res = trace_segment(
+            |lambda| Ok(Homotopy { from: &aggs[k - 1], to: &aggs[k], lambda }),
+            &start, ((k - 1) as f64, k as f64), options, deadline, &mut trace,
+        )?;
+        results.push(res);
+    }
+    Ok(ContinuationResult { results, trace })
+}
+
+// solves along a path of values for the named parameter, starting each point from the previous solution
+pub fn solve_path<A, S, T>(
+    agg: &T, param: &str, values: &[f64], options: &ContinuationOptions<S>
+) -> Result<ContinuationResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S> + Params
+{
+    if values.is_empty() {
+        return Err(argmin::core::Error::msg("Continuation needs at least one parameter value"));
+    }
+    let deadline = options.solver_options.timeout.map(|timeout| Instant::now() + timeout);
+    let with_value = |x: f64| agg.with_param(param, x).map_err(argmin::core::Error::msg);
+    let first = solve(&with_value(values[0])?, &options.solver_options)?;
+    let mut trace = vec![branch_point(values[0], &first, false)];
+    let mut results = vec![first];
+    for k in 1..values.len() {
+        if interrupted(&results[k - 1]) {
+            break;
+        }
+        let (from, to) = (values[k - 1], values[k]);
+        let start = results[k - 1].strategies.clone();
+        let res = trace_segment(
+            |lambda| with_value(from + lambda * (to - from)),
+            &start, (from, to), options, deadline, &mut trace,
+        )?;
+        results.push(res);
+    }
+    Ok(ContinuationResult { results, trace })
+}
@@ -1,6 +1,8 @@
 use numpy::ndarray::{Array, Ix1};
 
+use crate::params::{Params, index_mut, parse_index, unknown_param};
 use crate::strategies::*;
+use crate::utils::fd_step;
 
 pub trait CostFunc<A: ActionType>: Clone + Send + Sync {
 
@@ -9,6 +11,22 @@ pub trait CostFunc<A: ActionType>: Clone + Send + Sync {
         Array::from_iter((0..actions.n()).map(|i| self.c_i(i, actions)))
     }
 
+    // gradient of c_i w.r.t. player i's own action params
+    // defaults to finite differences; override when an analytic form is available
+    fn dc_i(&self, i: usize, actions: &A) -> Array<f64, Ix1> {
+        let mut actions_ = actions.clone();
+        Array::from_iter((0..A::nparams()).map(|m| {
+            let x = actions.data()[[i, m]];
+            let h = fd_step(x);
+            actions_.data_mut()[[i, m]] = x + h;
+            let up = self.c_i(i, &actions_);
+            actions_.data_mut()[[i, m]] = x - h;
+            let down = self.c_i(i, &actions_);
+            actions_.data_mut()[[i, m]] = x;
+            (up - down) / (2. * h)
+        }))
+    }
+
     fn n(&self) -> usize;
 }
 
@@ -30,6 +48,10 @@ impl CostFunc<Actions> for FixedUnitCost {
     fn c_i(&self, i: usize, actions: &Actions) -> f64 {
         self.r[i] * (actions.xs()[i] + actions.xp()[i])
     }
+
+    fn dc_i(&self, i: usize, _actions: &Actions) -> Array<f64, Ix1> {
+        Array::from_elem(2, self.r[i])
+    }
     
     fn n(&self) -> usize {
         self.r.len()
@@ -67,7 +89,32 @@ impl CostFunc<InvestActions> for FixedInvestCost {
         self.r_x[i] * (actions.xs()[i] + actions.xp()[i]) + self.r_inv[i] * (actions.inv_s()[i] + actions.inv_p()[i])
     }
 
+    fn dc_i(&self, i: usize, _actions: &InvestActions) -> Array<f64, Ix1> {
+        Array::from_vec(vec![self.r_x[i], self.r_x[i], self.r_inv[i], self.r_inv[i]])
+    }
+
     fn n(&self) -> usize {
         self.n
     }
 }
+
+impl Params for FixedUnitCost {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "r" => index_mut(&mut self.r, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+impl Params for FixedInvestCost {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "r_x" => index_mut(&mut self.r_x, index, name),
+            "r_inv" => index_mut(&mut self.r_inv, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
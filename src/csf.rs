@@ -1,10 +1,26 @@
-use numpy::ndarray::{Array, ArrayView, Ix1};
+use std::f64::consts::{PI, SQRT_2};
+
+use numpy::ndarray::{Array, ArrayView, Ix1, Ix2};
+
+use crate::params::{Params, index_mut, parse_index, unknown_param};
+use crate::utils::fd_jacobian;
 
 pub trait CSF: Clone + Send + Sync {
     fn q_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64;
     fn q(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
         Array::from_iter((0..p.len()).map(|i| self.q_i(i, p)))
     }
+
+    // jacobian of q w.r.t. p, indexed as [j, k] = d q_j / d p_k
+    // defaults to finite differences; override when an analytic form is available
+    fn dq(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        fd_jacobian(|p_| self.q(p_), p)
+    }
+
+    // number of players, for CSFs with per-player parameters
+    fn n(&self) -> Option<usize> {
+        None
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -28,6 +44,18 @@ impl CSF for DefaultCSF {
             Array::from_iter(p.iter().map(|x| x / sum_p))
         }
     }
+
+    fn dq(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        let n = p.len();
+        let sum_p: f64 = p.iter().sum();
+        if sum_p == 0.0 {
+            return Array::zeros((n, n));
+        }
+        Array::from_shape_fn((n, n), |(j, k)| {
+            let own = if j == k { 1.0 / sum_p } else { 0.0 };
+            own - p[j] / sum_p.powi(2)
+        })
+    }
 }
 
 
@@ -53,4 +81,478 @@ impl CSF for MaybeNoWinCSF {
             self.scale * x / (1. + self.scale * sum_p)
         ))
     }
+    fn dq(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        let n = p.len();
+        let denom = 1. + self.scale * p.iter().sum::<f64>();
+        Array::from_shape_fn((n, n), |(j, k)| {
+            let own = if j == k { self.scale / denom } else { 0. };
+            own - self.scale.powi(2) * p[j] / denom.powi(2)
+        })
+    }
+}
+
+impl Params for DefaultCSF {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        Err(unknown_param(name))
+    }
+}
+
+impl Params for MaybeNoWinCSF {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "scale" if index.is_none() => Ok(&mut self.scale),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+// q_j = h_j / (base + sum h), where h_j is player j's strength, which depends only on p_j,
+// and base is the strength of not awarding the win to anyone
+fn ratio_q(h: &Array<f64, Ix1>, base: f64) -> Array<f64, Ix1> {
+    let denom = base + h.sum();
+    if denom == 0. {
+        Array::zeros(h.len())
+    } else {
+        h / denom
+    }
+}
+
+// jacobian of ratio_q given dh_j = d h_j / d p_j
+fn ratio_dq(h: &Array<f64, Ix1>, dh: &Array<f64, Ix1>, base: f64) -> Array<f64, Ix2> {
+    let n = h.len();
+    let denom = base + h.sum();
+    if denom == 0. {
+        return Array::zeros((n, n));
+    }
+    Array::from_shape_fn((n, n), |(j, k)| {
+        let own = if j == k { dh[k] / denom } else { 0. };
+        own - h[j] * dh[k] / denom.powi(2)
+    })
+}
+
+fn validate_tullock(r: f64, w: &Array<f64, Ix1>) -> Result<(), &'static str> {
+    if !(r.is_finite() && r > 0.) {
+        return Err("When creating new Tullock CSF: r must be positive");
+    }
+    if w.is_empty() || w.iter().any(|&w_i| !(w_i.is_finite() && w_i > 0.)) {
+        return Err("When creating new Tullock CSF: w must be non-empty and positive");
+    }
+    Ok(())
+}
+
+fn validate_scale(scale: f64) -> Result<(), &'static str> {
+    if !(scale.is_finite() && scale > 0.) {
+        return Err("When creating new no-win CSF: scale must be positive");
+    }
+    Ok(())
+}
+
+// q_i = w_i p_i^r / sum_j w_j p_j^r;
+// r is the decisiveness of the contest, and w lets the contest favour some players
+#[derive(Clone, Debug)]
+pub struct TullockCSF {
+    pub r: f64,
+    pub w: Array<f64, Ix1>,
+}
+
+impl TullockCSF {
+    pub fn new(r: f64, w: Array<f64, Ix1>) -> Result<Self, &'static str> {
+        validate_tullock(r, &w)?;
+        Ok(TullockCSF { r, w })
+    }
+
+    pub fn unbiased(n: usize, r: f64) -> Result<Self, &'static str> {
+        Self::new(r, Array::ones(n))
+    }
+
+    fn h(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        &self.w * &p.mapv(|x| x.powf(self.r))
+    }
+
+    fn dh(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        self.r * &self.w * &p.mapv(|x| x.powf(self.r - 1.))
+    }
+}
+
+impl CSF for TullockCSF {
+    fn q_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        self.q(p)[i]
+    }
+    fn q(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        ratio_q(&self.h(p), 0.)
+    }
+    fn dq(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        ratio_dq(&self.h(p), &self.dh(p), 0.)
+    }
+    fn n(&self) -> Option<usize> {
+        Some(self.w.len())
+    }
+}
+
+// q_i = scale w_i p_i^r / (1 + scale sum_j w_j p_j^r), so that with some probability no one wins;
+// with r = 1 and w = 1 this is MaybeNoWinCSF
+#[derive(Clone, Debug)]
+pub struct MaybeNoWinTullockCSF {
+    pub r: f64,
+    pub w: Array<f64, Ix1>,
+    pub scale: f64,
+}
+
+impl MaybeNoWinTullockCSF {
+    pub fn new(r: f64, w: Array<f64, Ix1>, scale: f64) -> Result<Self, &'static str> {
+        validate_tullock(r, &w)?;
+        validate_scale(scale)?;
+        Ok(MaybeNoWinTullockCSF { r, w, scale })
+    }
+
+    pub fn unbiased(n: usize, r: f64, scale: f64) -> Result<Self, &'static str> {
+        Self::new(r, Array::ones(n), scale)
+    }
+
+    fn h(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        self.scale * &self.w * &p.mapv(|x| x.powf(self.r))
+    }
+
+    fn dh(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        self.scale * self.r * &self.w * &p.mapv(|x| x.powf(self.r - 1.))
+    }
+}
+
+impl CSF for MaybeNoWinTullockCSF {
+    fn q_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        self.q(p)[i]
+    }
+    fn q(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        ratio_q(&self.h(p), 1.)
+    }
+    fn dq(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        ratio_dq(&self.h(p), &self.dh(p), 1.)
+    }
+    fn n(&self) -> Option<usize> {
+        Some(self.w.len())
+    }
+}
+
+// strengths exp(k p_j + log_scale) relative to a no-win strength of exp(0),
+// shifted by the largest exponent so that large k p doesn't overflow
+fn logit_h(k: f64, p: ArrayView<f64, Ix1>, log_scale: Option<f64>) -> (Array<f64, Ix1>, f64) {
+    let exponents = p.mapv(|x| k * x + log_scale.unwrap_or(0.));
+    let null = if log_scale.is_some() { 0. } else { f64::NEG_INFINITY };
+    let shift = exponents.fold(null, |acc, &e| acc.max(e));
+    (exponents.mapv(|e| (e - shift).exp()), (null - shift).exp())
+}
+
+fn validate_logit(k: f64) -> Result<(), &'static str> {
+    if !(k.is_finite() && k > 0.) {
+        return Err("When creating new logit CSF: k must be positive");
+    }
+    Ok(())
+}
+
+// difference-form contest, q_i = exp(k p_i) / sum_j exp(k p_j);
+// only differences in p matter, and the contest becomes more decisive as k grows
+#[derive(Clone, Debug)]
+pub struct LogitCSF {
+    pub k: f64,
+}
+
+impl LogitCSF {
+    pub fn new(k: f64) -> Result<Self, &'static str> {
+        validate_logit(k)?;
+        Ok(LogitCSF { k })
+    }
+}
+
+impl CSF for LogitCSF {
+    fn q_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        self.q(p)[i]
+    }
+    fn q(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        let (h, base) = logit_h(self.k, p, None);
+        ratio_q(&h, base)
+    }
+    fn dq(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        let (h, base) = logit_h(self.k, p, None);
+        ratio_dq(&h, &(self.k * &h), base)
+    }
+}
+
+// q_i = scale exp(k p_i) / (1 + scale sum_j exp(k p_j))
+#[derive(Clone, Debug)]
+pub struct MaybeNoWinLogitCSF {
+    pub k: f64,
+    pub scale: f64,
+}
+
+impl MaybeNoWinLogitCSF {
+    pub fn new(k: f64, scale: f64) -> Result<Self, &'static str> {
+        validate_logit(k)?;
+        validate_scale(scale)?;
+        Ok(MaybeNoWinLogitCSF { k, scale })
+    }
+}
+
+impl CSF for MaybeNoWinLogitCSF {
+    fn q_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        self.q(p)[i]
+    }
+    fn q(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        let (h, base) = logit_h(self.k, p, Some(self.scale.ln()));
+        ratio_q(&h, base)
+    }
+    fn dq(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        let (h, base) = logit_h(self.k, p, Some(self.scale.ln()));
+        ratio_dq(&h, &(self.k * &h), base)
+    }
+}
+
+// the normal noise is integrated out on a grid over +-PROBIT_RANGE standard deviations;
+// the trapezoid rule converges very quickly for these smooth, gaussian-weighted integrands
+const PROBIT_RANGE: f64 = 8.;
+const PROBIT_STEP: f64 = 0.05;
+
+fn std_normal_cdf(x: f64) -> f64 {
+    0.5 * libm::erfc(-x / SQRT_2)
+}
+
+// probability that contestant i has the highest performance level + sigma * standard normal noise
+fn probit_q_i(i: usize, levels: &[f64], sigma: f64) -> f64 {
+    let n_steps = (2. * PROBIT_RANGE / PROBIT_STEP).round() as usize;
+    (0..=n_steps).map(|k| {
+        let z = -PROBIT_RANGE + k as f64 * PROBIT_STEP;
+        let density = (-0.5 * z * z).exp() / (2. * PI).sqrt();
+        levels.iter().enumerate()
+            .filter(|&(j, _)| j != i)
+            .fold(density, |acc, (_, &x_j)| acc * std_normal_cdf((levels[i] - x_j) / sigma + z))
+    }).sum::<f64>() * PROBIT_STEP
+}
+
+fn validate_probit(sigma: f64) -> Result<(), &'static str> {
+    if !(sigma.is_finite() && sigma > 0.) {
+        return Err("When creating new probit CSF: sigma must be positive");
+    }
+    Ok(())
+}
+
+// Lazear-Rosen style contest: the winner has the highest p_i + sigma * e_i, with independent
+// standard normal e_i; like LogitCSF, only differences in p matter
+// (jacobian by finite differences)
+#[derive(Clone, Debug)]
+pub struct ProbitCSF {
+    pub sigma: f64,
+}
+
+impl ProbitCSF {
+    pub fn new(sigma: f64) -> Result<Self, &'static str> {
+        validate_probit(sigma)?;
+        Ok(ProbitCSF { sigma })
+    }
+}
+
+impl CSF for ProbitCSF {
+    fn q_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        probit_q_i(i, &p.to_vec(), self.sigma)
+    }
+    fn q(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        let levels = p.to_vec();
+        Array::from_iter((0..p.len()).map(|i| probit_q_i(i, &levels, self.sigma)))
+    }
+}
+
+// as ProbitCSF, but against a null contestant with level -sigma * ln(scale);
+// no one wins if the null contestant does
+// (as in MaybeNoWinLogitCSF, whose null contestant is at -ln(scale) / k)
+#[derive(Clone, Debug)]
+pub struct MaybeNoWinProbitCSF {
+    pub sigma: f64,
+    pub scale: f64,
+}
+
+impl MaybeNoWinProbitCSF {
+    pub fn new(sigma: f64, scale: f64) -> Result<Self, &'static str> {
+        validate_probit(sigma)?;
+        validate_scale(scale)?;
+        Ok(MaybeNoWinProbitCSF { sigma, scale })
+    }
+
+    fn levels(&self, p: ArrayView<f64, Ix1>) -> Vec<f64> {
+        p.iter().copied().chain(std::iter::once(-self.sigma * self.scale.ln())).collect()
+    }
+}
+
+impl CSF for MaybeNoWinProbitCSF {
+    fn q_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        probit_q_i(i, &self.levels(p), self.sigma)
+    }
+    fn q(&self, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        let levels = self.levels(p);
+        Array::from_iter((0..p.len()).map(|i| probit_q_i(i, &levels, self.sigma)))
+    }
+}
+
+impl Params for TullockCSF {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "r" if index.is_none() => Ok(&mut self.r),
+            "w" => index_mut(&mut self.w, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_tullock(self.r, &self.w)?;
+        Ok(())
+    }
+}
+
+impl Params for MaybeNoWinTullockCSF {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "r" if index.is_none() => Ok(&mut self.r),
+            "w" => index_mut(&mut self.w, index, name),
+            "scale" if index.is_none() => Ok(&mut self.scale),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_tullock(self.r, &self.w)?;
+        validate_scale(self.scale)?;
+        Ok(())
+    }
+}
+
+impl Params for LogitCSF {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "k" if index.is_none() => Ok(&mut self.k),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_logit(self.k)?;
+        Ok(())
+    }
+}
+
+impl Params for MaybeNoWinLogitCSF {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "k" if index.is_none() => Ok(&mut self.k),
+            "scale" if index.is_none() => Ok(&mut self.scale),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_logit(self.k)?;
+        validate_scale(self.scale)?;
+        Ok(())
+    }
+}
+
+impl Params for ProbitCSF {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "sigma" if index.is_none() => Ok(&mut self.sigma),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_probit(self.sigma)?;
+        Ok(())
+    }
+}
+
+impl Params for MaybeNoWinProbitCSF {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "sigma" if index.is_none() => Ok(&mut self.sigma),
+            "scale" if index.is_none() => Ok(&mut self.scale),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_probit(self.sigma)?;
+        validate_scale(self.scale)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{assert_jacobian_matches_fd, isapprox_iters};
+
+    #[test]
+    fn dq_matches_finite_differences() {
+        let p = Array::from_vec(vec![1.5, 0.4, 2.2]);
+        let w = Array::from_vec(vec![1., 0.8, 1.3]);
+        let csf = MaybeNoWinCSF::new(0.7);
+        assert_jacobian_matches_fd("MaybeNoWinCSF", &csf.dq(p.view()), |p_| csf.q(p_), p.view());
+        let csf = TullockCSF::new(0.6, w.clone()).unwrap();
+        assert_jacobian_matches_fd("TullockCSF", &csf.dq(p.view()), |p_| csf.q(p_), p.view());
+        let csf = MaybeNoWinTullockCSF::new(1.8, w, 0.5).unwrap();
+        assert_jacobian_matches_fd("MaybeNoWinTullockCSF", &csf.dq(p.view()), |p_| csf.q(p_), p.view());
+        let csf = LogitCSF::new(1.3).unwrap();
+        assert_jacobian_matches_fd("LogitCSF", &csf.dq(p.view()), |p_| csf.q(p_), p.view());
+        let csf = MaybeNoWinLogitCSF::new(0.9, 0.2).unwrap();
+        assert_jacobian_matches_fd("MaybeNoWinLogitCSF", &csf.dq(p.view()), |p_| csf.q(p_), p.view());
+    }
+
+    #[test]
+    fn tullock_with_r_1_matches_baseline() {
+        let p = Array::from_vec(vec![1.5, 0.4, 2.2]);
+        let tullock = TullockCSF::unbiased(3, 1.).unwrap();
+        assert!(isapprox_iters(tullock.q(p.view()).into_iter(), DefaultCSF.q(p.view()).into_iter(), 1e-12, 1e-15));
+        assert!(isapprox_iters(tullock.dq(p.view()).into_iter(), DefaultCSF.dq(p.view()).into_iter(), 1e-12, 1e-15));
+        let tullock = MaybeNoWinTullockCSF::unbiased(3, 1., 0.5).unwrap();
+        let baseline = MaybeNoWinCSF::new(0.5);
+        assert!(isapprox_iters(tullock.q(p.view()).into_iter(), baseline.q(p.view()).into_iter(), 1e-12, 1e-15));
+        assert!(isapprox_iters(tullock.dq(p.view()).into_iter(), baseline.dq(p.view()).into_iter(), 1e-12, 1e-15));
+    }
+
+    #[test]
+    fn probit_matches_closed_form_for_two_players() {
+        // with two players, player 0 wins with probability Phi((p_0 - p_1) / (sigma sqrt(2)))
+        let sigma = 0.8;
+        let csf = ProbitCSF::new(sigma).unwrap();
+        let p = Array::from_vec(vec![1.5, 0.4]);
+        let z = (p[0] - p[1]) / (sigma * SQRT_2);
+        let q_0 = std_normal_cdf(z);
+        let dq_0 = (-0.5 * z * z).exp() / (2. * PI).sqrt() / (sigma * SQRT_2);
+        let expected_dq = Array::from_shape_vec((2, 2), vec![dq_0, -dq_0, -dq_0, dq_0]).unwrap();
+        assert!(isapprox_iters(csf.q(p.view()).into_iter(), [q_0, 1. - q_0].into_iter(), 1e-9, 1e-12));
+        assert!(isapprox_iters(csf.dq(p.view()).into_iter(), expected_dq.into_iter(), 1e-6, 1e-9));
+
+        // the null contestant takes some of the probability of winning
+        let csf = MaybeNoWinProbitCSF::new(sigma, 0.5).unwrap();
+        let q = csf.q(p.view());
+        assert!(q.iter().all(|&q_i| q_i > 0.) && q.sum() < 1.);
+        assert_jacobian_matches_fd("MaybeNoWinProbitCSF", &csf.dq(p.view()), |p_| csf.q(p_), p.view());
+    }
+
+    #[test]
+    fn set_param_rejects_invalid_values() {
+        let mut csf = MaybeNoWinTullockCSF::unbiased(2, 1., 0.5).unwrap();
+        assert!(csf.set_param("r", 0.).is_err());
+        assert!(csf.set_param("w[1]", -1.).is_err());
+        assert!(csf.set_param("scale", f64::NAN).is_err());
+        // a rejected value leaves the old one in place
+        assert_eq!((csf.r, csf.w[1], csf.scale), (1., 1., 0.5));
+        csf.set_param("r", 2.).unwrap();
+        assert_eq!(csf.r, 2.);
+        assert!(LogitCSF::new(1.).unwrap().with_param("k", -1.).is_err());
+        assert!(ProbitCSF::new(1.).unwrap().with_param("sigma", 0.).is_err());
+    }
 }
@@ -1,24 +1,47 @@
-use numpy::ndarray::{Array, ArrayView, Ix1};
+use numpy::ndarray::{Array, ArrayView, Ix1, Ix2};
+
+use crate::params::{Params, index_mut, parse_index, unknown_param};
+use crate::utils::fd_jacobian;
 
 pub trait DisasterCost: Clone + Send + Sync {
-    fn d_i(&self, i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> f64;
-    fn d(&self, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
-        Array::from_iter((0..s.len()).map(|i| self.d_i(i, s, p)))
+    // cost to player i of a disaster when player j wins the contest
+    fn d_ij(&self, i: usize, j: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> f64;
+    // costs to player i for each possible winner
+    fn d_i(&self, i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        Array::from_iter((0..s.len()).map(|j| self.d_ij(i, j, s, p)))
+    }
+    // costs to every player, indexed as [i, j] = d_ij
+    fn d(&self, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        let n = s.len();
+        Array::from_shape_fn((n, n), |(i, j)| self.d_ij(i, j, s, p))
+    }
+
+    // jacobians of d_i w.r.t. s and p, indexed as [j, k] = d d_ij / d s_k
+    // defaults to finite differences; override when an analytic form is available
+    fn dd_i(&self, i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        (
+            fd_jacobian(|s_| self.d_i(i, s_, p), s),
+            fd_jacobian(|p_| self.d_i(i, s, p_), p),
+        )
     }
 
     fn n(&self) -> usize;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct ConstantDisasterCost {
     pub d: Array<f64, Ix1>,
 }
 
 impl DisasterCost for ConstantDisasterCost {
-    fn d_i(&self, i: usize, _s: ArrayView<f64, Ix1>, _p: ArrayView<f64, Ix1>) -> f64 {
+    fn d_ij(&self, i: usize, _j: usize, _s: ArrayView<f64, Ix1>, _p: ArrayView<f64, Ix1>) -> f64 {
         self.d[i]
     }
 
+    fn dd_i(&self, _i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        (Array::zeros((s.len(), s.len())), Array::zeros((p.len(), p.len())))
+    }
+
     fn n(&self) -> usize {
         self.d.len()
     }
@@ -31,3 +54,161 @@ impl ConstantDisasterCost {
         }
     }
 }
+
+fn validate_kappa(kappa: f64) -> Result<(), &'static str> {
+    if !(kappa.is_finite() && kappa >= 0.) {
+        return Err("When creating new CapabilityDisasterCost: kappa must be non-negative");
+    }
+    Ok(())
+}
+
+// a more capable winner causes a worse disaster: d_ij = d0_i + d1_i p_j^kappa
+#[derive(Clone, Debug)]
+pub struct CapabilityDisasterCost {
+    pub d0: Array<f64, Ix1>,
+    pub d1: Array<f64, Ix1>,
+    pub kappa: f64,
+}
+
+impl CapabilityDisasterCost {
+    pub fn new(d0: Array<f64, Ix1>, d1: Array<f64, Ix1>, kappa: f64) -> Result<Self, &'static str> {
+        if d0.len() != d1.len() {
+            return Err("When creating new CapabilityDisasterCost: d0 and d1 must have the same length");
+        }
+        validate_kappa(kappa)?;
+        Ok(CapabilityDisasterCost { d0, d1, kappa })
+    }
+}
+
+impl DisasterCost for CapabilityDisasterCost {
+    fn d_ij(&self, i: usize, j: usize, _s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> f64 {
+        self.d0[i] + self.d1[i] * p[j].powf(self.kappa)
+    }
+
+    fn dd_i(&self, i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        // with kappa = 0 the cost doesn't depend on p, even at p = 0 where p^(kappa - 1) is infinite
+        let kappa = self.kappa;
+        let dp = Array::from_diag(&p.mapv(|x| if kappa == 0. { 0. } else { self.d1[i] * kappa * x.powf(kappa - 1.) }));
+        (Array::zeros((s.len(), s.len())), dp)
+    }
+
+    fn n(&self) -> usize {
+        self.d0.len()
+    }
+}
+
+// fixed cost d[i, j] to player i of a disaster caused by player j winning
+#[derive(Clone, Debug)]
+pub struct PairwiseDisasterCost {
+    pub d: Array<f64, Ix2>,
+}
+
+impl PairwiseDisasterCost {
+    pub fn new(d: Array<f64, Ix2>) -> Result<Self, &'static str> {
+        if d.nrows() != d.ncols() {
+            return Err("When creating new PairwiseDisasterCost: d must be a square matrix");
+        }
+        Ok(PairwiseDisasterCost { d })
+    }
+}
+
+impl DisasterCost for PairwiseDisasterCost {
+    fn d_ij(&self, i: usize, j: usize, _s: ArrayView<f64, Ix1>, _p: ArrayView<f64, Ix1>) -> f64 {
+        self.d[[i, j]]
+    }
+
+    fn d_i(&self, i: usize, _s: ArrayView<f64, Ix1>, _p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        self.d.row(i).to_owned()
+    }
+
+    fn d(&self, _s: ArrayView<f64, Ix1>, _p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        self.d.clone()
+    }
+
+    fn dd_i(&self, _i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        (Array::zeros((s.len(), s.len())), Array::zeros((p.len(), p.len())))
+    }
+
+    fn n(&self) -> usize {
+        self.d.nrows()
+    }
+}
+
+impl Params for ConstantDisasterCost {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "d" => index_mut(&mut self.d, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+impl Params for CapabilityDisasterCost {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "d0" => index_mut(&mut self.d0, index, name),
+            "d1" => index_mut(&mut self.d1, index, name),
+            "kappa" if index.is_none() => Ok(&mut self.kappa),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_kappa(self.kappa)?;
+        Ok(())
+    }
+}
+
+// parameter names only take a single index, so the matrix entries aren't exposed
+impl Params for PairwiseDisasterCost {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        Err(unknown_param(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::assert_jacobian_matches_fd;
+
+    #[test]
+    fn dd_i_matches_finite_differences() {
+        let s = Array::from_vec(vec![0.8, 1.7, 0.3]);
+        let p = Array::from_vec(vec![1.5, 0.4, 2.2]);
+        let capability = CapabilityDisasterCost::new(
+            Array::from_vec(vec![1., 0.5, 2.]), Array::from_vec(vec![0.3, 1., 0.2]), 1.4
+        ).unwrap();
+        let pairwise = PairwiseDisasterCost::new(
+            Array::from_shape_fn((3, 3), |(i, j)| 1. + i as f64 + 0.5 * j as f64)
+        ).unwrap();
+        for i in 0..3 {
+            let (ds, dp) = capability.dd_i(i, s.view(), p.view());
+            assert_jacobian_matches_fd("CapabilityDisasterCost ds", &ds, |s_| capability.d_i(i, s_, p.view()), s.view());
+            assert_jacobian_matches_fd("CapabilityDisasterCost dp", &dp, |p_| capability.d_i(i, s.view(), p_), p.view());
+            let (ds, dp) = pairwise.dd_i(i, s.view(), p.view());
+            assert_jacobian_matches_fd("PairwiseDisasterCost ds", &ds, |s_| pairwise.d_i(i, s_, p.view()), s.view());
+            assert_jacobian_matches_fd("PairwiseDisasterCost dp", &dp, |p_| pairwise.d_i(i, s.view(), p_), p.view());
+        }
+    }
+
+    #[test]
+    fn set_param_rejects_invalid_kappa() {
+        let mut cost = CapabilityDisasterCost::new(Array::ones(2), Array::ones(2), 1.5).unwrap();
+        assert!(cost.set_param("kappa", -1.).is_err());
+        assert!(cost.set_param("kappa", f64::NAN).is_err());
+        assert_eq!(cost.kappa, 1.5);
+        cost.set_param("kappa", 0.).unwrap();
+        assert_eq!(cost.kappa, 0.);
+    }
+
+    #[test]
+    fn dd_i_finite_at_zero_capability() {
+        let s = Array::from_vec(vec![0.8, 1.7]);
+        let p = Array::from_vec(vec![0., 0.4]);
+        let cost = CapabilityDisasterCost::new(Array::ones(2), Array::ones(2), 0.).unwrap();
+        let (ds, dp) = cost.dd_i(0, s.view(), p.view());
+        assert!(ds.iter().chain(dp.iter()).all(|&x| x == 0.));
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use numpy::ndarray::{Array, ArrayD, Dimension, Ix1, Ix2, IxDyn, indices, s};
+use argmin::core::TerminationReason;
+use ndarray_rand::rand::{SeedableRng, rngs::StdRng};
+use rayon::prelude::*;
+
+use crate::constraints::{LinearConstraint, project};
+use crate::solve::{BestResponseMethod, INIT_MU, respond};
+use crate::states::PayoffAggregator;
+use crate::strategies::*;
+use crate::utils::sub_seed;
+
+#[derive(Clone, Debug)]
+pub struct BestResponseOptions {
+    pub method: BestResponseMethod,
+    pub constraints: Vec<LinearConstraint>,
+    // number of searches, run in parallel; the first starts from the player's current strategy,
+    // the rest from random draws
+    pub n_starts: usize,
+    pub init_mu: f64,
+    pub init_sigma: f64,
+    pub seed: u64,
+}
+
+impl Default for BestResponseOptions {
+    fn default() -> Self {
+        BestResponseOptions {
+            method: BestResponseMethod::default(),
+            constraints: Vec::new(),
+            n_starts: 1,
+            init_mu: INIT_MU,
+            init_sigma: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BestResponseResult<S: StrategyType> {
+    // the player's best strategy found, as a t x nparams array
+    pub response: Array<f64, Ix2>,
+    // the profile with the response swapped in
+    pub strategies: S,
+    pub payoff: f64,
+    // improvement over the player's current strategy
+    pub gain: f64,
+    pub termination_reason: TerminationReason,
+}
+
+impl<S: StrategyType + fmt::Display> fmt::Display for BestResponseResult<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Best response stopped: {}", self.termination_reason.text())?;
+        writeln!(f, "payoff = {:.4}, gain = {:.4}", self.payoff, self.gain)?;
+        write!(f, "{}", self.strategies)
+    }
+}
+
+// player i's best response to everyone else playing as in strategies
+pub fn best_response<A, S, T>(
+    agg: &T, i: usize, strategies: &S, options: &BestResponseOptions
+) -> Result<BestResponseResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    if i >= strategies.n() {
+        return Err(argmin::core::Error::msg(format!("Player {} out of range for {} players", i, strategies.n())));
+    }
+    if options.n_starts == 0 {
+        return Err(argmin::core::Error::msg("n_starts must be at least 1"));
+    }
+    // draw all starts up front so they don't depend on how the searches are scheduled
+    let starts = (0..options.n_starts).map(|k| {
+        let mut start = strategies.clone();
+        if k != 0 {
+            let mut rng = StdRng::seed_from_u64(sub_seed(options.seed, k));
+            let draw = S::random_using(strategies.t(), 1, options.init_mu, options.init_sigma, &mut rng)
+                .map_err(argmin::core::Error::msg)?;
+            let draw = project(draw.data().slice(s![.., 0, ..]), i, &options.constraints)
+                .map_err(argmin::core::Error::msg)?;
+            start.data_mut().slice_mut(s![.., i, ..]).assign(&draw);
+        }
+        Ok(start)
+    }).collect::<Result<Vec<_>, argmin::core::Error>>()?;
+    let responses = starts.into_par_iter().map(|start| {
+        let (response, termination_reason) = respond(i, &start, agg, &options.method, &options.constraints)?;
+        let mut deviation = start;
+        deviation.data_mut().slice_mut(s![.., i, ..]).assign(&response);
+        let payoff = agg.u_i(i, &deviation);
+        Ok((response, deviation, payoff, termination_reason))
+    }).collect::<Result<Vec<_>, argmin::core::Error>>()?;
+
+    let (response, strategies_, payoff, termination_reason) = responses.into_iter()
+        .max_by(|a, b| a.2.total_cmp(&b.2))
+        .unwrap();
+    Ok(BestResponseResult {
+        gain: payoff - agg.u_i(i, strategies),
+        response,
+        strategies: strategies_,
+        payoff,
+        termination_reason,
+    })
+}
+
+// one dimension of a payoff grid: the values taken by param m of the player's strategy,
+// either in period t or, if t is None, in every period at once
+#[derive(Clone, Debug)]
+pub struct ParamAxis {
+    pub t: Option<usize>,
+    pub m: usize,
+    pub values: Array<f64, Ix1>,
+}
+
+impl ParamAxis {
+    pub fn new(t: Option<usize>, m: usize, values: Array<f64, Ix1>) -> Self {
+        ParamAxis { t, m, values }
+    }
+}
+
+// player i's payoff at every combination of the axis values, with the rest of the profile as in strategies;
+// entry [k0, k1, ...] has the first axis at its k0-th value, the second at its k1-th, and so on
+pub fn payoff_grid<A, S, T>(
+    agg: &T, i: usize, strategies: &S, param_axes: &[ParamAxis]
+) -> Result<ArrayD<f64>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    if i >= strategies.n() {
+        return Err(argmin::core::Error::msg(format!("Player {} out of range for {} players", i, strategies.n())));
+    }
+    for axis in param_axes.iter() {
+        if axis.m >= S::nparams() || axis.t.is_some_and(|t| t >= strategies.t()) {
+            return Err(argmin::core::Error::msg(format!(
+                "Axis for param {} in period {:?} is out of range for strategies with {} params and {} periods",
+                axis.m, axis.t, S::nparams(), strategies.t()
+            )));
+        }
+    }
+    let shape = param_axes.iter().map(|axis| axis.values.len()).collect::<Vec<_>>();
+    let base = strategies.data().slice(s![.., i, ..]).to_owned();
+    let indices = indices(IxDyn(&shape)).into_iter().collect::<Vec<_>>();
+    let values = indices.par_iter().map_init(
+        || (agg.evaluator_i(i, strategies), base.clone()),
+        |(evaluator, x), index| {
+            for (axis, &k) in param_axes.iter().zip(index.slice().iter()) {
+                match axis.t {
+                    Some(t) => x[[t, axis.m]] = axis.values[k],
+                    None => x.column_mut(axis.m).fill(axis.values[k]),
+                }
+            }
+            evaluator.u_i(x.view())
+        },
+    ).collect::<Vec<_>>();
+    Ok(Array::from_shape_vec(IxDyn(&shape), values)?)
+}
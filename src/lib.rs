@@ -1,6 +1,8 @@
 use::pyo3::prelude::*;
 
 pub mod utils;
+pub mod params;
+pub mod constraints;
 
 pub mod strategies;
 
@@ -14,6 +16,15 @@ pub mod risk_func;
 pub mod states;
 
 pub mod solve;
+pub mod multistart;
+pub mod markov;
+pub mod stackelberg;
+pub mod planner;
+pub mod sensitivity;
+pub mod newton;
+pub mod stationary;
+pub mod landscape;
+pub mod continuation;
 pub mod scenarios;
 
 pub mod pybindings;
@@ -25,8 +36,26 @@ fn dynapai(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyStrategies>()?;
     m.add_class::<PyDefaultProd>()?;
     m.add_class::<PyLinearReward>()?;
+    m.add_class::<PyRewardFunc>()?;
+    m.add_class::<PyCSF>()?;
     m.add_class::<PyDefaultPayoff>()?;
+    m.add_class::<PyLinearConstraint>()?;
     m.add_class::<PySolverOptions>()?;
+    m.add_class::<PySolveResult>()?;
+    m.add_class::<PyEquilibrium>()?;
+    m.add_class::<PyMultiStartResult>()?;
+    m.add_class::<PyEquilibriumCheck>()?;
+    m.add_class::<PyMarkovResult>()?;
+    m.add_class::<PyStackelbergResult>()?;
+    m.add_class::<PyPlannerResult>()?;
+    m.add_class::<PyOutcomeSummary>()?;
+    m.add_class::<PyPlannerReport>()?;
+    m.add_class::<PyNewtonResult>()?;
+    m.add_class::<PyStationaryResult>()?;
+    m.add_class::<PyHorizonResult>()?;
+    m.add_class::<PyBestResponseResult>()?;
+    m.add_class::<PySensitivity>()?;
+    m.add_class::<PyContinuationResult>()?;
     m.add_class::<PyExponentialDiscounter>()?;
     m.add_class::<PyInvestActions>()?;
     m.add_class::<PyInvestStrategies>()?;
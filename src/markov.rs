@@ -0,0 +1,306 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use numpy::ndarray::{Array, ArrayView, Axis, Ix1, Ix2, Ix3, Ix4, s};
+use rayon::prelude::*;
+
+use crate::payoff_func::PayoffFunc;
+use crate::solve::{InitGuess, SolverOptions, solve};
+use crate::states::{Discounter, PayoffAggregator, State, StateIterator, terminal_u_i};
+use crate::strategies::*;
+use crate::utils::solve_linear;
+
+// regularization for the policy regression, so that coefficients on
+// state variables that don't vary across samples are set to zero
+const RIDGE: f64 = 1e-10;
+
+// feedback (Markov) strategies: in period t, each player's log action params are
+// linear in the log of the current state variables (see FeedbackState), i.e.
+// ln x_im = coefs[t, i, m, 0] + sum_k coefs[t, i, m, k + 1] * ln features_k
+#[derive(Clone, Debug)]
+pub struct FeedbackStrategies<S: StrategyType> {
+    coefs: Array<f64, Ix4>,
+    _phantom: PhantomData<S>,
+}
+
+impl<S: StrategyType> FeedbackStrategies<S> {
+    pub fn from_array(coefs: Array<f64, Ix4>) -> Result<Self, String> {
+        if coefs.shape()[2] != S::nparams() {
+            return Err(format!("Coefs for FeedbackStrategies should have {} params but got {}", S::nparams(), coefs.shape()[2]));
+        }
+        if coefs.shape()[3] == 0 {
+            return Err("Coefs for FeedbackStrategies must include an intercept".to_string());
+        }
+        Ok(FeedbackStrategies { coefs, _phantom: PhantomData })
+    }
+
+    // policies that ignore the state and play the given open-loop strategies
+    pub fn from_open_loop(strategies: &S, nfeatures: usize) -> Self {
+        let data = strategies.data();
+        let mut coefs = Array::zeros((strategies.t(), strategies.n(), S::nparams(), nfeatures + 1));
+        coefs.slice_mut(s![.., .., .., 0]).assign(&data.mapv(f64::ln));
+        FeedbackStrategies { coefs, _phantom: PhantomData }
+    }
+
+    pub fn coefs(&self) -> ArrayView<'_, f64, Ix4> {
+        self.coefs.view()
+    }
+
+    pub fn t(&self) -> usize { self.coefs.shape()[0] }
+    pub fn n(&self) -> usize { self.coefs.shape()[1] }
+    pub fn nfeatures(&self) -> usize { self.coefs.shape()[3] - 1 }
+
+    // actions taken in period t when the state variables are given by features
+    pub fn actions(&self, t: usize, features: ArrayView<f64, Ix1>) -> S::Act {
+        let log_features = features.mapv(f64::ln);
+        let coefs = self.coefs.index_axis(Axis(0), t);
+        let data = Array::from_shape_fn((self.n(), S::nparams()), |(i, m)| {
+            coefs[[i, m, 0]] + coefs.slice(s![i, m, 1..]).dot(&log_features)
+        });
+        S::Act::from_array_unchecked(data.mapv(f64::exp))
+    }
+
+    // open-loop strategies realized when these policies are played from the aggregator's initial state
+    pub fn play<D>(&self, agg: &D) -> S
+    where D: StateIterator<S::Act, S>, D::StateType: FeedbackState
+    {
+        let (_, actions) = simulate(agg, self);
+        S::from_actions(actions).unwrap()
+    }
+}
+
+// states visited and actions taken in each period when the policies are played from state0
+fn simulate<A, S, D>(agg: &D, policies: &FeedbackStrategies<S>) -> (Vec<D::StateType>, Vec<A>)
+where A: ActionType, S: StrategyType<Act = A>, D: StateIterator<A, S>, D::StateType: FeedbackState
+{
+    let mut state = agg.state0().clone();
+    let mut states = Vec::with_capacity(policies.t());
+    let mut actions_seq = Vec::with_capacity(policies.t());
+    for t in 0..policies.t() {
+        let actions = policies.actions(t, state.features().view());
+        states.push(state.clone());
+        if t != policies.t() - 1 {
+            agg.advance_state(&mut state, &actions);
+        }
+        actions_seq.push(actions);
+    }
+    (states, actions_seq)
+}
+
+// player i's discounted payoff from period t0 onwards, starting in the given state
+fn continuation_i<A, S, D>(agg: &D, policies: &FeedbackStrategies<S>, i: usize, t0: usize, mut state: D::StateType) -> f64
+where A: ActionType, S: StrategyType<Act = A>, D: StateIterator<A, S> + Discounter, D::StateType: FeedbackState
+{
+    let gamma = agg.gammas()[i];
+    let mut u = 0.;
+    for t in t0..policies.t() {
+        let actions = policies.actions(t, state.features().view());
+        u += gamma.powi((t - t0) as i32) * state.belief(i).u_i(i, &actions);
+        if t != policies.t() - 1 {
+            agg.advance_state(&mut state, &actions);
+        } else {
+            u += terminal_u_i(agg, &state, i, &actions, gamma, t + 1 - t0);
+        }
+    }
+    u
+}
+
+// the one-period game played in period t from a given state, where payoffs include
+// the continuation values from following the policies in later periods;
+// strategies for this game have t = 1
+struct StageGame<'a, A, S, D>
+where A: ActionType, S: StrategyType<Act = A>, D: StateIterator<A, S> + Discounter, D::StateType: FeedbackState
+{
+    agg: &'a D,
+    policies: &'a FeedbackStrategies<S>,
+    state: D::StateType,
+    t: usize,
+    _phantom: PhantomData<A>,
+}
+
+impl<A, S, D> PayoffAggregator<A, S> for StageGame<'_, A, S, D>
+where A: ActionType, S: StrategyType<Act = A>, D: StateIterator<A, S> + Discounter, D::StateType: FeedbackState
+{
+    fn n(&self) -> usize {
+        self.state.n()
+    }
+    fn u_i(&self, i: usize, strategies: &S) -> f64 {
+        let actions = A::from_array_unchecked(strategies.data().index_axis(Axis(0), 0).to_owned());
+        let mut u = self.state.belief(i).u_i(i, &actions);
+        if self.t != self.policies.t() - 1 {
+            let mut next_state = self.state.clone();
+            self.agg.advance_state(&mut next_state, &actions);
+            u += self.agg.gammas()[i] * continuation_i(self.agg, self.policies, i, self.t + 1, next_state);
+        } else {
+            u += terminal_u_i(self.agg, &self.state, i, &actions, self.agg.gammas()[i], 1);
+        }
+        u
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MarkovOptions<S: StrategyType> {
+    // init_guess sets the horizon and the open-loop profile used as the initial (constant) policies;
+    // the remaining options are used when solving the stage game at each sampled state
+    pub solver_options: SolverOptions<S>,
+    // maximum number of policy iterations (each a full backward pass)
+    pub max_iters: u64,
+    // tolerance on the largest change in any policy coefficient
+    pub tol: f64,
+    // each log state variable is perturbed by +/- this much around the current path when fitting policies
+    pub sample_spread: f64,
+    // actions are clamped below at this value before taking logs, since corner solutions
+    // can end up at arbitrarily small values
+    pub action_floor: f64,
+}
+
+impl<S: StrategyType> MarkovOptions<S> {
+    pub fn new(t: usize) -> Self {
+        MarkovOptions {
+            solver_options: SolverOptions::random_init(t),
+            max_iters: 50,
+            tol: 1e-4,
+            sample_spread: 0.1,
+            action_floor: 1e-8,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MarkovResult<S: StrategyType> {
+    pub strategies: FeedbackStrategies<S>,
+    // open-loop strategies realized on the equilibrium path
+    pub path: S,
+    pub converged: bool,
+    pub iterations: u64,
+    // largest change in any policy coefficient on each iteration
+    pub max_changes: Vec<f64>,
+    // number of stage games on the last iteration where the solver did not converge
+    pub n_unconverged: usize,
+    pub payoffs: Array<f64, Ix1>,
+}
+
+impl<S: StrategyType + fmt::Display> fmt::Display for MarkovResult<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.converged {
+            writeln!(f, "Converged after {} iterations", self.iterations)?;
+        } else {
+            writeln!(f, "Did not converge after {} iterations", self.iterations)?;
+        }
+        if self.n_unconverged > 0 {
+            writeln!(f, "{} stage games did not converge", self.n_unconverged)?;
+        }
+        writeln!(f, "payoffs = {:.4}", self.payoffs)?;
+        write!(f, "{}", self.path)
+    }
+}
+
+// log state variables at which to solve the stage game: the center first, then each variable perturbed up and down
+fn sample_points(center: &Array<f64, Ix1>, spread: f64) -> Vec<Array<f64, Ix1>> {
+    let mut samples = vec![center.clone()];
+    for k in 0..center.len() {
+        for delta in [spread, -spread] {
+            let mut x = center.clone();
+            x[k] += delta;
+            samples.push(x);
+        }
+    }
+    samples
+}
+
+// fit of log actions (one n x nparams array per sample) on log state variables, where the first sample
+// is the center; the fit passes exactly through the center, with slopes found by least squares on the
+// remaining samples. returned as n x nparams x (1 + k) coefficients on [1, ln features]
+fn fit_policy(
+    log_features: &[Array<f64, Ix1>], log_actions: &[Array<f64, Ix2>]
+) -> Result<Array<f64, Ix3>, argmin::core::Error> {
+    let center = &log_features[0];
+    let k = center.len();
+    let x = Array::from_shape_fn((log_features.len() - 1, k), |(j, l)| {
+        log_features[j + 1][l] - center[l]
+    });
+    let xtx = x.t().dot(&x) + Array::<f64, Ix2>::eye(k) * RIDGE;
+    let (n, nparams) = log_actions[0].dim();
+    let mut coefs = Array::zeros((n, nparams, k + 1));
+    for i in 0..n {
+        for m in 0..nparams {
+            let y0 = log_actions[0][[i, m]];
+            let y = Array::from_iter(log_actions[1..].iter().map(|a| a[[i, m]] - y0));
+            let slopes = solve_linear(xtx.view(), x.t().dot(&y).view())
+                .ok_or_else(|| argmin::core::Error::msg("Singular system when fitting feedback policy"))?;
+            coefs[[i, m, 0]] = y0 - slopes.dot(center);
+            coefs.slice_mut(s![i, m, 1..]).assign(&slopes);
+        }
+    }
+    Ok(coefs)
+}
+
+// finds a Markov perfect equilibrium by policy iteration: each iteration is a backward pass in which,
+// for each period, the stage game is solved at states sampled around the current path
+// (given the policies already found for later periods), and log-linear policies are fit to the results
+pub fn solve_markov<A, S, D>(agg: &D, options: &MarkovOptions<S>) -> Result<MarkovResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, D: StateIterator<A, S> + Discounter, D::StateType: FeedbackState
+{
+    let init_guess = options.solver_options.init_guess.to_fixed(agg.n())?;
+    let nfeatures = agg.state0().features().len();
+    let mut policies = FeedbackStrategies::from_open_loop(&init_guess, nfeatures);
+    let mut max_changes = Vec::new();
+    let mut n_unconverged = 0;
+    let mut converged = false;
+    for _ in 0..options.max_iters {
+        let (states, _) = simulate(agg, &policies);
+        let mut new_policies = policies.clone();
+        n_unconverged = 0;
+        for t in (0..policies.t()).rev() {
+            let center = states[t].features().mapv(f64::ln);
+            // the initial state can't be affected by anyone, so there's nothing to respond to
+            let samples = if t == 0 {
+                vec![center.clone()]
+            } else {
+                sample_points(&center, options.sample_spread)
+            };
+            let results = samples.par_iter().map(|x| {
+                let features = x.mapv(f64::exp);
+                let stage = StageGame {
+                    agg,
+                    policies: &new_policies,
+                    state: states[t].with_features(features.view()),
+                    t,
+                    _phantom: PhantomData,
+                };
+                let init_guess = S::from_actions(vec![new_policies.actions(t, features.view())])
+                    .map_err(argmin::core::Error::msg)?;
+                let solver_options = SolverOptions {
+                    init_guess: InitGuess::Fixed(init_guess),
+                    trace: false,
+                    ..options.solver_options.clone()
+                };
+                solve(&stage, &solver_options)
+            }).collect::<Result<Vec<_>, _>>()?;
+            n_unconverged += results.iter().filter(|res| !res.converged).count();
+            let log_actions = results.iter().map(|res| {
+                res.strategies.data().index_axis(Axis(0), 0).mapv(|x| x.max(options.action_floor).ln())
+            }).collect::<Vec<_>>();
+            let coefs = fit_policy(&samples, &log_actions)?;
+            new_policies.coefs.index_axis_mut(Axis(0), t).assign(&coefs);
+        }
+        let change = new_policies.coefs.iter().zip(policies.coefs.iter())
+            .fold(0., |acc: f64, (x, y)| acc.max((x - y).abs()));
+        max_changes.push(change);
+        policies = new_policies;
+        if change < options.tol {
+            converged = true;
+            break;
+        }
+    }
+    let path = policies.play(agg);
+    Ok(MarkovResult {
+        payoffs: agg.u(&path),
+        strategies: policies,
+        path,
+        converged,
+        iterations: max_changes.len() as u64,
+        max_changes,
+        n_unconverged,
+    })
+}
@@ -0,0 +1,133 @@
+use std::cmp::Reverse;
+
+use numpy::ndarray::{Array, Ix1, s};
+use ndarray_rand::rand::{SeedableRng, rngs::StdRng};
+use rayon::prelude::*;
+
+use crate::solve::{InitGuess, SolveResult, SolverOptions, solve, INIT_MU};
+use crate::states::PayoffAggregator;
+use crate::strategies::*;
+use crate::utils::sub_seed;
+
+#[derive(Clone, Debug)]
+pub struct MultiStartOptions<S: StrategyType> {
+    // options for each individual solve; init_guess is replaced by the random starts,
+    // except that fixed players keep their strategies from it, so it must then be InitGuess::Fixed
+    pub solver_options: SolverOptions<S>,
+    pub n_starts: usize,
+    pub seed: u64,
+    // starting points are drawn from a lognormal distribution with these parameters
+    pub init_mu: f64,
+    pub init_sigma: f64,
+    // profiles whose log values all differ by less than this are treated as the same equilibrium
+    pub cluster_tol: f64,
+    // values below this are treated as equal when clustering, since corner solutions
+    // can end up at arbitrarily small values
+    pub cluster_floor: f64,
+}
+
+impl<S: StrategyType> MultiStartOptions<S> {
+    pub fn new(t: usize, n_starts: usize, seed: u64) -> Self {
+        MultiStartOptions {
+            solver_options: SolverOptions::random_init(t),
+            n_starts,
+            seed,
+            init_mu: INIT_MU,
+            init_sigma: 1.0,
+            cluster_tol: 1e-3,
+            cluster_floor: 1e-8,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Equilibrium<S: StrategyType> {
+    pub strategies: S,
+    pub payoffs: Array<f64, Ix1>,
+    // number of starting points that converged to this equilibrium
+    pub basin_count: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct MultiStartResult<S: StrategyType> {
+    // distinct equilibria found, ordered by basin count (largest first)
+    pub equilibria: Vec<Equilibrium<S>>,
+    // number of starting points from which the solver did not converge
+    pub n_unconverged: usize,
+    // individual results, in the same order as the starting points
+    pub results: Vec<SolveResult<S>>,
+}
+
+impl<S: StrategyType> MultiStartResult<S> {
+    // whether every converged start found the same equilibrium
+    pub fn is_unique(&self) -> bool {
+        self.equilibria.len() == 1
+    }
+}
+
+// largest absolute difference between any pair of log strategy values, with values clamped below at floor
+pub(crate) fn log_distance<S: StrategyType>(a: &S, b: &S, floor: f64) -> f64 {
+    a.data().iter().zip(b.data().iter()).fold(0., |acc, (x, y)| {
+        f64::max(acc, (x.max(floor).ln() - y.max(floor).ln()).abs())
+    })
+}
+
+pub fn solve_multistart<A, S, T>(agg: &T, options: &MultiStartOptions<S>) -> Result<MultiStartResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    let t = options.solver_options.init_guess.t();
+    let fixed_players = &options.solver_options.fixed_players;
+    let fixed_init = match &options.solver_options.init_guess {
+        _ if fixed_players.is_empty() => None,
+        InitGuess::Fixed(x) if x.n() == agg.n() => Some(x),
+        InitGuess::Fixed(_) => return Err(argmin::core::Error::msg(
+            "Initial guess for multi-start with fixed players must have a strategy for every player"
+        )),
+        InitGuess::Random { .. } => return Err(argmin::core::Error::msg(
+            "Multi-start with fixed players needs a fixed initial guess to take their strategies from"
+        )),
+    };
+    if let Some(&i) = fixed_players.iter().find(|&&i| i >= agg.n()) {
+        return Err(argmin::core::Error::msg(format!("Fixed player {} out of range for {} players", i, agg.n())));
+    }
+    // draw all starts up front so they don't depend on how the solves are scheduled
+    let starts = (0..options.n_starts).map(|k| {
+        let mut rng = StdRng::seed_from_u64(sub_seed(options.seed, k));
+        let mut start = S::random_using(t, agg.n(), options.init_mu, options.init_sigma, &mut rng)
+            .map_err(argmin::core::Error::msg)?;
+        if let Some(init) = fixed_init {
+            for &i in fixed_players.iter() {
+                start.data_mut().slice_mut(s![.., i, ..]).assign(&init.data().slice(s![.., i, ..]));
+            }
+        }
+        Ok(start)
+    }).collect::<Result<Vec<_>, argmin::core::Error>>()?;
+    let results = starts.into_par_iter().map(|start| {
+        let solver_options = SolverOptions {
+            init_guess: InitGuess::Fixed(start),
+            ..options.solver_options.clone()
+        };
+        solve(agg, &solver_options)
+    }).collect::<Result<Vec<_>, _>>()?;
+
+    let mut equilibria: Vec<Equilibrium<S>> = Vec::new();
+    let mut n_unconverged = 0;
+    for res in results.iter() {
+        if !res.converged {
+            n_unconverged += 1;
+            continue;
+        }
+        match equilibria.iter_mut().find(|eq| {
+            log_distance(&eq.strategies, &res.strategies, options.cluster_floor) < options.cluster_tol
+        }) {
+            Some(eq) => eq.basin_count += 1,
+            None => equilibria.push(Equilibrium {
+                strategies: res.strategies.clone(),
+                payoffs: res.payoffs.clone(),
+                basin_count: 1,
+            }),
+        }
+    }
+    equilibria.sort_by_key(|eq| Reverse(eq.basin_count));
+    Ok(MultiStartResult { equilibria, n_unconverged, results })
+}
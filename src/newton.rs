@@ -0,0 +1,212 @@
+use std::fmt;
+
+use numpy::ndarray::{Array, Axis, Ix1, Ix2};
+
+use crate::sensitivity::{stacked_foc, strategies_from_flat};
+use crate::solve::InitGuess;
+use crate::states::PayoffAggregator;
+use crate::strategies::*;
+use crate::utils::{fd_jacobian, is_negative_definite, solve_linear};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewtonJacobian {
+    // recompute the jacobian by finite differences on every iteration
+    FiniteDifferences,
+    // compute it by finite differences once, then apply rank-one (Broyden) updates,
+    // recomputing it only when a step fails
+    Broyden,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewtonOptions<S: StrategyType> {
+    pub init_guess: InitGuess<S>,
+    pub max_iters: u64,
+    // converged once every first-order condition (in log space) is below this in absolute value
+    pub tol: f64,
+    // the step is halved until the residual decreases; the solve stops if it gets smaller than this
+    pub min_step: f64,
+    // largest change in any log strategy value in a single step
+    pub max_step: f64,
+    pub jacobian: NewtonJacobian,
+    // players whose strategies stay at the initial guess; their first-order conditions are left out
+    pub fixed_players: Vec<usize>,
+}
+
+impl<S: StrategyType> NewtonOptions<S> {
+    pub fn from_init_guess(init_guess: S) -> Self {
+        NewtonOptions {
+            init_guess: InitGuess::Fixed(init_guess),
+            max_iters: 100,
+            tol: 1e-6,
+            min_step: 1e-4,
+            max_step: 1.,
+            jacobian: NewtonJacobian::FiniteDifferences,
+            fixed_players: Vec::new(),
+        }
+    }
+
+    pub fn random_init(t: usize) -> Self {
+        NewtonOptions {
+            init_guess: InitGuess::random(t),
+            max_iters: 100,
+            tol: 1e-6,
+            min_step: 1e-4,
+            max_step: 1.,
+            jacobian: NewtonJacobian::FiniteDifferences,
+            fixed_players: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NewtonResult<S: StrategyType> {
+    pub strategies: S,
+    pub payoffs: Array<f64, Ix1>,
+    pub converged: bool,
+    pub iterations: u64,
+    // largest first-order condition (in absolute value) at the start of each iteration and at the end
+    pub residuals: Vec<f64>,
+    // whether each player's payoff is locally concave in their own (log) params at the solution,
+    // i.e. whether the solution satisfies the second-order conditions for a maximum;
+    // always true for fixed players
+    pub second_order: Vec<bool>,
+}
+
+impl<S: StrategyType> NewtonResult<S> {
+    // a converged solution is only an equilibrium if every player is at a local maximum
+    pub fn is_local_equilibrium(&self) -> bool {
+        self.converged && self.second_order.iter().all(|&b| b)
+    }
+}
+
+impl<S: StrategyType + fmt::Display> fmt::Display for NewtonResult<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.converged {
+            writeln!(f, "Newton converged after {} iterations", self.iterations)?;
+        } else {
+            writeln!(f, "Newton did not converge after {} iterations", self.iterations)?;
+        }
+        writeln!(f, "second-order conditions hold: {:?}", self.second_order)?;
+        writeln!(f, "payoffs = {:.4}", self.payoffs)?;
+        write!(f, "{}", self.strategies)
+    }
+}
+
+// first-order conditions w.r.t. log params: d u_i / d log x = x * d u_i / d x
+fn log_foc<A, S, T>(agg: &T, y: &Array<f64, Ix1>, shape: (usize, usize, usize)) -> Array<f64, Ix1>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    let x = y.mapv(f64::exp);
+    stacked_foc(agg, &strategies_from_flat::<S>(&x, shape)) * &x
+}
+
+fn max_abs(x: &Array<f64, Ix1>) -> f64 {
+    x.fold(0., |acc: f64, v| acc.max(v.abs()))
+}
+
+// flat indices (in [t, i, m] order) of player i's params
+fn player_indices(i: usize, shape: (usize, usize, usize)) -> Vec<usize> {
+    let (t, n, m) = shape;
+    (0..t).flat_map(|s| (0..m).map(move |k| (s * n + i) * m + k)).collect()
+}
+
+// solves the stacked first-order conditions of all players at once with a damped Newton method,
+// which can succeed where iterated best response cycles or converges slowly
+pub fn solve_newton<A, S, T>(agg: &T, options: &NewtonOptions<S>) -> Result<NewtonResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    if let Some(&i) = options.fixed_players.iter().find(|&&i| i >= agg.n()) {
+        return Err(argmin::core::Error::msg(format!("Fixed player {} out of range for {} players", i, agg.n())));
+    }
+    let init_guess = options.init_guess.to_fixed(agg.n())?;
+    let shape = init_guess.data().dim();
+    let y0 = Array::from_iter(init_guess.data().iter().map(|x| x.ln()));
+    // the system only includes the params and first-order conditions of players who aren't fixed,
+    // with each active player's params in a contiguous block
+    let active = (0..agg.n()).filter(|i| !options.fixed_players.contains(i)).collect::<Vec<_>>();
+    let free = active.iter().flat_map(|&i| player_indices(i, shape)).collect::<Vec<_>>();
+    let embed = |y: &Array<f64, Ix1>| {
+        let mut y_full = y0.clone();
+        free.iter().zip(y.iter()).for_each(|(&k, &v)| y_full[k] = v);
+        y_full
+    };
+    let foc = |y: &Array<f64, Ix1>| log_foc(agg, &embed(y), shape).select(Axis(0), &free);
+    let jacobian = |y: &Array<f64, Ix1>| fd_jacobian(|y_| foc(&y_.to_owned()), y.view());
+
+    let mut y = y0.select(Axis(0), &free);
+    let mut f = foc(&y);
+    let mut jac: Array<f64, Ix2> = jacobian(&y);
+    let mut fresh = true;
+    let mut residuals = vec![max_abs(&f)];
+    let mut converged = residuals[0] < options.tol;
+    let mut iterations = 0;
+    while !converged && iterations < options.max_iters {
+        let step = match solve_linear(jac.view(), (-&f).view()) {
+            Some(step) => step,
+            None if !fresh => {
+                jac = jacobian(&y);
+                fresh = true;
+                continue;
+            },
+            None => break,
+        };
+        let scale = f64::min(1., options.max_step / max_abs(&step));
+        let norm = f.dot(&f);
+        let mut alpha = 1.;
+        let accepted = loop {
+            let y_ = &y + &(alpha * scale * &step);
+            let f_ = foc(&y_);
+            if f_.dot(&f_) < norm {
+                break Some((y_, f_));
+            }
+            alpha /= 2.;
+            if alpha < options.min_step {
+                break None;
+            }
+        };
+        match accepted {
+            Some((y_, f_)) => {
+                iterations += 1;
+                match options.jacobian {
+                    NewtonJacobian::FiniteDifferences => jac = jacobian(&y_),
+                    NewtonJacobian::Broyden => {
+                        let dy = &y_ - &y;
+                        let df = &f_ - &f;
+                        let update = (&df - &jac.dot(&dy)) / dy.dot(&dy);
+                        jac = jac + update.insert_axis(Axis(1)).dot(&dy.insert_axis(Axis(0)));
+                        fresh = false;
+                    },
+                }
+                y = y_;
+                f = f_;
+                residuals.push(max_abs(&f));
+                converged = max_abs(&f) < options.tol;
+            },
+            // a stale Broyden jacobian may point the wrong way, so retry with a fresh one
+            None if !fresh => {
+                jac = jacobian(&y);
+                fresh = true;
+            },
+            None => break,
+        }
+    }
+
+    let jac = if fresh { jac } else { jacobian(&y) };
+    let block = shape.0 * shape.2;
+    let second_order = (0..agg.n()).map(|i| match active.iter().position(|&j| j == i) {
+        Some(k) => {
+            let idx = (k * block..(k + 1) * block).collect::<Vec<_>>();
+            is_negative_definite(jac.select(Axis(0), &idx).select(Axis(1), &idx).view())
+        },
+        None => true,
+    }).collect();
+    let strategies = strategies_from_flat::<S>(&embed(&y).mapv(f64::exp), shape);
+    Ok(NewtonResult {
+        payoffs: agg.u(&strategies),
+        strategies,
+        converged,
+        iterations,
+        residuals,
+        second_order,
+    })
+}
@@ -0,0 +1,61 @@
+use numpy::ndarray::{Array, Ix1};
+
+// named access to model parameters, where names follow the component fields,
+// e.g. "risk_func.theta[0]" or "gammas[1]"
+pub trait Params: Clone {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String>;
+
+    // checks the constraints the constructor enforces, so that set_param can't break them;
+    // components with constrained params override this, and containers check their components
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn get_param(&self, name: &str) -> Result<f64, String> {
+        self.clone().param_mut(name).map(|x| *x)
+    }
+    fn set_param(&mut self, name: &str, value: f64) -> Result<(), String> {
+        let old = std::mem::replace(self.param_mut(name)?, value);
+        if let Err(e) = self.validate() {
+            *self.param_mut(name)? = old;
+            return Err(format!("Invalid value {} for parameter {}: {}", value, name, e));
+        }
+        Ok(())
+    }
+    fn with_param(&self, name: &str, value: f64) -> Result<Self, String> {
+        let mut new = self.clone();
+        new.set_param(name, value)?;
+        Ok(new)
+    }
+}
+
+pub fn unknown_param(name: &str) -> String {
+    format!("Unknown parameter: {}", name)
+}
+
+// splits "field.rest" into ("field", "rest")
+pub fn split_param(name: &str) -> (&str, &str) {
+    match name.split_once('.') {
+        Some((head, rest)) => (head, rest),
+        None => (name, ""),
+    }
+}
+
+// splits "field[k]" into ("field", Some(k)), and "field" into ("field", None)
+pub fn parse_index(name: &str) -> Result<(&str, Option<usize>), String> {
+    match name.split_once('[') {
+        Some((field, rest)) => match rest.strip_suffix(']').map(|k| k.parse::<usize>()) {
+            Some(Ok(k)) => Ok((field, Some(k))),
+            _ => Err(format!("Invalid index in parameter name: {}", name)),
+        },
+        None => Ok((name, None)),
+    }
+}
+
+// element of an array-valued parameter; name is only used for error messages
+pub fn index_mut<'a>(arr: &'a mut Array<f64, Ix1>, index: Option<usize>, name: &str) -> Result<&'a mut f64, String> {
+    match index {
+        Some(k) => arr.get_mut(k).ok_or_else(|| format!("Index out of bounds for parameter: {}", name)),
+        None => Err(format!("Parameter {} needs an index", name)),
+    }
+}
@@ -1,12 +1,14 @@
-use numpy::ndarray::{Array, Ix1};
+use numpy::ndarray::{Array, ArrayView, Axis, Ix1, Ix2};
 
 use crate::cost_func::CostFunc;
 use crate::csf::CSF;
 use crate::disaster_cost::DisasterCost;
+use crate::params::{Params, split_param, unknown_param};
 use crate::prod_func::ProdFunc;
 use crate::reward_func::RewardFunc;
 use crate::risk_func::RiskFunc;
-use crate::strategies::{ActionType, MutatesOnAction};
+use crate::strategies::{ActionType, FeedbackState, MutatesOnAction};
+use crate::utils::fd_step;
 
 pub trait PayoffFunc: Clone + Send + Sync {
     type Act: ActionType;
@@ -15,6 +17,35 @@ pub trait PayoffFunc: Clone + Send + Sync {
     fn u(&self, actions: &Self::Act) -> Array<f64, Ix1> {
         Array::from_iter((0..actions.n()).map(|i| self.u_i(i, actions)))
     }
+
+    // gradient of u_i w.r.t. player i's own action params
+    // defaults to finite differences; override when an analytic form is available
+    fn du_i(&self, i: usize, actions: &Self::Act) -> Array<f64, Ix1> {
+        let mut actions_ = actions.clone();
+        Array::from_iter((0..Self::Act::nparams()).map(|m| {
+            let x = actions.data()[[i, m]];
+            let h = fd_step(x);
+            actions_.data_mut()[[i, m]] = x + h;
+            let up = self.u_i(i, &actions_);
+            actions_.data_mut()[[i, m]] = x - h;
+            let down = self.u_i(i, &actions_);
+            actions_.data_mut()[[i, m]] = x;
+            (up - down) / (2. * h)
+        }))
+    }
+
+    // gradient of u_i w.r.t. player i's action params in any earlier period,
+    // through the changes those actions made to this payoff function (see MutatesOnAction);
+    // None if that effect is not known analytically
+    fn du_i_carried(&self, _i: usize, _actions: &Self::Act) -> Option<Array<f64, Ix1>> {
+        None
+    }
+
+    // probability that the contest is won and a disaster occurs;
+    // None if this payoff function has no notion of disaster
+    fn proba_disaster(&self, _actions: &Self::Act) -> Option<f64> {
+        None
+    }
 }
 
 #[derive(Clone)]
@@ -54,6 +85,7 @@ where A: ActionType,
             || n != reward_func.n()
             || n != disaster_cost.n()
             || n != cost_func.n()
+            || csf.n().is_some_and(|m| m != n)
         {
             return Err("When creating new DefaultPayoff: All components must have the same n");
         }
@@ -68,6 +100,51 @@ where A: ActionType,
             phantom: std::marker::PhantomData,
         })
     }
+
+    // gradient of the expected reward net of disaster cost (u_i + c_i) given jacobians
+    // of s and p w.r.t. some set of params, indexed as [j, m] = d s_j / d x_m
+    fn du_i_given(
+        &self, i: usize,
+        s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>,
+        ds: ArrayView<f64, Ix2>, dp: ArrayView<f64, Ix2>,
+    ) -> Array<f64, Ix1> {
+        let sigmas = self.risk_func.sigma(s, p);
+        let qs = self.csf.q(p);
+        let rewards = self.reward_func.reward(i, p);
+        let d_i = self.disaster_cost.d_i(i, s, p);
+
+        let (dsigma_ds, dsigma_dp) = self.risk_func.dsigma(s, p);
+        let dsigmas = dsigma_ds.dot(&ds) + dsigma_dp.dot(&dp);
+        let dqs = self.csf.dq(p).dot(&dp);
+        let drewards = self.reward_func.dreward(i, p).dot(&dp);
+        let (dd_ds, dd_dp) = self.disaster_cost.dd_i(i, s, p);
+        let dd_i = dd_ds.dot(&ds) + dd_dp.dot(&dp);
+
+        let mut du = Array::zeros(ds.ncols());
+        for j in 0..sigmas.len() {
+            du = du
+                + &dsigmas.row(j) * (qs[j] * (rewards[j] + d_i[j]))
+                + &dqs.row(j) * (sigmas[j] * rewards[j] - (1.0 - sigmas[j]) * d_i[j])
+                + &drewards.row(j) * (sigmas[j] * qs[j])
+                - &dd_i.row(j) * ((1.0 - sigmas[j]) * qs[j]);
+        }
+        du
+    }
+
+    // proba that someone wins the contest, together with its gradients w.r.t. player i's action params
+    // in the current period and, if known, in earlier periods (see ProdFunc::df_carried)
+    pub fn proba_win_with_grad(&self, i: usize, actions: &A) -> (f64, Array<f64, Ix1>, Option<Array<f64, Ix1>>) {
+        let (_, p) = self.prod_func.f(actions);
+        let dq_dp = self.csf.dq(p.view());
+        let dq_sum = dq_dp.sum_axis(Axis(0));
+        let (_, dp) = self.prod_func.df(i, actions);
+        let carried = self.prod_func.df_carried(i, actions).map(|(_, dp_)| dq_sum.dot(&dp_));
+        (
+            self.csf.q(p.view()).iter().sum::<f64>(),
+            dq_sum.dot(&dp),
+            carried,
+        )
+    }
 }
 
 impl<A, T, U, V, W, X, Y> PayoffFunc for DefaultPayoff<A, T, U, V, W, X, Y>
@@ -94,10 +171,11 @@ where A: ActionType,
         let no_d = sigmas.iter().zip(qs.iter()).zip(rewards.iter()).map(
             |((sigma, q), reward)| sigma * q * reward
         ).sum::<f64>();
-        // cost given disaster * proba disaster
-        let yes_d = (1.0 - sigmas.iter().zip(qs.iter()).map(
-            |(sigma, q)| sigma * q
-        ).sum::<f64>()) * self.disaster_cost.d_i(i, s.view(), p.view());
+        // cost given disaster * proba disaster, for each winner
+        let disaster_costs = self.disaster_cost.d_i(i, s.view(), p.view());
+        let yes_d = sigmas.iter().zip(qs.iter()).zip(disaster_costs.iter()).map(
+            |((sigma, q), d)| (1.0 - sigma) * q * d
+        ).sum::<f64>();
 
         no_d - yes_d - self.cost_func.c_i(i, actions)
     }
@@ -117,12 +195,13 @@ where A: ActionType,
             ).sum::<f64>()
         );
 
-        let proba_d = 1.0 - sigmas.iter().zip(qs.iter()).map(
-            |(sigma, q)| sigma * q
-        ).sum::<f64>();
+        // proba of a disaster caused by each winner
+        let probas_d = Array::from_iter(sigmas.iter().zip(qs.iter()).map(
+            |(sigma, q)| (1.0 - sigma) * q
+        ));
 
         let disaster_costs = self.disaster_cost.d(s.view(), p.view());
-        let yes_d = disaster_costs.iter().map(|d| d * proba_d);
+        let yes_d = disaster_costs.dot(&probas_d).into_iter();
 
         let net_rewards = no_d.zip(yes_d).map(|(n, y)| n - y);
 
@@ -130,6 +209,25 @@ where A: ActionType,
 
         Array::from_iter(net_rewards.zip(cost.iter()).map(|(r, c)| r - c))
     }
+
+    fn du_i(&self, i: usize, actions: &A) -> Array<f64, Ix1> {
+        let (s, p) = self.prod_func.f(actions);
+        let (ds, dp) = self.prod_func.df(i, actions);
+        self.du_i_given(i, s.view(), p.view(), ds.view(), dp.view()) - self.cost_func.dc_i(i, actions)
+    }
+
+    fn du_i_carried(&self, i: usize, actions: &A) -> Option<Array<f64, Ix1>> {
+        let (s, p) = self.prod_func.f(actions);
+        let (ds, dp) = self.prod_func.df_carried(i, actions)?;
+        Some(self.du_i_given(i, s.view(), p.view(), ds.view(), dp.view()))
+    }
+
+    fn proba_disaster(&self, actions: &A) -> Option<f64> {
+        let (s, p) = self.prod_func.f(actions);
+        let sigmas = self.risk_func.sigma(s.view(), p.view());
+        let qs = self.csf.q(p.view());
+        Some(sigmas.iter().zip(qs.iter()).map(|(sigma, q)| (1. - sigma) * q).sum::<f64>())
+    }
 }
 
 impl<A, T, U, V, W, X, Y> MutatesOnAction<A> for DefaultPayoff<A, T, U, V, W, X, Y>
@@ -145,3 +243,54 @@ where A: ActionType,
         self.prod_func.mutate_on_action_inplace(action);
     }
 }
+
+impl<A, T, U, V, W, X, Y> FeedbackState for DefaultPayoff<A, T, U, V, W, X, Y>
+where A: ActionType,
+      T: ProdFunc<A> + FeedbackState,
+      U: RiskFunc,
+      V: CSF,
+      W: RewardFunc,
+      X: DisasterCost,
+      Y: CostFunc<A>,
+{
+    fn features(&self) -> Array<f64, Ix1> {
+        self.prod_func.features()
+    }
+    fn with_features(&self, features: ArrayView<f64, Ix1>) -> Self {
+        let mut new_state = self.clone();
+        new_state.prod_func = self.prod_func.with_features(features);
+        new_state
+    }
+}
+
+impl<A, T, U, V, W, X, Y> Params for DefaultPayoff<A, T, U, V, W, X, Y>
+where A: ActionType,
+      T: ProdFunc<A> + Params,
+      U: RiskFunc + Params,
+      V: CSF + Params,
+      W: RewardFunc + Params,
+      X: DisasterCost + Params,
+      Y: CostFunc<A> + Params,
+{
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (head, rest) = split_param(name);
+        match head {
+            "prod_func" => self.prod_func.param_mut(rest),
+            "risk_func" => self.risk_func.param_mut(rest),
+            "csf" => self.csf.param_mut(rest),
+            "reward_func" => self.reward_func.param_mut(rest),
+            "disaster_cost" => self.disaster_cost.param_mut(rest),
+            "cost_func" => self.cost_func.param_mut(rest),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.prod_func.validate()?;
+        self.risk_func.validate()?;
+        self.csf.validate()?;
+        self.reward_func.validate()?;
+        self.disaster_cost.validate()?;
+        self.cost_func.validate()
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use numpy::ndarray::{Array, Axis, Ix1};
+use argmin::core::{CostFunction, Gradient, TerminationReason};
+
+use crate::solve::{BestResponseMethod, InitGuess, LBFGSOptions, SolverOptions, minimize, solve};
+use crate::states::PayoffAggregator;
+use crate::strategies::*;
+use crate::utils::fd_step;
+
+#[derive(Clone, Debug)]
+pub struct PlannerOptions<S: StrategyType> {
+    pub init_guess: InitGuess<S>,
+    // weight on each player's payoff in the planner's objective; None means equal weights
+    pub weights: Option<Array<f64, Ix1>>,
+    // the planner chooses every player's strategy at once, so there are many more params
+    // than in a best response and LBFGS is the default
+    pub method: BestResponseMethod,
+}
+
+impl<S: StrategyType> PlannerOptions<S> {
+    pub fn from_init_guess(init_guess: S) -> Self {
+        PlannerOptions {
+            init_guess: InitGuess::Fixed(init_guess),
+            weights: None,
+            method: BestResponseMethod::LBFGS(LBFGSOptions::default()),
+        }
+    }
+
+    pub fn random_init(t: usize) -> Self {
+        PlannerOptions {
+            init_guess: InitGuess::random(t),
+            weights: None,
+            method: BestResponseMethod::LBFGS(LBFGSOptions::default()),
+        }
+    }
+}
+
+fn expand_weights(weights: &Option<Array<f64, Ix1>>, n: usize) -> Result<Array<f64, Ix1>, argmin::core::Error> {
+    match weights {
+        Some(w) if w.len() != n => Err(argmin::core::Error::msg("Planner weights must have length n")),
+        Some(w) => Ok(w.clone()),
+        None => Ok(Array::ones(n)),
+    }
+}
+
+struct PlannerObjective<'a, A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>> {
+    payoff_aggregator: &'a T,
+    weights: &'a Array<f64, Ix1>,
+    shape: (usize, usize, usize),
+    _phantom: PhantomData<(A, S)>,
+}
+
+impl<A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>> PlannerObjective<'_, A, S, T> {
+    fn strategies_from(&self, params: &[f64]) -> Result<S, argmin::core::Error> {
+        Ok(S::from_array_unchecked(Array::from_shape_vec(
+            self.shape,
+            params.iter().map(|x| x.exp()).collect(),
+        )?))
+    }
+}
+
+impl<A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>> CostFunction for PlannerObjective<'_, A, S, T> {
+    type Param = Vec<f64>;
+    type Output = f64;
+
+    fn cost(&self, params: &Self::Param) -> Result<Self::Output, argmin::core::Error> {
+        let strategies = self.strategies_from(params)?;
+        Ok(-self.payoff_aggregator.u(&strategies).dot(self.weights))
+    }
+}
+
+impl<A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>> Gradient for PlannerObjective<'_, A, S, T> {
+    type Param = Vec<f64>;
+    type Gradient = Vec<f64>;
+
+    // grad_i only covers each player's own payoff, so use finite differences in log space
+    fn gradient(&self, params: &Self::Param) -> Result<Self::Gradient, argmin::core::Error> {
+        let mut params_ = params.clone();
+        params.iter().enumerate().map(|(k, &x)| {
+            let h = fd_step(x);
+            params_[k] = x + h;
+            let up = self.cost(&params_)?;
+            params_[k] = x - h;
+            let down = self.cost(&params_)?;
+            params_[k] = x;
+            Ok((up - down) / (2. * h))
+        }).collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PlannerResult<S: StrategyType> {
+    pub strategies: S,
+    pub payoffs: Array<f64, Ix1>,
+    // weighted sum of payoffs
+    pub welfare: f64,
+    pub termination_reason: TerminationReason,
+}
+
+impl<S: StrategyType> PlannerResult<S> {
+    pub fn converged(&self) -> bool {
+        self.termination_reason != TerminationReason::MaxItersReached
+    }
+}
+
+impl<S: StrategyType + fmt::Display> fmt::Display for PlannerResult<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Planner stopped: {}", self.termination_reason.text())?;
+        writeln!(f, "welfare = {:.4}, payoffs = {:.4}", self.welfare, self.payoffs)?;
+        write!(f, "{}", self.strategies)
+    }
+}
+
+// maximizes the weighted sum of payoffs over the whole strategy profile
+pub fn solve_planner<A, S, T>(agg: &T, options: &PlannerOptions<S>) -> Result<PlannerResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    let weights = expand_weights(&options.weights, agg.n())?;
+    let init_guess = options.init_guess.to_fixed(agg.n())?;
+    let obj = PlannerObjective {
+        payoff_aggregator: agg,
+        weights: &weights,
+        shape: (init_guess.t(), init_guess.n(), S::nparams()),
+        _phantom: PhantomData,
+    };
+    let init_param: Vec<f64> = init_guess.data().iter().map(|x| x.ln()).collect();
+    let (best_param, termination_reason) = minimize(obj, init_param, &options.method)?;
+    let strategies = S::from_array_unchecked(Array::from_shape_vec(
+        (init_guess.t(), init_guess.n(), S::nparams()),
+        best_param.iter().map(|x| x.exp()).collect(),
+    )?);
+    let payoffs = agg.u(&strategies);
+    Ok(PlannerResult {
+        welfare: payoffs.dot(&weights),
+        payoffs,
+        strategies,
+        termination_reason,
+    })
+}
+
+// summary statistics for one strategy profile
+#[derive(Clone, Debug)]
+pub struct OutcomeSummary {
+    pub payoffs: Array<f64, Ix1>,
+    pub welfare: f64,
+    // each player's safety spending (xs, plus inv_s when investing) summed over all periods
+    pub safety_spending: Array<f64, Ix1>,
+    // according to each player's beliefs; None if the aggregator has no notion of disaster
+    pub disaster_proba: Option<Array<f64, Ix1>>,
+}
+
+impl OutcomeSummary {
+    pub fn new<A, S, T>(agg: &T, strategies: &S, weights: &Array<f64, Ix1>) -> Self
+    where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+    {
+        let payoffs = agg.u(strategies);
+        OutcomeSummary {
+            welfare: payoffs.dot(weights),
+            payoffs,
+            safety_spending: strategies.data()
+                .select(Axis(2), A::safety_params())
+                .sum_axis(Axis(2))
+                .sum_axis(Axis(0)),
+            disaster_proba: agg.cumulative_disaster_proba(strategies),
+        }
+    }
+
+    pub fn total_safety_spending(&self) -> f64 {
+        self.safety_spending.sum()
+    }
+}
+
+impl fmt::Display for OutcomeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "welfare = {:.4}, payoffs = {:.4}, safety spending = {:.4}",
+            self.welfare, self.payoffs, self.total_safety_spending()
+        )?;
+        if let Some(proba) = &self.disaster_proba {
+            write!(f, ", disaster proba = {:.4}", proba)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PlannerReport<S: StrategyType> {
+    pub planner_strategies: S,
+    pub nash_strategies: S,
+    pub planner: OutcomeSummary,
+    pub nash: OutcomeSummary,
+}
+
+impl<S: StrategyType> PlannerReport<S> {
+    pub fn new<A, T>(agg: &T, planner_strategies: S, nash_strategies: S, weights: &Option<Array<f64, Ix1>>) -> Result<Self, argmin::core::Error>
+    where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+    {
+        let weights = expand_weights(weights, agg.n())?;
+        Ok(PlannerReport {
+            planner: OutcomeSummary::new(agg, &planner_strategies, &weights),
+            nash: OutcomeSummary::new(agg, &nash_strategies, &weights),
+            planner_strategies,
+            nash_strategies,
+        })
+    }
+
+    // ratio of planner welfare to Nash welfare; only meaningful when both are positive
+    pub fn price_of_anarchy(&self) -> f64 {
+        self.planner.welfare / self.nash.welfare
+    }
+
+    // welfare lost by not coordinating, which is well defined regardless of sign
+    pub fn welfare_loss(&self) -> f64 {
+        self.planner.welfare - self.nash.welfare
+    }
+}
+
+impl<S: StrategyType> fmt::Display for PlannerReport<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Planner: {}", self.planner)?;
+        writeln!(f, "Nash: {}", self.nash)?;
+        write!(f, "price of anarchy = {:.4}, welfare loss = {:.4}", self.price_of_anarchy(), self.welfare_loss())
+    }
+}
+
+// solves for both the planner and the Nash solution and compares them
+pub fn planner_report<A, S, T>(
+    agg: &T, planner_options: &PlannerOptions<S>, solver_options: &SolverOptions<S>
+) -> Result<PlannerReport<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    let planner = solve_planner(agg, planner_options)?;
+    let nash = solve(agg, solver_options)?;
+    PlannerReport::new(agg, planner.strategies, nash.strategies, &planner_options.weights)
+}
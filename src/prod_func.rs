@@ -1,7 +1,9 @@
-use numpy::ndarray::{Array, Ix1};
+use numpy::ndarray::{Array, ArrayView, Axis, Ix1, Ix2, concatenate, s};
 use std::fmt;
 
+use crate::params::{Params, index_mut, parse_index, unknown_param};
 use crate::strategies::*;
+use crate::utils::fd_step;
 
 pub trait ProdFunc<A: ActionType>: Clone + Send + Sync {
     fn f_i(&self, i: usize, actions: &A) -> (f64, f64);
@@ -10,6 +12,34 @@ pub trait ProdFunc<A: ActionType>: Clone + Send + Sync {
         (Array::from_vec(s), Array::from_vec(p))
     }
 
+    // jacobians of s and p w.r.t. player i's action params, indexed as [j, m] = d s_j / d x_im
+    // defaults to finite differences; override when an analytic form is available
+    fn df(&self, i: usize, actions: &A) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        let n = actions.n();
+        let mut ds = Array::zeros((n, A::nparams()));
+        let mut dp = Array::zeros((n, A::nparams()));
+        let mut actions_ = actions.clone();
+        for m in 0..A::nparams() {
+            let x = actions.data()[[i, m]];
+            let h = fd_step(x);
+            actions_.data_mut()[[i, m]] = x + h;
+            let (s_up, p_up) = self.f(&actions_);
+            actions_.data_mut()[[i, m]] = x - h;
+            let (s_down, p_down) = self.f(&actions_);
+            actions_.data_mut()[[i, m]] = x;
+            ds.column_mut(m).assign(&((s_up - s_down) / (2. * h)));
+            dp.column_mut(m).assign(&((p_up - p_down) / (2. * h)));
+        }
+        (ds, dp)
+    }
+
+    // jacobians of s and p w.r.t. player i's action params in any earlier period,
+    // through the changes those actions made to this production function (see MutatesOnAction);
+    // None if that effect is not known analytically
+    fn df_carried(&self, _i: usize, _actions: &A) -> Option<(Array<f64, Ix2>, Array<f64, Ix2>)> {
+        None
+    }
+
     fn n(&self) -> usize;
 }
 
@@ -30,17 +60,62 @@ impl DefaultProd {
         }
         Ok(DefaultProd { n, a, alpha, b, beta })
     }
-}
 
-impl<A: ActionType> ProdFunc<A> for DefaultProd {
-
-    fn f_i(&self, i: usize, actions: &A) -> (f64, f64) {
+    fn f_i_inner<A: ActionType>(&self, i: usize, actions: &A) -> (f64, f64) {
         (
             self.a[i] * actions.xs()[i].powf(self.alpha[i]),
             self.b[i] * actions.xp()[i].powf(self.beta[i])
         )
     }
 
+    fn df_inner<A: ActionType>(&self, i: usize, actions: &A) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        let mut ds = Array::zeros((self.n, A::nparams()));
+        let mut dp = Array::zeros((self.n, A::nparams()));
+        ds[[i, 0]] = self.a[i] * self.alpha[i] * actions.xs()[i].powf(self.alpha[i] - 1.);
+        dp[[i, 1]] = self.b[i] * self.beta[i] * actions.xp()[i].powf(self.beta[i] - 1.);
+        (ds, dp)
+    }
+}
+
+impl ProdFunc<Actions> for DefaultProd {
+
+    fn f_i(&self, i: usize, actions: &Actions) -> (f64, f64) {
+        self.f_i_inner(i, actions)
+    }
+
+    fn df(&self, i: usize, actions: &Actions) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        self.df_inner(i, actions)
+    }
+
+    fn df_carried(&self, _i: usize, _actions: &Actions) -> Option<(Array<f64, Ix2>, Array<f64, Ix2>)> {
+        // actions don't mutate the production function
+        Some((Array::zeros((self.n, 2)), Array::zeros((self.n, 2))))
+    }
+
+    fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl ProdFunc<InvestActions> for DefaultProd {
+
+    fn f_i(&self, i: usize, actions: &InvestActions) -> (f64, f64) {
+        self.f_i_inner(i, actions)
+    }
+
+    fn df(&self, i: usize, actions: &InvestActions) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        self.df_inner(i, actions)
+    }
+
+    fn df_carried(&self, i: usize, actions: &InvestActions) -> Option<(Array<f64, Ix2>, Array<f64, Ix2>)> {
+        // every unit of inv_s (inv_p) invested earlier adds one to a (b)
+        let mut ds = Array::zeros((self.n, 4));
+        let mut dp = Array::zeros((self.n, 4));
+        ds[[i, 2]] = actions.xs()[i].powf(self.alpha[i]);
+        dp[[i, 3]] = actions.xp()[i].powf(self.beta[i]);
+        Some((ds, dp))
+    }
+
     fn n(&self) -> usize {
         self.n
     }
@@ -59,6 +134,20 @@ impl MutatesOnAction<InvestActions> for DefaultProd {
     }
 }
 
+// state variables are a followed by b
+impl FeedbackState for DefaultProd {
+    fn features(&self) -> Array<f64, Ix1> {
+        concatenate(Axis(0), &[self.a.view(), self.b.view()]).unwrap()
+    }
+    fn with_features(&self, features: ArrayView<f64, Ix1>) -> Self {
+        DefaultProd {
+            a: features.slice(s![..self.n]).to_owned(),
+            b: features.slice(s![self.n..]).to_owned(),
+            ..self.clone()
+        }
+    }
+}
+
 impl fmt::Display for DefaultProd {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -67,3 +156,506 @@ impl fmt::Display for DefaultProd {
         )
     }
 }
+
+impl Params for DefaultProd {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "a" => index_mut(&mut self.a, index, name),
+            "alpha" => index_mut(&mut self.alpha, index, name),
+            "b" => index_mut(&mut self.b, index, name),
+            "beta" => index_mut(&mut self.beta, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+
+// mixes a player's own input with the other input, with elasticity of substitution 1 / (1 - rho):
+// (omega * own^rho + (1 - omega) * other^rho)^(1 / rho), or own^omega * other^(1 - omega) when rho = 0
+fn ces_mix(own: f64, other: f64, omega: f64, rho: f64) -> f64 {
+    if rho == 0. {
+        own.powf(omega) * other.powf(1. - omega)
+    } else {
+        (omega * own.powf(rho) + (1. - omega) * other.powf(rho)).powf(1. / rho)
+    }
+}
+
+// derivatives of ces_mix w.r.t. own and other, given the mixed input m
+fn ces_dmix(own: f64, other: f64, omega: f64, rho: f64, m: f64) -> (f64, f64) {
+    // an input with zero weight contributes nothing, even when it is zero itself
+    let d = |x: f64, w: f64| if w == 0. { 0. } else { w * x.powf(rho - 1.) * m.powf(1. - rho) };
+    (d(own, omega), d(other, 1. - omega))
+}
+
+fn validate_ces(
+    omega_s: &Array<f64, Ix1>, omega_p: &Array<f64, Ix1>, rho: &Array<f64, Ix1>
+) -> Result<(), &'static str> {
+    if omega_s.iter().chain(omega_p.iter()).any(|&w| !(0. ..=1.).contains(&w)) {
+        return Err("When creating new CESProd: omega_s and omega_p must be between 0 and 1");
+    }
+    if rho.iter().any(|&r| !r.is_finite() || r > 1.) {
+        return Err("When creating new CESProd: rho must be finite and at most 1");
+    }
+    Ok(())
+}
+
+// s = a * m_s^alpha and p = b * m_p^beta, where m_s (m_p) is a CES mix of xs and xp
+// giving weight omega_s to xs (omega_p to xp); with omega_s = omega_p = 1 this is DefaultProd
+#[derive(Clone, Debug)]
+pub struct CESProd {
+    n: usize,
+    pub a: Array<f64, Ix1>,
+    pub alpha: Array<f64, Ix1>,
+    pub b: Array<f64, Ix1>,
+    pub beta: Array<f64, Ix1>,
+    pub omega_s: Array<f64, Ix1>,
+    pub omega_p: Array<f64, Ix1>,
+    pub rho: Array<f64, Ix1>,
+}
+
+impl CESProd {
+    pub fn new(
+        a: Array<f64, Ix1>, alpha: Array<f64, Ix1>,
+        b: Array<f64, Ix1>, beta: Array<f64, Ix1>,
+        omega_s: Array<f64, Ix1>, omega_p: Array<f64, Ix1>,
+        rho: Array<f64, Ix1>,
+    ) -> Result<CESProd, &'static str> {
+        let n = a.len();
+        if n != alpha.len() || n != b.len() || n != beta.len()
+            || n != omega_s.len() || n != omega_p.len() || n != rho.len()
+        {
+            return Err("When creating new CESProd: All input arrays must have the same length");
+        }
+        validate_ces(&omega_s, &omega_p, &rho)?;
+        Ok(CESProd { n, a, alpha, b, beta, omega_s, omega_p, rho })
+    }
+
+    fn mix_i<A: ActionType>(&self, i: usize, actions: &A) -> (f64, f64) {
+        let (xs, xp) = (actions.xs()[i], actions.xp()[i]);
+        (
+            ces_mix(xs, xp, self.omega_s[i], self.rho[i]),
+            ces_mix(xp, xs, self.omega_p[i], self.rho[i]),
+        )
+    }
+
+    fn f_i_inner<A: ActionType>(&self, i: usize, actions: &A) -> (f64, f64) {
+        let (m_s, m_p) = self.mix_i(i, actions);
+        (
+            self.a[i] * m_s.powf(self.alpha[i]),
+            self.b[i] * m_p.powf(self.beta[i])
+        )
+    }
+
+    fn df_inner<A: ActionType>(&self, i: usize, actions: &A) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        let mut ds = Array::zeros((self.n, A::nparams()));
+        let mut dp = Array::zeros((self.n, A::nparams()));
+        let (xs, xp) = (actions.xs()[i], actions.xp()[i]);
+        let (m_s, m_p) = self.mix_i(i, actions);
+        let (dms_dxs, dms_dxp) = ces_dmix(xs, xp, self.omega_s[i], self.rho[i], m_s);
+        let (dmp_dxp, dmp_dxs) = ces_dmix(xp, xs, self.omega_p[i], self.rho[i], m_p);
+        let ds_dm = self.a[i] * self.alpha[i] * m_s.powf(self.alpha[i] - 1.);
+        let dp_dm = self.b[i] * self.beta[i] * m_p.powf(self.beta[i] - 1.);
+        ds[[i, 0]] = ds_dm * dms_dxs;
+        ds[[i, 1]] = ds_dm * dms_dxp;
+        dp[[i, 0]] = dp_dm * dmp_dxs;
+        dp[[i, 1]] = dp_dm * dmp_dxp;
+        (ds, dp)
+    }
+}
+
+impl ProdFunc<Actions> for CESProd {
+
+    fn f_i(&self, i: usize, actions: &Actions) -> (f64, f64) {
+        self.f_i_inner(i, actions)
+    }
+
+    fn df(&self, i: usize, actions: &Actions) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        self.df_inner(i, actions)
+    }
+
+    fn df_carried(&self, _i: usize, _actions: &Actions) -> Option<(Array<f64, Ix2>, Array<f64, Ix2>)> {
+        // actions don't mutate the production function
+        Some((Array::zeros((self.n, 2)), Array::zeros((self.n, 2))))
+    }
+
+    fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl ProdFunc<InvestActions> for CESProd {
+
+    fn f_i(&self, i: usize, actions: &InvestActions) -> (f64, f64) {
+        self.f_i_inner(i, actions)
+    }
+
+    fn df(&self, i: usize, actions: &InvestActions) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        self.df_inner(i, actions)
+    }
+
+    fn df_carried(&self, i: usize, actions: &InvestActions) -> Option<(Array<f64, Ix2>, Array<f64, Ix2>)> {
+        // every unit of inv_s (inv_p) invested earlier adds one to a (b)
+        let mut ds = Array::zeros((self.n, 4));
+        let mut dp = Array::zeros((self.n, 4));
+        let (m_s, m_p) = self.mix_i(i, actions);
+        ds[[i, 2]] = m_s.powf(self.alpha[i]);
+        dp[[i, 3]] = m_p.powf(self.beta[i]);
+        Some((ds, dp))
+    }
+
+    fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl MutatesOnAction<Actions> for CESProd {}
+
+impl MutatesOnAction<InvestActions> for CESProd {
+    fn mutate_on_action_inplace(&mut self, actions: &InvestActions) {
+        self.a.iter_mut().zip(actions.inv_s().iter()).for_each(
+            |(a, inv_s)| *a += inv_s
+        );
+        self.b.iter_mut().zip(actions.inv_p().iter()).for_each(
+            |(b, inv_p)| *b += inv_p
+        );
+    }
+}
+
+// state variables are a followed by b
+impl FeedbackState for CESProd {
+    fn features(&self) -> Array<f64, Ix1> {
+        concatenate(Axis(0), &[self.a.view(), self.b.view()]).unwrap()
+    }
+    fn with_features(&self, features: ArrayView<f64, Ix1>) -> Self {
+        CESProd {
+            a: features.slice(s![..self.n]).to_owned(),
+            b: features.slice(s![self.n..]).to_owned(),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for CESProd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "CESProd {{ a = {}, alpha = {}, b = {}, beta = {}, omega_s = {}, omega_p = {}, rho = {} }}",
+            self.a, self.alpha, self.b, self.beta, self.omega_s, self.omega_p, self.rho
+        )
+    }
+}
+
+impl Params for CESProd {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "a" => index_mut(&mut self.a, index, name),
+            "alpha" => index_mut(&mut self.alpha, index, name),
+            "b" => index_mut(&mut self.b, index, name),
+            "beta" => index_mut(&mut self.beta, index, name),
+            "omega_s" => index_mut(&mut self.omega_s, index, name),
+            "omega_p" => index_mut(&mut self.omega_p, index, name),
+            "rho" => index_mut(&mut self.rho, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_ces(&self.omega_s, &self.omega_p, &self.rho)?;
+        Ok(())
+    }
+}
+
+
+// s is as in DefaultProd, but each player's p also includes a share of its rivals' own p:
+// p_i = b_i * xp_i^beta_i + sum_{j != i} spillover_ij * b_j * xp_j^beta_j
+#[derive(Clone, Debug)]
+pub struct SpilloverProd {
+    n: usize,
+    pub a: Array<f64, Ix1>,
+    pub alpha: Array<f64, Ix1>,
+    pub b: Array<f64, Ix1>,
+    pub beta: Array<f64, Ix1>,
+    pub spillover: Array<f64, Ix2>,
+}
+
+impl SpilloverProd {
+    pub fn new(
+        a: Array<f64, Ix1>, alpha: Array<f64, Ix1>,
+        b: Array<f64, Ix1>, beta: Array<f64, Ix1>,
+        spillover: Array<f64, Ix2>,
+    ) -> Result<SpilloverProd, &'static str> {
+        let n = a.len();
+        if n != alpha.len() || n != b.len() || n != beta.len() {
+            return Err("When creating new SpilloverProd: All input arrays must have the same length");
+        }
+        if spillover.shape() != [n, n] {
+            return Err("When creating new SpilloverProd: spillover must be an n x n matrix");
+        }
+        if spillover.iter().any(|&x| x.is_nan() || x < 0.) {
+            return Err("When creating new SpilloverProd: spillover must be nonnegative");
+        }
+        if spillover.diag().iter().any(|&x| x != 0.) {
+            return Err("When creating new SpilloverProd: spillover must have a zero diagonal");
+        }
+        Ok(SpilloverProd { n, a, alpha, b, beta, spillover })
+    }
+
+    // every player receives the same share of each rival's p
+    pub fn uniform(
+        a: Array<f64, Ix1>, alpha: Array<f64, Ix1>,
+        b: Array<f64, Ix1>, beta: Array<f64, Ix1>,
+        share: f64,
+    ) -> Result<SpilloverProd, &'static str> {
+        let n = a.len();
+        let spillover = Array::from_shape_fn((n, n), |(i, j)| if i == j { 0. } else { share });
+        SpilloverProd::new(a, alpha, b, beta, spillover)
+    }
+
+    // p that each player produces on its own, before spillovers
+    fn own_p<A: ActionType>(&self, actions: &A) -> Array<f64, Ix1> {
+        Array::from_iter((0..self.n).map(|j| self.b[j] * actions.xp()[j].powf(self.beta[j])))
+    }
+
+    // share of player i's own p received by player j
+    fn share(&self, j: usize, i: usize) -> f64 {
+        if j == i { 1. } else { self.spillover[[j, i]] }
+    }
+
+    fn f_i_inner<A: ActionType>(&self, i: usize, actions: &A) -> (f64, f64) {
+        (
+            self.a[i] * actions.xs()[i].powf(self.alpha[i]),
+            self.own_p(actions).iter().enumerate().map(|(j, p_j)| self.share(i, j) * p_j).sum()
+        )
+    }
+
+    fn f_inner<A: ActionType>(&self, actions: &A) -> (Array<f64, Ix1>, Array<f64, Ix1>) {
+        let s = Array::from_iter((0..self.n).map(|i| self.a[i] * actions.xs()[i].powf(self.alpha[i])));
+        let own_p = self.own_p(actions);
+        let p = self.spillover.dot(&own_p) + &own_p;
+        (s, p)
+    }
+
+    fn df_inner<A: ActionType>(&self, i: usize, actions: &A) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        let mut ds = Array::zeros((self.n, A::nparams()));
+        let mut dp = Array::zeros((self.n, A::nparams()));
+        ds[[i, 0]] = self.a[i] * self.alpha[i] * actions.xs()[i].powf(self.alpha[i] - 1.);
+        let dp_i = self.b[i] * self.beta[i] * actions.xp()[i].powf(self.beta[i] - 1.);
+        for j in 0..self.n {
+            dp[[j, 1]] = self.share(j, i) * dp_i;
+        }
+        (ds, dp)
+    }
+}
+
+impl ProdFunc<Actions> for SpilloverProd {
+
+    fn f_i(&self, i: usize, actions: &Actions) -> (f64, f64) {
+        self.f_i_inner(i, actions)
+    }
+
+    fn f(&self, actions: &Actions) -> (Array<f64, Ix1>, Array<f64, Ix1>) {
+        self.f_inner(actions)
+    }
+
+    fn df(&self, i: usize, actions: &Actions) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        self.df_inner(i, actions)
+    }
+
+    fn df_carried(&self, _i: usize, _actions: &Actions) -> Option<(Array<f64, Ix2>, Array<f64, Ix2>)> {
+        // actions don't mutate the production function
+        Some((Array::zeros((self.n, 2)), Array::zeros((self.n, 2))))
+    }
+
+    fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl ProdFunc<InvestActions> for SpilloverProd {
+
+    fn f_i(&self, i: usize, actions: &InvestActions) -> (f64, f64) {
+        self.f_i_inner(i, actions)
+    }
+
+    fn f(&self, actions: &InvestActions) -> (Array<f64, Ix1>, Array<f64, Ix1>) {
+        self.f_inner(actions)
+    }
+
+    fn df(&self, i: usize, actions: &InvestActions) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        self.df_inner(i, actions)
+    }
+
+    fn df_carried(&self, i: usize, actions: &InvestActions) -> Option<(Array<f64, Ix2>, Array<f64, Ix2>)> {
+        // every unit of inv_s (inv_p) invested earlier adds one to a (b),
+        // and rivals receive their share of the resulting increase in p
+        let mut ds = Array::zeros((self.n, 4));
+        let mut dp = Array::zeros((self.n, 4));
+        ds[[i, 2]] = actions.xs()[i].powf(self.alpha[i]);
+        let dp_i = actions.xp()[i].powf(self.beta[i]);
+        for j in 0..self.n {
+            dp[[j, 3]] = self.share(j, i) * dp_i;
+        }
+        Some((ds, dp))
+    }
+
+    fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl MutatesOnAction<Actions> for SpilloverProd {}
+
+impl MutatesOnAction<InvestActions> for SpilloverProd {
+    fn mutate_on_action_inplace(&mut self, actions: &InvestActions) {
+        self.a.iter_mut().zip(actions.inv_s().iter()).for_each(
+            |(a, inv_s)| *a += inv_s
+        );
+        self.b.iter_mut().zip(actions.inv_p().iter()).for_each(
+            |(b, inv_p)| *b += inv_p
+        );
+    }
+}
+
+// state variables are a followed by b
+impl FeedbackState for SpilloverProd {
+    fn features(&self) -> Array<f64, Ix1> {
+        concatenate(Axis(0), &[self.a.view(), self.b.view()]).unwrap()
+    }
+    fn with_features(&self, features: ArrayView<f64, Ix1>) -> Self {
+        SpilloverProd {
+            a: features.slice(s![..self.n]).to_owned(),
+            b: features.slice(s![self.n..]).to_owned(),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for SpilloverProd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "SpilloverProd {{ a = {}, alpha = {}, b = {}, beta = {}, spillover = {} }}",
+            self.a, self.alpha, self.b, self.beta, self.spillover
+        )
+    }
+}
+
+// the spillover matrix is fixed; only the DefaultProd params are exposed
+impl Params for SpilloverProd {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "a" => index_mut(&mut self.a, index, name),
+            "alpha" => index_mut(&mut self.alpha, index, name),
+            "b" => index_mut(&mut self.b, index, name),
+            "beta" => index_mut(&mut self.beta, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{assert_jacobian_matches_fd, isapprox_iters};
+
+    fn arr(x: &[f64]) -> Array<f64, Ix1> {
+        Array::from_vec(x.to_vec())
+    }
+
+    fn invest_actions() -> InvestActions {
+        InvestActions::from_inputs(
+            arr(&[0.7, 1.3, 0.4]), arr(&[1.1, 0.5, 0.9]), arr(&[0.2, 0.6, 0.3]), arr(&[0.5, 0.1, 0.4])
+        ).unwrap()
+    }
+
+    // actions with player i's action params replaced by x
+    fn with_row<A: ActionType>(actions: &A, i: usize, x: ArrayView<f64, Ix1>) -> A {
+        let mut actions_ = actions.clone();
+        actions_.data_mut().row_mut(i).assign(&x);
+        actions_
+    }
+
+    fn assert_df_matches_fd<A: ActionType, P: ProdFunc<A>>(name: &str, prod: &P, actions: &A) {
+        for i in 0..actions.n() {
+            let x = actions.data().row(i).to_owned();
+            let (ds, dp) = prod.df(i, actions);
+            assert_jacobian_matches_fd(&format!("{} ds", name), &ds, |x_| prod.f(&with_row(actions, i, x_)).0, x.view());
+            assert_jacobian_matches_fd(&format!("{} dp", name), &dp, |x_| prod.f(&with_row(actions, i, x_)).1, x.view());
+        }
+    }
+
+    // investments made in an earlier period change the production function used now
+    fn assert_df_carried_matches_fd<P>(name: &str, prod: &P, actions: &InvestActions)
+    where P: ProdFunc<InvestActions> + MutatesOnAction<InvestActions>
+    {
+        let carried = |earlier: &InvestActions| prod.mutate_on_action(earlier).f(actions);
+        for i in 0..actions.n() {
+            let x = actions.data().row(i).to_owned();
+            let (ds, dp) = prod.df_carried(i, actions).unwrap();
+            assert_jacobian_matches_fd(&format!("{} carried ds", name), &ds, |x_| carried(&with_row(actions, i, x_)).0, x.view());
+            assert_jacobian_matches_fd(&format!("{} carried dp", name), &dp, |x_| carried(&with_row(actions, i, x_)).1, x.view());
+        }
+    }
+
+    fn default_prod() -> DefaultProd {
+        DefaultProd::new(arr(&[1., 2., 1.5]), arr(&[0.5, 0.3, 0.6]), arr(&[1.2, 0.8, 1.]), arr(&[0.4, 0.7, 0.5])).unwrap()
+    }
+
+    fn ces_prod(omega_s: f64, omega_p: f64, rho: f64) -> CESProd {
+        let d = default_prod();
+        CESProd::new(
+            d.a, d.alpha, d.b, d.beta,
+            Array::from_elem(3, omega_s), Array::from_elem(3, omega_p), Array::from_elem(3, rho)
+        ).unwrap()
+    }
+
+    fn spillover_prod(share: f64) -> SpilloverProd {
+        let d = default_prod();
+        SpilloverProd::uniform(d.a, d.alpha, d.b, d.beta, share).unwrap()
+    }
+
+    #[test]
+    fn df_matches_finite_differences() {
+        let invest = invest_actions();
+        let actions = invest.truncate_to_actions().unwrap();
+        for (name, prod) in [
+            ("CESProd", ces_prod(0.7, 0.6, 0.4)),
+            ("CESProd with rho = 0", ces_prod(0.7, 0.6, 0.)),
+            ("CESProd with rho < 0", ces_prod(0.8, 0.3, -1.5)),
+        ] {
+            assert_df_matches_fd(name, &prod, &actions);
+            assert_df_matches_fd(name, &prod, &invest);
+            assert_df_carried_matches_fd(name, &prod, &invest);
+        }
+        let prod = spillover_prod(0.3);
+        assert_df_matches_fd("SpilloverProd", &prod, &actions);
+        assert_df_matches_fd("SpilloverProd", &prod, &invest);
+        assert_df_carried_matches_fd("SpilloverProd", &prod, &invest);
+    }
+
+    #[test]
+    fn reduces_to_default_prod() {
+        let invest = invest_actions();
+        let baseline = default_prod();
+        let (s, p) = baseline.f(&invest);
+        for (name, (s_, p_)) in [
+            ("CESProd", ces_prod(1., 1., 0.4).f(&invest)),
+            ("SpilloverProd", spillover_prod(0.).f(&invest)),
+        ] {
+            assert!(isapprox_iters(s_.iter().copied(), s.iter().copied(), 1e-12, 1e-15), "{}: s differs", name);
+            assert!(isapprox_iters(p_.iter().copied(), p.iter().copied(), 1e-12, 1e-15), "{}: p differs", name);
+        }
+    }
+
+    #[test]
+    fn set_param_rejects_invalid_ces_params() {
+        let mut prod = ces_prod(0.7, 0.6, 0.4);
+        assert!(prod.set_param("omega_s[0]", 1.5).is_err());
+        assert!(prod.set_param("omega_p[2]", -0.1).is_err());
+        assert!(prod.set_param("rho[1]", 2.).is_err());
+        assert_eq!((prod.omega_s[0], prod.omega_p[2], prod.rho[1]), (0.7, 0.6, 0.4));
+        prod.set_param("rho[1]", -3.).unwrap();
+        assert_eq!(prod.rho[1], -3.);
+    }
+}
@@ -1,18 +1,39 @@
-use numpy::{PyArray1, PyReadonlyArray1, PyReadonlyArray3, IntoPyArray, PyArray, Ix3, Ix2};
-use numpy::ndarray::{Array1};
+use std::thread;
+use std::time::Duration;
+
+use numpy::{PyArray1, PyArrayDyn, PyReadonlyArray1, PyReadonlyArray2, PyReadonlyArray3, IntoPyArray, PyArray, Ix3, Ix2};
+use numpy::ndarray::{Array1, Array2, Array4, ArrayView1, Ix4};
 use pyo3::exceptions::PyException;
 use pyo3::{prelude::*, types::PyList};
 
+use crate::constraints::{ConstraintScope, LinearConstraint};
 use crate::cost_func::{FixedUnitCost, FixedInvestCost};
-use crate::csf::{DefaultCSF, MaybeNoWinCSF};
-use crate::disaster_cost::ConstantDisasterCost;
+use crate::csf::{
+    CSF, DefaultCSF, LogitCSF, MaybeNoWinCSF, MaybeNoWinLogitCSF, MaybeNoWinProbitCSF, MaybeNoWinTullockCSF,
+    ProbitCSF, TullockCSF,
+};
+use crate::disaster_cost::{CapabilityDisasterCost, ConstantDisasterCost, DisasterCost, PairwiseDisasterCost};
+use crate::params::Params;
 use crate::payoff_func::{PayoffFunc, DefaultPayoff};
 use crate::prod_func::{ProdFunc, DefaultProd};
-use crate::reward_func::LinearReward;
-use crate::risk_func::WinnerOnlyRisk;
+use crate::reward_func::{LinearReward, MatrixReward, PowerReward, RelativeReward, RewardFunc};
+use crate::risk_func::{ProductRisk, RiskFunc, WeakestLinkRisk, WeightedRisk, WinnerOnlyRisk};
+use crate::continuation::{ContinuationOptions, ContinuationResult, solve_path};
+use crate::landscape::{BestResponseOptions, BestResponseResult, ParamAxis, best_response, payoff_grid};
+use crate::markov::{MarkovOptions, MarkovResult, solve_markov};
+use crate::multistart::{MultiStartOptions, MultiStartResult, solve_multistart};
+use crate::newton::{NewtonJacobian, NewtonOptions, NewtonResult, solve_newton};
+use crate::planner::{OutcomeSummary, PlannerOptions, PlannerReport, PlannerResult, planner_report, solve_planner};
 use crate::scenarios::Scenario;
-use crate::solve::{InitGuess, NMOptions, SolverOptions, solve};
-use crate::states::{PayoffAggregator, ExponentialDiscounter, InvestExpDiscounter, EndsOnContestWin};
+use crate::sensitivity::{Sensitivity, SensitivityMethod, SensitivityOptions, sensitivities};
+use crate::solve::{
+    AnnealOptions, BestResponseMethod, CancelToken, EquilibriumCheck, InitGuess, LBFGSOptions, NMOptions, PSOOptions,
+    Progress, ProgressCallback, SolveResult, SolverOptions, StopReason, Symmetry, UpdateScheme, VerifyOptions,
+    solve, verify_equilibrium,
+};
+use crate::stackelberg::{StackelbergOptions, StackelbergResult, solve_stackelberg};
+use crate::stationary::{HorizonOptions, HorizonResult, StationaryResult, solve_extending_horizon, solve_stationary};
+use crate::states::{PayoffAggregator, ExponentialDiscounter, InvestExpDiscounter, EndsOnContestWin, TerminalValue};
 use crate::strategies::*;
 use crate::init_rep;
 
@@ -108,11 +129,12 @@ impl PyContainer for PyStrategies {
 #[pymethods]
 impl PyStrategies {
     #[new]
-    fn from_array(x: PyReadonlyArray3<f64>) -> Self {
-        PyStrategies(match Strategies::from_array(x.as_array().to_owned()) {
-            Ok(strategies) => strategies,
-            Err(e) => panic!("{}", e),
-        })
+    #[args(constraints = "Vec::new()")]
+    fn from_array(x: PyReadonlyArray3<f64>, constraints: Vec<PyLinearConstraint>) -> PyResult<Self> {
+        let constraints = constraints.into_iter().map(|c| c.0).collect::<Vec<_>>();
+        Strategies::from_array_constrained(x.as_array().to_owned(), &constraints)
+            .map(PyStrategies)
+            .map_err(PyException::new_err)
     }
 
     #[staticmethod]
@@ -153,11 +175,12 @@ impl PyContainer for PyInvestStrategies {
 #[pymethods]
 impl PyInvestStrategies {
     #[new]
-    fn from_array(x: PyReadonlyArray3<f64>) -> Self {
-        PyInvestStrategies(match InvestStrategies::from_array(x.as_array().to_owned()) {
-            Ok(strategies) => strategies,
-            Err(e) => panic!("{}", e),
-        })
+    #[args(constraints = "Vec::new()")]
+    fn from_array(x: PyReadonlyArray3<f64>, constraints: Vec<PyLinearConstraint>) -> PyResult<Self> {
+        let constraints = constraints.into_iter().map(|c| c.0).collect::<Vec<_>>();
+        InvestStrategies::from_array_constrained(x.as_array().to_owned(), &constraints)
+            .map(PyInvestStrategies)
+            .map_err(PyException::new_err)
     }
 
     #[staticmethod]
@@ -369,15 +392,470 @@ impl PyLinearReward {
     }
 }
 
+// create python class container "RewardFunc" for a choice of reward function
+
+#[derive(Clone, Debug)]
+pub enum AnyRewardFunc {
+    Linear(LinearReward),
+    Power(PowerReward),
+    Relative(RelativeReward),
+    Matrix(MatrixReward),
+}
+
+macro_rules! match_reward {
+    ($reward:expr, $r:ident => $body:expr) => {
+        match $reward {
+            AnyRewardFunc::Linear($r) => $body,
+            AnyRewardFunc::Power($r) => $body,
+            AnyRewardFunc::Relative($r) => $body,
+            AnyRewardFunc::Matrix($r) => $body,
+        }
+    };
+}
+
+impl RewardFunc for AnyRewardFunc {
+    fn reward_ij(&self, i: usize, j: usize, p: ArrayView1<f64>) -> f64 {
+        match_reward!(self, r => r.reward_ij(i, j, p))
+    }
+    fn reward(&self, i: usize, p: ArrayView1<f64>) -> Array1<f64> {
+        match_reward!(self, r => r.reward(i, p))
+    }
+    fn dreward(&self, i: usize, p: ArrayView1<f64>) -> Array2<f64> {
+        match_reward!(self, r => r.dreward(i, p))
+    }
+    fn n(&self) -> usize {
+        match_reward!(self, r => r.n())
+    }
+}
+
+impl Params for AnyRewardFunc {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        match_reward!(self, r => r.param_mut(name))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match_reward!(self, r => r.validate())
+    }
+}
+
+impl std::fmt::Display for AnyRewardFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AnyRewardFunc::Linear(r) => write!(f, "{}", r),
+            AnyRewardFunc::Power(r) => write!(f, "{:?}", r),
+            AnyRewardFunc::Relative(r) => write!(f, "{:?}", r),
+            AnyRewardFunc::Matrix(r) => write!(f, "{:?}", r),
+        }
+    }
+}
+
+#[pyclass(name = "RewardFunc")]
+#[derive(Clone)]
+pub struct PyRewardFunc(AnyRewardFunc);
+
+impl PyContainer for PyRewardFunc {
+    type Item = AnyRewardFunc;
+    fn get(&self) -> &Self::Item {
+        &self.0
+    }
+}
+
+#[pymethods]
+impl PyRewardFunc {
+    // when j wins, win_a_j + win_b_j p_j for j and lose_a_j + lose_b_j p_j for everyone else, as in LinearReward
+    #[staticmethod]
+    fn linear(
+        win_a: PyReadonlyArray1<f64>, win_b: PyReadonlyArray1<f64>,
+        lose_a: PyReadonlyArray1<f64>, lose_b: PyReadonlyArray1<f64>,
+    ) -> PyResult<Self> {
+        LinearReward::new(
+            win_a.as_array().to_owned(),
+            win_b.as_array().to_owned(),
+            lose_a.as_array().to_owned(),
+            lose_b.as_array().to_owned(),
+        ).map(|r| PyRewardFunc(AnyRewardFunc::Linear(r))).map_err(PyException::new_err)
+    }
+
+    // as linear, but in ((1 + p_j)^gamma - 1) / gamma, or ln(1 + p_j) when gamma = 0
+    #[staticmethod]
+    fn power(
+        win_a: PyReadonlyArray1<f64>, win_b: PyReadonlyArray1<f64>,
+        lose_a: PyReadonlyArray1<f64>, lose_b: PyReadonlyArray1<f64>,
+        gamma: f64,
+    ) -> PyResult<Self> {
+        PowerReward::new(
+            win_a.as_array().to_owned(),
+            win_b.as_array().to_owned(),
+            lose_a.as_array().to_owned(),
+            lose_b.as_array().to_owned(),
+            gamma,
+        ).map(|r| PyRewardFunc(AnyRewardFunc::Power(r))).map_err(PyException::new_err)
+    }
+
+    // when j wins, win_a_j + win_b_j p_j for j and lose_a_j - lose_c_j (1 + p_j) / (1 + p_i) for each other player i
+    #[staticmethod]
+    fn relative(
+        win_a: PyReadonlyArray1<f64>, win_b: PyReadonlyArray1<f64>,
+        lose_a: PyReadonlyArray1<f64>, lose_c: PyReadonlyArray1<f64>,
+    ) -> PyResult<Self> {
+        RelativeReward::new(
+            win_a.as_array().to_owned(),
+            win_b.as_array().to_owned(),
+            lose_a.as_array().to_owned(),
+            lose_c.as_array().to_owned(),
+        ).map(|r| PyRewardFunc(AnyRewardFunc::Relative(r))).map_err(PyException::new_err)
+    }
+
+    // reward a[i, j] + b[i, j] p_j to player i when j wins; b defaults to zeros
+    #[staticmethod]
+    #[args(b = "None")]
+    fn matrix(a: PyReadonlyArray2<f64>, b: Option<PyReadonlyArray2<f64>>) -> PyResult<Self> {
+        let a = a.as_array().to_owned();
+        let reward = match b {
+            Some(b) => MatrixReward::new(a, b.as_array().to_owned()),
+            None => MatrixReward::constant(a),
+        };
+        reward.map(|r| PyRewardFunc(AnyRewardFunc::Matrix(r))).map_err(PyException::new_err)
+    }
+
+    // rewards to player i for each possible winner
+    fn reward<'py>(&self, py: Python<'py>, i: usize, p: PyReadonlyArray1<f64>) -> &'py PyArray1<f64> {
+        self.0.reward(i, p.as_array()).into_pyarray(py)
+    }
+
+    fn __str__(&self) -> String {
+        format!("{}", self.0)
+    }
+}
+
+// payoff functions take either a LinearReward or a RewardFunc
+fn extract_reward(reward_func: &PyAny) -> PyResult<AnyRewardFunc> {
+    if let Ok(r) = reward_func.extract::<PyLinearReward>() {
+        return Ok(AnyRewardFunc::Linear(r.0));
+    }
+    match reward_func.extract::<PyRewardFunc>() {
+        Ok(r) => Ok(r.0),
+        Err(_) => Err(PyException::new_err("reward_func must be a LinearReward or a RewardFunc")),
+    }
+}
+
+// create python class container "CSF" for a choice of contest success function
+
+// the CSFs that can be chosen from python
+#[derive(Clone, Debug)]
+pub enum AnyCSF {
+    Default(DefaultCSF),
+    MaybeNoWin(MaybeNoWinCSF),
+    Tullock(TullockCSF),
+    MaybeNoWinTullock(MaybeNoWinTullockCSF),
+    Logit(LogitCSF),
+    MaybeNoWinLogit(MaybeNoWinLogitCSF),
+    Probit(ProbitCSF),
+    MaybeNoWinProbit(MaybeNoWinProbitCSF),
+}
+
+macro_rules! match_csf {
+    ($csf:expr, $c:ident => $body:expr) => {
+        match $csf {
+            AnyCSF::Default($c) => $body,
+            AnyCSF::MaybeNoWin($c) => $body,
+            AnyCSF::Tullock($c) => $body,
+            AnyCSF::MaybeNoWinTullock($c) => $body,
+            AnyCSF::Logit($c) => $body,
+            AnyCSF::MaybeNoWinLogit($c) => $body,
+            AnyCSF::Probit($c) => $body,
+            AnyCSF::MaybeNoWinProbit($c) => $body,
+        }
+    };
+}
+
+impl AnyCSF {
+    // the same contest, but where no one wins with some probability (with scale 1)
+    fn no_win(self) -> Self {
+        match self {
+            AnyCSF::Default(_) => AnyCSF::MaybeNoWin(MaybeNoWinCSF::default()),
+            AnyCSF::Tullock(c) => AnyCSF::MaybeNoWinTullock(MaybeNoWinTullockCSF { r: c.r, w: c.w, scale: 1. }),
+            AnyCSF::Logit(c) => AnyCSF::MaybeNoWinLogit(MaybeNoWinLogitCSF { k: c.k, scale: 1. }),
+            AnyCSF::Probit(c) => AnyCSF::MaybeNoWinProbit(MaybeNoWinProbitCSF { sigma: c.sigma, scale: 1. }),
+            no_win => no_win,
+        }
+    }
+}
+
+impl CSF for AnyCSF {
+    fn q_i(&self, i: usize, p: ArrayView1<f64>) -> f64 {
+        match_csf!(self, c => c.q_i(i, p))
+    }
+    fn q(&self, p: ArrayView1<f64>) -> Array1<f64> {
+        match_csf!(self, c => c.q(p))
+    }
+    fn dq(&self, p: ArrayView1<f64>) -> Array2<f64> {
+        match_csf!(self, c => c.dq(p))
+    }
+    fn n(&self) -> Option<usize> {
+        match_csf!(self, c => c.n())
+    }
+}
+
+impl Params for AnyCSF {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        match_csf!(self, c => c.param_mut(name))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match_csf!(self, c => c.validate())
+    }
+}
+
+#[pyclass(name = "CSF")]
+#[derive(Clone)]
+pub struct PyCSF(AnyCSF);
+
+impl PyContainer for PyCSF {
+    type Item = AnyCSF;
+    fn get(&self) -> &Self::Item {
+        &self.0
+    }
+}
+
+#[pymethods]
+impl PyCSF {
+    // q_i = p_i / sum_j p_j
+    #[new]
+    fn new() -> Self {
+        PyCSF(AnyCSF::Default(DefaultCSF))
+    }
+
+    // q_i = scale p_i / (1 + scale sum_j p_j)
+    #[staticmethod]
+    #[args(scale = "1.0")]
+    fn maybe_no_win(scale: f64) -> Self {
+        PyCSF(AnyCSF::MaybeNoWin(MaybeNoWinCSF::new(scale)))
+    }
+
+    // q_i = w_i p_i^r / sum_j w_j p_j^r, or the no-win variant if scale is given
+    #[staticmethod]
+    #[args(scale = "None")]
+    fn tullock(r: f64, w: PyReadonlyArray1<f64>, scale: Option<f64>) -> PyResult<Self> {
+        let w = w.as_array().to_owned();
+        let csf = match scale {
+            Some(scale) => MaybeNoWinTullockCSF::new(r, w, scale).map(AnyCSF::MaybeNoWinTullock),
+            None => TullockCSF::new(r, w).map(AnyCSF::Tullock),
+        };
+        csf.map(PyCSF).map_err(PyException::new_err)
+    }
+
+    // q_i = exp(k p_i) / sum_j exp(k p_j), or the no-win variant if scale is given
+    #[staticmethod]
+    #[args(scale = "None")]
+    fn logit(k: f64, scale: Option<f64>) -> PyResult<Self> {
+        let csf = match scale {
+            Some(scale) => MaybeNoWinLogitCSF::new(k, scale).map(AnyCSF::MaybeNoWinLogit),
+            None => LogitCSF::new(k).map(AnyCSF::Logit),
+        };
+        csf.map(PyCSF).map_err(PyException::new_err)
+    }
+
+    // the winner has the highest p_i + sigma * standard normal noise, or the no-win variant if scale is given
+    #[staticmethod]
+    #[args(scale = "None")]
+    fn probit(sigma: f64, scale: Option<f64>) -> PyResult<Self> {
+        let csf = match scale {
+            Some(scale) => MaybeNoWinProbitCSF::new(sigma, scale).map(AnyCSF::MaybeNoWinProbit),
+            None => ProbitCSF::new(sigma).map(AnyCSF::Probit),
+        };
+        csf.map(PyCSF).map_err(PyException::new_err)
+    }
+
+    fn q<'py>(&self, py: Python<'py>, p: PyReadonlyArray1<f64>) -> &'py PyArray1<f64> {
+        self.0.q(p.as_array()).into_pyarray(py)
+    }
+
+    fn __str__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+// the risk functions that can be chosen from python, by name
+
+#[derive(Clone, Debug)]
+pub enum AnyRiskFunc {
+    WinnerOnly(WinnerOnlyRisk),
+    Product(ProductRisk),
+    WeakestLink(WeakestLinkRisk),
+    Weighted(WeightedRisk),
+}
+
+macro_rules! match_risk {
+    ($risk:expr, $r:ident => $body:expr) => {
+        match $risk {
+            AnyRiskFunc::WinnerOnly($r) => $body,
+            AnyRiskFunc::Product($r) => $body,
+            AnyRiskFunc::WeakestLink($r) => $body,
+            AnyRiskFunc::Weighted($r) => $body,
+        }
+    };
+}
+
+impl AnyRiskFunc {
+    fn theta(&self) -> &Array1<f64> {
+        match_risk!(self, r => &r.theta)
+    }
+}
+
+impl RiskFunc for AnyRiskFunc {
+    fn sigma_i(&self, i: usize, s: ArrayView1<f64>, p: ArrayView1<f64>) -> f64 {
+        match_risk!(self, r => r.sigma_i(i, s, p))
+    }
+    fn sigma(&self, s: ArrayView1<f64>, p: ArrayView1<f64>) -> Array1<f64> {
+        match_risk!(self, r => r.sigma(s, p))
+    }
+    fn dsigma(&self, s: ArrayView1<f64>, p: ArrayView1<f64>) -> (Array2<f64>, Array2<f64>) {
+        match_risk!(self, r => r.dsigma(s, p))
+    }
+    fn n(&self) -> usize {
+        match_risk!(self, r => r.n())
+    }
+}
+
+impl Params for AnyRiskFunc {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        match_risk!(self, r => r.param_mut(name))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match_risk!(self, r => r.validate())
+    }
+}
+
+// risk is one of "winner_only", "product", "weakest_link" or "weighted";
+// weights (an n x n matrix with rows summing to 1) are needed for, and only used by, "weighted"
+fn parse_risk(theta: Array1<f64>, risk: &str, weights: Option<&PyReadonlyArray2<f64>>) -> PyResult<AnyRiskFunc> {
+    match (risk, weights) {
+        ("winner_only", None) => Ok(AnyRiskFunc::WinnerOnly(WinnerOnlyRisk { theta })),
+        ("product", None) => Ok(AnyRiskFunc::Product(ProductRisk { theta })),
+        ("weakest_link", None) => Ok(AnyRiskFunc::WeakestLink(WeakestLinkRisk { theta })),
+        ("weighted", Some(w)) => WeightedRisk::new(theta, w.as_array().to_owned())
+            .map(AnyRiskFunc::Weighted)
+            .map_err(PyException::new_err),
+        ("weighted", None) => Err(PyException::new_err("risk_weights are needed for risk=\"weighted\"")),
+        ("winner_only" | "product" | "weakest_link", Some(_)) => Err(PyException::new_err(
+            "risk_weights are only used with risk=\"weighted\""
+        )),
+        _ => Err(PyException::new_err(
+            "risk must be one of \"winner_only\", \"product\", \"weakest_link\" or \"weighted\""
+        )),
+    }
+}
+
+fn expand_risk_funcs(
+    theta_list: Vec<PyReadonlyArray1<f64>>, risk: &str, risk_weights: Option<PyReadonlyArray2<f64>>,
+) -> PyResult<Vec<AnyRiskFunc>> {
+    theta_list.into_iter().map(|x|
+        parse_risk(x.as_array().to_owned(), risk, risk_weights.as_ref())
+    ).collect()
+}
+
+// the disaster costs that can be chosen from python
+
+#[derive(Clone, Debug)]
+pub enum AnyDisasterCost {
+    Constant(ConstantDisasterCost),
+    Capability(CapabilityDisasterCost),
+    Pairwise(PairwiseDisasterCost),
+}
+
+macro_rules! match_disaster_cost {
+    ($cost:expr, $d:ident => $body:expr) => {
+        match $cost {
+            AnyDisasterCost::Constant($d) => $body,
+            AnyDisasterCost::Capability($d) => $body,
+            AnyDisasterCost::Pairwise($d) => $body,
+        }
+    };
+}
+
+impl DisasterCost for AnyDisasterCost {
+    fn d_ij(&self, i: usize, j: usize, s: ArrayView1<f64>, p: ArrayView1<f64>) -> f64 {
+        match_disaster_cost!(self, d => d.d_ij(i, j, s, p))
+    }
+    fn d_i(&self, i: usize, s: ArrayView1<f64>, p: ArrayView1<f64>) -> Array1<f64> {
+        match_disaster_cost!(self, d => d.d_i(i, s, p))
+    }
+    fn d(&self, s: ArrayView1<f64>, p: ArrayView1<f64>) -> Array2<f64> {
+        match_disaster_cost!(self, d => d.d(s, p))
+    }
+    fn dd_i(&self, i: usize, s: ArrayView1<f64>, p: ArrayView1<f64>) -> (Array2<f64>, Array2<f64>) {
+        match_disaster_cost!(self, d => d.dd_i(i, s, p))
+    }
+    fn n(&self) -> usize {
+        match_disaster_cost!(self, d => d.n())
+    }
+}
+
+impl Params for AnyDisasterCost {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        match_disaster_cost!(self, d => d.param_mut(name))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match_disaster_cost!(self, d => d.validate())
+    }
+}
+
+// d is either a vector, giving each player's cost whoever wins, or an n x n matrix d[i, j],
+// giving player i's cost when player j wins; with d1, a vector d is d0 in d0_i + d1_i p_winner^kappa
+fn extract_disaster_cost(d: &PyAny, d1: Option<&PyReadonlyArray1<f64>>, kappa: f64) -> PyResult<AnyDisasterCost> {
+    if let Ok(d) = d.extract::<PyReadonlyArray1<f64>>() {
+        let d = d.as_array().to_owned();
+        return match d1 {
+            Some(d1) => CapabilityDisasterCost::new(d, d1.as_array().to_owned(), kappa)
+                .map(AnyDisasterCost::Capability)
+                .map_err(PyException::new_err),
+            None => Ok(AnyDisasterCost::Constant(ConstantDisasterCost { d })),
+        };
+    }
+    match (d.extract::<PyReadonlyArray2<f64>>(), d1) {
+        (Ok(d), None) => PairwiseDisasterCost::new(d.as_array().to_owned())
+            .map(AnyDisasterCost::Pairwise)
+            .map_err(PyException::new_err),
+        (Ok(_), Some(_)) => Err(PyException::new_err("d1 can only be used with a vector d")),
+        (Err(_), _) => Err(PyException::new_err("d must be a 1d or 2d array")),
+    }
+}
+
+fn expand_disaster_costs(
+    d_list: Vec<&PyAny>, d1_list: Option<Vec<PyReadonlyArray1<f64>>>, kappa: f64,
+) -> PyResult<Vec<AnyDisasterCost>> {
+    match d1_list {
+        Some(d1_list) => {
+            // pair up the lists, repeating the last entry of the shorter one as init_rep! does
+            let len = d_list.len().max(d1_list.len());
+            (0..len).map(|k| extract_disaster_cost(
+                d_list[k.min(d_list.len() - 1)], Some(&d1_list[k.min(d1_list.len() - 1)]), kappa,
+            )).collect()
+        },
+        None => d_list.into_iter().map(|d| extract_disaster_cost(d, None, kappa)).collect(),
+    }
+}
+
 // create python class container "PayoffFunc" for DefaultPayoff
 
+// the CSFs for expand_from, which uses the default CSF if none are given
+fn expand_csfs(csf_list: Option<Vec<PyCSF>>) -> Vec<AnyCSF> {
+    match csf_list {
+        Some(csfs) if !csfs.is_empty() => csfs.into_iter().map(|x| x.0).collect(),
+        _ => vec![AnyCSF::Default(DefaultCSF)],
+    }
+}
+
 type DefaultPayoff_ = DefaultPayoff<
     Actions,
     DefaultProd,
-    WinnerOnlyRisk,
-    DefaultCSF,
-    LinearReward,
-    ConstantDisasterCost,
+    AnyRiskFunc,
+    AnyCSF,
+    AnyRewardFunc,
+    AnyDisasterCost,
     FixedUnitCost
 >;
 
@@ -395,59 +873,72 @@ impl PyContainer for PyDefaultPayoff {
 #[pymethods]
 impl PyDefaultPayoff {
     #[new]
+    #[args(csf = "None", risk = "\"winner_only\"", risk_weights = "None", d1 = "None", kappa = "1.0")]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         prod_func: PyDefaultProd,
-        reward_func: PyLinearReward,
+        reward_func: &PyAny,
         theta: PyReadonlyArray1<f64>,
-        d: PyReadonlyArray1<f64>,
+        d: &PyAny,
         r: PyReadonlyArray1<f64>,
-    ) -> Self {
-        PyDefaultPayoff(DefaultPayoff::new(
+        csf: Option<PyCSF>,
+        risk: &str,
+        risk_weights: Option<PyReadonlyArray2<f64>>,
+        d1: Option<PyReadonlyArray1<f64>>,
+        kappa: f64,
+    ) -> PyResult<Self> {
+        DefaultPayoff::new(
             prod_func.0,
-            WinnerOnlyRisk { theta: theta.as_array().to_owned() },
-            DefaultCSF,
-            reward_func.0,
-            ConstantDisasterCost { d: d.as_array().to_owned() },
+            parse_risk(theta.as_array().to_owned(), risk, risk_weights.as_ref())?,
+            csf.map_or(AnyCSF::Default(DefaultCSF), |c| c.0),
+            extract_reward(reward_func)?,
+            extract_disaster_cost(d, d1.as_ref(), kappa)?,
             FixedUnitCost { r: r.as_array().to_owned() },
-        ).expect("invalid payoff function parameters"))
+        ).map(PyDefaultPayoff).map_err(PyException::new_err)
     }
 
     #[staticmethod]
+    #[args(
+        csf_list = "None", risk = "\"winner_only\"", risk_weights = "None", d1_list = "None", kappa = "1.0",
+    )]
+    #[allow(clippy::too_many_arguments)]
     pub fn expand_from<'py>(
         py: Python<'py>,
         prod_func_list: Vec<PyDefaultProd>,
-        reward_func_list: Vec<PyLinearReward>,
+        reward_func_list: Vec<&PyAny>,
         theta_list: Vec<PyReadonlyArray1<f64>>,
-        d_list: Vec<PyReadonlyArray1<f64>>,
+        d_list: Vec<&PyAny>,
         r_list: Vec<PyReadonlyArray1<f64>>,
-    ) -> &'py PyList {
+        csf_list: Option<Vec<PyCSF>>,
+        risk: &str,
+        risk_weights: Option<PyReadonlyArray2<f64>>,
+        d1_list: Option<Vec<PyReadonlyArray1<f64>>>,
+        kappa: f64,
+    ) -> PyResult<&'py PyList> {
         let prod_funcs = prod_func_list.into_iter().map(|x| x.0).collect::<Vec<_>>();
-        let reward_funcs = reward_func_list.into_iter().map(|x| x.0).collect::<Vec<_>>();
-        let risk_funcs = theta_list.into_iter().map(|x|
-            WinnerOnlyRisk { theta: x.as_array().to_owned() }
-        ).collect::<Vec<_>>();
-        let disaster_costs = d_list.into_iter().map(|x|
-            ConstantDisasterCost { d: x.as_array().to_owned() }
-        ).collect::<Vec<_>>();
+        let csfs = expand_csfs(csf_list);
+        let reward_funcs = reward_func_list.into_iter().map(extract_reward).collect::<PyResult<Vec<_>>>()?;
+        let risk_funcs = expand_risk_funcs(theta_list, risk, risk_weights)?;
+        let disaster_costs = expand_disaster_costs(d_list, d1_list, kappa)?;
         let cost_funcs = r_list.into_iter().map(|x| {
             FixedUnitCost { r: x.as_array().to_owned() }
         }).collect::<Vec<_>>();
 
         let payoff_funcs = init_rep!(DefaultPayoff_ =>
             prod_func: DefaultProd = prod_funcs;
-            risk_func: WinnerOnlyRisk = risk_funcs;
-            csf: DefaultCSF = vec![DefaultCSF];
-            reward_func: LinearReward = reward_funcs;
-            disaster_cost: ConstantDisasterCost = disaster_costs;
+            risk_func: AnyRiskFunc = risk_funcs;
+            csf: AnyCSF = csfs;
+            reward_func: AnyRewardFunc = reward_funcs;
+            disaster_cost: AnyDisasterCost = disaster_costs;
             cost_funcs: FixedUnitCost = cost_funcs
         );
 
-        PyList::new(
+        Ok(PyList::new(
             py,
             payoff_funcs.into_iter().map(|x|
                 PyDefaultPayoff(x).into_py(py)
             )
-        )
+        ))
     }
 
     fn u_i(&self, i: usize, actions: &PyActions) -> f64 {
@@ -460,11 +951,12 @@ impl PyDefaultPayoff {
 
     fn __str__(&self) -> String {
         format!(
-            "PayoffFunc (PyDefaultPayoff):\nprod_func = {}\nreward_func = {}\ntheta = {}\nd = {}\nr = {}",
+            "PayoffFunc (PyDefaultPayoff):\nprod_func = {}\ncsf = {:?}\nreward_func = {}\ntheta = {}\ndisaster_cost = {:?}\nr = {}",
             self.0.prod_func,
+            self.0.csf,
             self.0.reward_func,
-            self.0.risk_func.theta,
-            self.0.disaster_cost.d,
+            self.0.risk_func.theta(),
+            self.0.disaster_cost,
             self.0.cost_func.r
         )
     } 
@@ -473,10 +965,10 @@ impl PyDefaultPayoff {
 type InvestPayoff_ = DefaultPayoff<
     InvestActions,
     DefaultProd,
-    WinnerOnlyRisk,
-    DefaultCSF,
-    LinearReward,
-    ConstantDisasterCost,
+    AnyRiskFunc,
+    AnyCSF,
+    AnyRewardFunc,
+    AnyDisasterCost,
     FixedInvestCost
 >;
 
@@ -494,49 +986,60 @@ impl PyContainer for PyInvestPayoff {
 #[pymethods]
 impl PyInvestPayoff {
     #[new]
+    #[args(csf = "None", risk = "\"winner_only\"", risk_weights = "None", d1 = "None", kappa = "1.0")]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         prod_func: PyInvestProd,
-        reward_func: PyLinearReward,
+        reward_func: &PyAny,
         theta: PyReadonlyArray1<f64>,
-        d: PyReadonlyArray1<f64>,
+        d: &PyAny,
         r_x: PyReadonlyArray1<f64>,
         r_inv: PyReadonlyArray1<f64>,
-    ) -> Self {
-        PyInvestPayoff(DefaultPayoff::new(
+        csf: Option<PyCSF>,
+        risk: &str,
+        risk_weights: Option<PyReadonlyArray2<f64>>,
+        d1: Option<PyReadonlyArray1<f64>>,
+        kappa: f64,
+    ) -> PyResult<Self> {
+        DefaultPayoff::new(
             prod_func.0,
-            WinnerOnlyRisk { theta: theta.as_array().to_owned() },
-            DefaultCSF,
-            reward_func.0,
-            ConstantDisasterCost { d: d.as_array().to_owned() },
+            parse_risk(theta.as_array().to_owned(), risk, risk_weights.as_ref())?,
+            csf.map_or(AnyCSF::Default(DefaultCSF), |c| c.0),
+            extract_reward(reward_func)?,
+            extract_disaster_cost(d, d1.as_ref(), kappa)?,
             FixedInvestCost::new(
                 r_x.as_array().to_owned(),
                 r_inv.as_array().to_owned(),
             ),
-        ).expect("invalid payoff function parameters"))
+        ).map(PyInvestPayoff).map_err(PyException::new_err)
     }
 
     #[staticmethod]
+    #[args(
+        csf_list = "None", risk = "\"winner_only\"", risk_weights = "None", d1_list = "None", kappa = "1.0",
+    )]
+    #[allow(clippy::too_many_arguments)]
     pub fn expand_from<'py>(
         py: Python<'py>,
         prod_func_list: Vec<PyInvestProd>,
-        reward_func_list: Vec<PyLinearReward>,
+        reward_func_list: Vec<&PyAny>,
         theta_list: Vec<PyReadonlyArray1<f64>>,
-        d_list: Vec<PyReadonlyArray1<f64>>,
+        d_list: Vec<&PyAny>,
         r_x_list: Vec<PyReadonlyArray1<f64>>,
         r_inv_list: Vec<PyReadonlyArray1<f64>>,
-    ) -> &'py PyList {
+        csf_list: Option<Vec<PyCSF>>,
+        risk: &str,
+        risk_weights: Option<PyReadonlyArray2<f64>>,
+        d1_list: Option<Vec<PyReadonlyArray1<f64>>>,
+        kappa: f64,
+    ) -> PyResult<&'py PyList> {
+        let csfs = expand_csfs(csf_list);
         let prod_funcs = prod_func_list.into_iter().map(|x|
             x.0
         ).collect::<Vec<_>>();
-        let reward_funcs = reward_func_list.into_iter().map(|x|
-            x.0
-        ).collect::<Vec<_>>();
-        let risk_funcs = theta_list.into_iter().map(|x|
-            WinnerOnlyRisk { theta: x.as_array().to_owned() }
-        ).collect::<Vec<_>>();
-        let disaster_costs = d_list.into_iter().map(|x|
-            ConstantDisasterCost { d: x.as_array().to_owned() }
-        ).collect::<Vec<_>>();
+        let reward_funcs = reward_func_list.into_iter().map(extract_reward).collect::<PyResult<Vec<_>>>()?;
+        let risk_funcs = expand_risk_funcs(theta_list, risk, risk_weights)?;
+        let disaster_costs = expand_disaster_costs(d_list, d1_list, kappa)?;
         let cost_funcs = r_x_list.into_iter().zip(r_inv_list.into_iter()).map(|(r_x, r_inv)| {
             FixedInvestCost::new(
                 r_x.as_array().to_owned(),
@@ -546,19 +1049,19 @@ impl PyInvestPayoff {
 
         let payoff_funcs = init_rep!(InvestPayoff_ =>
             prod_func: DefaultProd = prod_funcs;
-            risk_func: WinnerOnlyRisk = risk_funcs;
-            csf: DefaultCSF = vec![DefaultCSF];
-            reward_func: LinearReward = reward_funcs;
-            disaster_cost: ConstantDisasterCost = disaster_costs;
+            risk_func: AnyRiskFunc = risk_funcs;
+            csf: AnyCSF = csfs;
+            reward_func: AnyRewardFunc = reward_funcs;
+            disaster_cost: AnyDisasterCost = disaster_costs;
             cost_funcs: FixedInvestCost = cost_funcs
         );
 
-        PyList::new(
+        Ok(PyList::new(
             py,
             payoff_funcs.into_iter().map(|x|
                 PyInvestPayoff(x).into_py(py)
             )
-        )
+        ))
     }
 
     pub fn u_i(&self, i: usize, actions: &PyInvestActions) -> f64 {
@@ -571,34 +1074,121 @@ impl PyInvestPayoff {
 
     fn __str__(&self) -> String {
         format!(
-            "InvestPayoffFunc (PyInvestPayoff):\nprod_func = {}\nreward_func = {}\ntheta = {}\nd = {}\nr_x = {}\nr_inv = {}",
+            "InvestPayoffFunc (PyInvestPayoff):\nprod_func = {}\ncsf = {:?}\nreward_func = {}\ntheta = {}\ndisaster_cost = {:?}\nr_x = {}\nr_inv = {}",
             self.0.prod_func,
+            self.0.csf,
             self.0.reward_func,
-            self.0.risk_func.theta,
-            self.0.disaster_cost.d,
+            self.0.risk_func.theta(),
+            self.0.disaster_cost,
             self.0.cost_func.r_x,
             self.0.cost_func.r_inv,
         )
     } 
 }
 
+// create python class container for LinearConstraint
+
+#[derive(Clone)]
+#[pyclass(name = "LinearConstraint")]
+pub struct PyLinearConstraint(LinearConstraint);
+
+#[pymethods]
+impl PyLinearConstraint {
+    // scope is "period" or "cumulative"; with scope "period", the constraint applies in every
+    // period unless a single period is given
+    #[new]
+    #[args(scope = "\"period\"", period = "None", lower = "None", upper = "None", players = "None")]
+    fn new(
+        coefs: PyReadonlyArray1<f64>, scope: &str, period: Option<usize>,
+        lower: Option<f64>, upper: Option<f64>, players: Option<Vec<usize>>,
+    ) -> PyResult<Self> {
+        let scope = match (scope, period) {
+            ("period", None) => ConstraintScope::EachPeriod,
+            ("period", Some(k)) => ConstraintScope::Period(k),
+            ("cumulative", None) => ConstraintScope::Cumulative,
+            ("cumulative", Some(_)) => return Err(PyException::new_err("period can't be given for a cumulative constraint")),
+            _ => return Err(PyException::new_err("scope must be either \"period\" or \"cumulative\"")),
+        };
+        let constraint = LinearConstraint::new(coefs.as_array().to_owned(), scope, lower, upper)
+            .map_err(PyException::new_err)?;
+        Ok(PyLinearConstraint(match players {
+            Some(players) => constraint.for_players(players),
+            None => constraint,
+        }))
+    }
+
+    fn __str__(&self) -> String {
+        format!("LinearConstraint: {}", self.0)
+    }
+}
+
 // create python class container for SolverOptions
 
+// the optimizers that can be chosen by name in python
+#[derive(Clone, Copy, Debug)]
+enum MethodName {
+    NelderMead,
+    Lbfgs,
+    ParticleSwarm,
+    Anneal,
+}
+
+/// Options for solve and the other equilibrium solvers.
+///
+/// method is one of "neldermead", "lbfgs", "pso" or "anneal". Particle swarm ("pso") draws from
+/// an unseeded random number generator, so its results differ between runs even when solve is
+/// given a seed; use "anneal" with anneal_seed for a reproducible global search.
 #[pyclass(name = "SolverOptions")]
 pub struct PySolverOptions {
     pub max_iters: u64,
     pub tol: f64,
+    method: MethodName,
     pub init_simplex_size: f64,
     pub nm_max_iters: u64,
     pub nm_tol: f64,
+    pub lbfgs_m: usize,
+    pub lbfgs_max_iters: u64,
+    pub lbfgs_tol_grad: f64,
+    pub lbfgs_tol_cost: f64,
+    pub lbfgs_fd: bool,
+    pub pso_particles: usize,
+    pub pso_max_iters: u64,
+    pub pso_radius: f64,
+    pub anneal_temp: f64,
+    pub anneal_max_iters: u64,
+    pub anneal_step: f64,
+    pub anneal_stall: u64,
+    pub anneal_seed: Option<u64>,
+    update: UpdateScheme,
+    symmetry: Symmetry,
+    pub trace: bool,
+    pub constraints: Vec<LinearConstraint>,
 }
 
-const DEFAULT_OPTIONS: PySolverOptions = PySolverOptions {
+static DEFAULT_OPTIONS: PySolverOptions = PySolverOptions {
     max_iters: 200,
     tol: 1e-6,
+    method: MethodName::NelderMead,
     init_simplex_size: 0.1,
     nm_max_iters: 200,
-    nm_tol: 1e-8
+    nm_tol: 1e-8,
+    lbfgs_m: 7,
+    lbfgs_max_iters: 200,
+    lbfgs_tol_grad: 1e-8,
+    lbfgs_tol_cost: 1e-12,
+    lbfgs_fd: false,
+    pso_particles: 40,
+    pso_max_iters: 100,
+    pso_radius: 2.,
+    anneal_temp: 0.1,
+    anneal_max_iters: 2000,
+    anneal_step: 0.5,
+    anneal_stall: 500,
+    anneal_seed: None,
+    update: UpdateScheme::Jacobi,
+    symmetry: Symmetry::Off,
+    trace: false,
+    constraints: Vec::new(),
 };
 
 #[pymethods]
@@ -610,76 +1200,1118 @@ impl PySolverOptions {
         init_simplex_size = "DEFAULT_OPTIONS.init_simplex_size",
         nm_max_iters = "DEFAULT_OPTIONS.nm_max_iters",
         nm_tol = "DEFAULT_OPTIONS.nm_tol",
+        method = "\"neldermead\"",
+        lbfgs_m = "DEFAULT_OPTIONS.lbfgs_m",
+        lbfgs_max_iters = "DEFAULT_OPTIONS.lbfgs_max_iters",
+        lbfgs_tol_grad = "DEFAULT_OPTIONS.lbfgs_tol_grad",
+        lbfgs_tol_cost = "DEFAULT_OPTIONS.lbfgs_tol_cost",
+        lbfgs_fd = "DEFAULT_OPTIONS.lbfgs_fd",
+        pso_particles = "DEFAULT_OPTIONS.pso_particles",
+        pso_max_iters = "DEFAULT_OPTIONS.pso_max_iters",
+        pso_radius = "DEFAULT_OPTIONS.pso_radius",
+        anneal_temp = "DEFAULT_OPTIONS.anneal_temp",
+        anneal_max_iters = "DEFAULT_OPTIONS.anneal_max_iters",
+        anneal_step = "DEFAULT_OPTIONS.anneal_step",
+        anneal_stall = "DEFAULT_OPTIONS.anneal_stall",
+        anneal_seed = "None",
+        update = "\"jacobi\"",
+        damping = "DEFAULT_UPDATE.0",
+        anderson_m = "DEFAULT_UPDATE.1",
+        symmetry = "\"off\"",
+        trace = "DEFAULT_OPTIONS.trace",
+        constraints = "Vec::new()",
     )]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         max_iters: u64,
         tol: f64,
         init_simplex_size: f64,
         nm_max_iters: u64,
         nm_tol: f64,
-    ) -> Self {
-        PySolverOptions { max_iters, tol, init_simplex_size, nm_max_iters, nm_tol }
+        method: &str,
+        lbfgs_m: usize,
+        lbfgs_max_iters: u64,
+        lbfgs_tol_grad: f64,
+        lbfgs_tol_cost: f64,
+        lbfgs_fd: bool,
+        pso_particles: usize,
+        pso_max_iters: u64,
+        pso_radius: f64,
+        anneal_temp: f64,
+        anneal_max_iters: u64,
+        anneal_step: f64,
+        anneal_stall: u64,
+        anneal_seed: Option<u64>,
+        update: &str,
+        damping: f64,
+        anderson_m: usize,
+        symmetry: &str,
+        trace: bool,
+        constraints: Vec<PyLinearConstraint>,
+    ) -> PyResult<Self> {
+        let method = parse_method(method)?;
+        let update = parse_update(update, damping, anderson_m)?;
+        let symmetry = match symmetry {
+            "off" => Symmetry::Off,
+            "detect" => Symmetry::Detect,
+            "assume" => Symmetry::Assume,
+            _ => return Err(PyException::new_err("symmetry must be one of \"off\", \"detect\" or \"assume\"")),
+        };
+        if anneal_temp <= 0. {
+            return Err(PyException::new_err("anneal_temp must be positive"));
+        }
+        Ok(PySolverOptions {
+            max_iters, tol, method,
+            init_simplex_size, nm_max_iters, nm_tol,
+            lbfgs_m, lbfgs_max_iters, lbfgs_tol_grad, lbfgs_tol_cost, lbfgs_fd,
+            pso_particles, pso_max_iters, pso_radius,
+            anneal_temp, anneal_max_iters, anneal_step, anneal_stall, anneal_seed,
+            update, symmetry,
+            trace,
+            constraints: constraints.into_iter().map(|c| c.0).collect(),
+        })
     }
 
     fn __str__(&self) -> String {
         format!(
-            "SolverOptions:\nmax_iters = {}\ntol = {}\ninit_simplex_size = {}\nnm_max_iters = {}\nnm_tol = {}",
-            self.max_iters, self.tol, self.init_simplex_size, self.nm_max_iters, self.nm_tol
+            "SolverOptions:\nmax_iters = {}\ntol = {}\nmethod = {:?}\ninit_simplex_size = {}\nnm_max_iters = {}\nnm_tol = {}\nlbfgs_m = {}\nlbfgs_max_iters = {}\nlbfgs_tol_grad = {}\nlbfgs_tol_cost = {}\nlbfgs_fd = {}\npso_particles = {}\npso_max_iters = {}\npso_radius = {}\nanneal_temp = {}\nanneal_max_iters = {}\nanneal_step = {}\nanneal_stall = {}\nanneal_seed = {:?}\nupdate = {:?}\nsymmetry = {:?}\ntrace = {}\nconstraints = {}",
+            self.max_iters, self.tol, self.method,
+            self.init_simplex_size, self.nm_max_iters, self.nm_tol,
+            self.lbfgs_m, self.lbfgs_max_iters, self.lbfgs_tol_grad, self.lbfgs_tol_cost, self.lbfgs_fd,
+            self.pso_particles, self.pso_max_iters, self.pso_radius,
+            self.anneal_temp, self.anneal_max_iters, self.anneal_step, self.anneal_stall, self.anneal_seed,
+            self.update, self.symmetry, self.trace, self.constraints.len(),
         )
     }
 }
 
+fn parse_method(method: &str) -> PyResult<MethodName> {
+    match method {
+        "neldermead" => Ok(MethodName::NelderMead),
+        "lbfgs" => Ok(MethodName::Lbfgs),
+        "pso" => Ok(MethodName::ParticleSwarm),
+        "anneal" => Ok(MethodName::Anneal),
+        _ => Err(PyException::new_err("method must be one of \"neldermead\", \"lbfgs\", \"pso\" or \"anneal\"")),
+    }
+}
+
+// damping and anderson_m only apply to the "damped" and "anderson" updates respectively
+const DEFAULT_UPDATE: (f64, usize) = (0.5, 5);
+
+fn parse_update(update: &str, damping: f64, anderson_m: usize) -> PyResult<UpdateScheme> {
+    match update {
+        "jacobi" => Ok(UpdateScheme::Jacobi),
+        "damped" if damping > 0. && damping <= 1. => Ok(UpdateScheme::Damped(damping)),
+        "damped" => Err(PyException::new_err("damping must be in (0, 1]")),
+        "gauss_seidel" => Ok(UpdateScheme::GaussSeidel),
+        "anderson" if anderson_m > 0 => Ok(UpdateScheme::Anderson(anderson_m)),
+        "anderson" => Err(PyException::new_err("anderson_m must be at least 1")),
+        _ => Err(PyException::new_err(
+            "update must be one of \"jacobi\", \"damped\", \"gauss_seidel\" or \"anderson\""
+        )),
+    }
+}
+
+// the chosen optimizer, with settings taken from the keyword arguments for that optimizer
+fn expand_method(method: MethodName, options: &PySolverOptions) -> BestResponseMethod {
+    match method {
+        MethodName::NelderMead => BestResponseMethod::NelderMead(NMOptions {
+            init_simplex_size: options.init_simplex_size,
+            max_iters: options.nm_max_iters,
+            tol: options.nm_tol,
+        }),
+        MethodName::Lbfgs => BestResponseMethod::LBFGS(LBFGSOptions {
+            m: options.lbfgs_m,
+            max_iters: options.lbfgs_max_iters,
+            tol_grad: options.lbfgs_tol_grad,
+            tol_cost: options.lbfgs_tol_cost,
+            finite_differences: options.lbfgs_fd,
+        }),
+        MethodName::ParticleSwarm => BestResponseMethod::ParticleSwarm(PSOOptions {
+            n_particles: options.pso_particles,
+            max_iters: options.pso_max_iters,
+            radius: options.pso_radius,
+        }),
+        MethodName::Anneal => BestResponseMethod::SimulatedAnnealing(AnnealOptions {
+            init_temp: options.anneal_temp,
+            max_iters: options.anneal_max_iters,
+            step_size: options.anneal_step,
+            stall_best: options.anneal_stall,
+            seed: options.anneal_seed,
+        }),
+    }
+}
+
 fn expand_options<S: StrategyType>(init_guess: InitGuess<S>, options: &PySolverOptions) -> SolverOptions<S> {
     SolverOptions {
         init_guess: init_guess,
         max_iters: options.max_iters,
         tol: options.tol,
-        nm_options: NMOptions {
-            init_simplex_size: options.init_simplex_size,
-            max_iters: options.nm_max_iters,
-            tol: options.nm_tol,
-        }
+        method: expand_method(options.method, options),
+        update: options.update,
+        symmetry: options.symmetry,
+        fixed_players: Vec::new(),
+        trace: options.trace,
+        constraints: options.constraints.clone(),
+        progress: None,
+        timeout: None,
+        cancel: None,
     }
 }
 
-type ExpDiscounter_ = ExponentialDiscounter<DefaultPayoff_, DefaultPayoff_>;
+// how often to check for KeyboardInterrupt while a solve is running
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// sets up the progress callback and timeout, returning a token for cancelling the solve
+fn set_solve_control<S: StrategyType>(
+    options: &mut SolverOptions<S>, progress: Option<PyObject>, timeout: Option<f64>,
+) -> PyResult<CancelToken> {
+    options.progress = progress.map(|callback| ProgressCallback::new(move |p: &Progress| {
+        Python::with_gil(|py| {
+            let args = (p.iteration, p.max_change, p.elapsed.as_secs_f64(), p.index);
+            if let Err(e) = callback.call1(py, args) {
+                e.print(py);
+            }
+        })
+    }));
+    options.timeout = match timeout {
+        Some(t) if t.is_nan() || t < 0. => return Err(PyException::new_err("timeout must be non-negative")),
+        Some(t) => Some(Duration::from_secs_f64(t)),
+        None => None,
+    };
+    let cancel = CancelToken::new();
+    options.cancel = Some(cancel.clone());
+    Ok(cancel)
+}
 
-// create python class container "Aggregator" for ExponentialDiscounter
-#[derive(Clone)]
-#[pyclass(name = "Aggregator")]
-pub struct PyExponentialDiscounter(ExpDiscounter_);
+// runs f on another thread with the GIL released, cancelling it on KeyboardInterrupt
+fn run_interruptible<R: Send, F: FnOnce() -> R + Send>(py: Python, cancel: &CancelToken, f: F) -> R {
+    thread::scope(|scope| {
+        let handle = scope.spawn(f);
+        while !py.allow_threads(|| {
+            thread::sleep(POLL_INTERVAL);
+            handle.is_finished()
+        }) {
+            if py.check_signals().is_err() {
+                cancel.cancel();
+            }
+        }
+        match handle.join() {
+            Ok(res) => res,
+            Err(e) => std::panic::resume_unwind(e),
+        }
+    })
+}
 
-impl PyContainer for PyExponentialDiscounter {
-    type Item = ExpDiscounter_;
-    fn get(&self) -> &Self::Item {
-        &self.0
+fn expand_verify_options(
+    options: &PySolverOptions, n_starts: usize, fixed_players: Vec<usize>, seed: Option<u64>,
+) -> VerifyOptions {
+    let default = VerifyOptions::default();
+    VerifyOptions {
+        n_starts,
+        method: expand_method(options.method, options),
+        constraints: options.constraints.clone(),
+        fixed_players,
+        seed: seed.unwrap_or(default.seed),
+        ..default
     }
 }
 
-fn extract_init<'a, S, P>(init: &'a PyAny) -> PyResult<InitGuess<S>>
-where S: StrategyType, P: PyContainer<Item = S> + FromPyObject<'a>
-{
-    match init.extract::<P>() {
-        Ok(s) => Ok(InitGuess::Fixed(s.get().clone())),
-        Err(_) => match init.extract::<usize>() {
-            Ok(n) => Ok(InitGuess::Random(n)),
-            Err(_) => Err(PyException::new_err("init must be either a strategy object or a positive integer"))
+// create python class container for SolveResult
+
+#[pyclass(name = "SolveResult")]
+pub struct PySolveResult {
+    #[pyo3(get)]
+    strategies: PyObject,
+    #[pyo3(get)]
+    converged: bool,
+    #[pyo3(get)]
+    iterations: u64,
+    max_changes: Vec<f64>,
+    #[pyo3(get)]
+    termination_reasons: Vec<String>,
+    payoffs: Array1<f64>,
+    #[pyo3(get)]
+    trace: Py<PyList>,
+    #[pyo3(get)]
+    stop_reason: &'static str,
+    #[pyo3(get)]
+    warnings: Vec<String>,
+    #[pyo3(get)]
+    symmetric: bool,
+}
+
+impl PySolveResult {
+    fn from_result<S, P>(py: Python, res: SolveResult<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        let trace = PyList::new(
+            py,
+            res.trace.into_iter().map(|s| wrap(s).into_py(py))
+        );
+        PySolveResult {
+            strategies: wrap(res.strategies).into_py(py),
+            converged: res.converged,
+            iterations: res.iterations,
+            max_changes: res.max_changes,
+            termination_reasons: res.termination_reasons.iter().map(|r| r.text().to_string()).collect(),
+            payoffs: res.payoffs,
+            trace: trace.into(),
+            stop_reason: match res.stop_reason {
+                StopReason::Converged => "converged",
+                StopReason::MaxItersReached => "max_iters",
+                StopReason::Timeout => "timeout",
+                StopReason::Cancelled => "cancelled",
+            },
+            warnings: res.warnings,
+            symmetric: res.symmetric,
         }
     }
 }
 
-
 #[pymethods]
-impl PyExponentialDiscounter {
-    #[new]
-    fn new(state: PyDefaultPayoff, gammas: PyReadonlyArray1<f64>) -> Self {
-        PyExponentialDiscounter(
-            match ExponentialDiscounter::new(state.0, gammas.as_array().to_owned()) {
-                Ok(discounter) => discounter,
-                Err(e) => panic!("Error when constructing aggregator: {}", e),
-            }
+impl PySolveResult {
+    #[getter]
+    fn max_changes<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        PyArray1::from_slice(py, &self.max_changes)
+    }
+
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.payoffs.clone().into_pyarray(py)
+    }
+
+    fn __str__(&self, py: Python) -> PyResult<String> {
+        let status = if self.converged { "Converged" } else { "Did not converge" };
+        let warnings = self.warnings.iter().map(|w| format!("warning: {}\n", w)).collect::<String>();
+        Ok(format!(
+            "SolveResult:\n{} after {} iterations\n{}payoffs = {}\nstrategies =\n{}",
+            status, self.iterations, warnings, self.payoffs, self.strategies.as_ref(py).str()?
+        ))
+    }
+}
+
+// create python class containers for MultiStartResult and its equilibria
+
+#[pyclass(name = "Equilibrium")]
+pub struct PyEquilibrium {
+    #[pyo3(get)]
+    strategies: PyObject,
+    payoffs: Array1<f64>,
+    #[pyo3(get)]
+    basin_count: usize,
+}
+
+#[pymethods]
+impl PyEquilibrium {
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.payoffs.clone().into_pyarray(py)
+    }
+
+    fn __str__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "Equilibrium (basin count = {}):\npayoffs = {}\nstrategies =\n{}",
+            self.basin_count, self.payoffs, self.strategies.as_ref(py).str()?
+        ))
+    }
+}
+
+#[pyclass(name = "MultiStartResult")]
+pub struct PyMultiStartResult {
+    #[pyo3(get)]
+    equilibria: Py<PyList>,
+    #[pyo3(get)]
+    n_unconverged: usize,
+    #[pyo3(get)]
+    results: Py<PyList>,
+}
+
+impl PyMultiStartResult {
+    fn from_result<S, P>(py: Python, res: MultiStartResult<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        let equilibria = PyList::new(
+            py,
+            res.equilibria.into_iter().map(|eq| PyEquilibrium {
+                strategies: wrap(eq.strategies).into_py(py),
+                payoffs: eq.payoffs,
+                basin_count: eq.basin_count,
+            }.into_py(py))
+        );
+        let results = PyList::new(
+            py,
+            res.results.into_iter().map(|r| PySolveResult::from_result(py, r, wrap).into_py(py))
+        );
+        PyMultiStartResult {
+            equilibria: equilibria.into(),
+            n_unconverged: res.n_unconverged,
+            results: results.into(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyMultiStartResult {
+    #[getter]
+    fn is_unique(&self, py: Python) -> bool {
+        self.equilibria.as_ref(py).len() == 1
+    }
+
+    fn __str__(&self, py: Python) -> String {
+        format!(
+            "MultiStartResult:\n{} distinct equilibria\n{} unconverged starts",
+            self.equilibria.as_ref(py).len(), self.n_unconverged
+        )
+    }
+}
+
+fn expand_multistart_options<S: StrategyType>(
+    t: usize, n_starts: usize, seed: u64, init_sigma: f64, cluster_tol: f64, options: &PySolverOptions
+) -> MultiStartOptions<S> {
+    MultiStartOptions {
+        solver_options: expand_options(InitGuess::random(t), options),
+        n_starts,
+        seed,
+        init_sigma,
+        cluster_tol,
+        ..MultiStartOptions::new(t, n_starts, seed)
+    }
+}
+
+const DEFAULT_MULTISTART: (usize, u64, f64, f64) = (16, 0, 1.0, 1e-3);
+
+// create python class container for EquilibriumCheck
+
+#[pyclass(name = "EquilibriumCheck")]
+pub struct PyEquilibriumCheck {
+    payoffs: Array1<f64>,
+    epsilons: Array1<f64>,
+    #[pyo3(get)]
+    deviations: Py<PyList>,
+}
+
+impl PyEquilibriumCheck {
+    fn from_check<S, P>(py: Python, check: EquilibriumCheck<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        let deviations = PyList::new(
+            py,
+            check.deviations.into_iter().map(|s| wrap(s).into_py(py))
+        );
+        PyEquilibriumCheck {
+            payoffs: check.payoffs,
+            epsilons: check.epsilons,
+            deviations: deviations.into(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyEquilibriumCheck {
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.payoffs.clone().into_pyarray(py)
+    }
+
+    #[getter]
+    fn epsilons<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.epsilons.clone().into_pyarray(py)
+    }
+
+    #[getter]
+    fn epsilon(&self) -> f64 {
+        self.epsilons.fold(0., |acc, x| f64::max(acc, *x))
+    }
+
+    fn __str__(&self) -> String {
+        format!(
+            "EquilibriumCheck:\npayoffs = {}\nepsilons = {}\nepsilon = {}",
+            self.payoffs, self.epsilons, self.epsilon()
+        )
+    }
+}
+
+// create python class container for StackelbergResult
+
+#[pyclass(name = "StackelbergResult")]
+pub struct PyStackelbergResult {
+    #[pyo3(get)]
+    strategies: PyObject,
+    #[pyo3(get)]
+    leader_strategies: PyObject,
+    #[pyo3(get)]
+    follower_strategies: PyObject,
+    #[pyo3(get)]
+    leaders: Vec<usize>,
+    #[pyo3(get)]
+    followers: Vec<usize>,
+    #[pyo3(get)]
+    converged: bool,
+    payoffs: Array1<f64>,
+    first_mover_advantage: Array1<f64>,
+    #[pyo3(get)]
+    nash: Py<PySolveResult>,
+}
+
+impl PyStackelbergResult {
+    fn from_result<S, P>(py: Python, res: StackelbergResult<S>, wrap: fn(S) -> P) -> PyResult<Self>
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        Ok(PyStackelbergResult {
+            leader_strategies: wrap(res.leader_strategies()).into_py(py),
+            follower_strategies: wrap(res.follower_strategies()).into_py(py),
+            first_mover_advantage: res.first_mover_advantage(),
+            strategies: wrap(res.strategies).into_py(py),
+            leaders: res.leaders,
+            followers: res.followers,
+            converged: res.converged,
+            payoffs: res.payoffs,
+            nash: Py::new(py, PySolveResult::from_result(py, res.nash, wrap))?,
+        })
+    }
+}
+
+#[pymethods]
+impl PyStackelbergResult {
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.payoffs.clone().into_pyarray(py)
+    }
+
+    #[getter]
+    fn first_mover_advantage<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.first_mover_advantage.clone().into_pyarray(py)
+    }
+
+    fn __str__(&self, py: Python) -> PyResult<String> {
+        let status = if self.converged { "Converged" } else { "Did not converge" };
+        Ok(format!(
+            "StackelbergResult:\n{} with leaders {:?}\npayoffs = {}\nfirst_mover_advantage = {}\nstrategies =\n{}",
+            status, self.leaders, self.payoffs, self.first_mover_advantage, self.strategies.as_ref(py).str()?
+        ))
+    }
+}
+
+// defaults for leader_max_iters, leader_tol
+const DEFAULT_STACKELBERG: (u64, f64) = (100, 1e-4);
+
+fn expand_stackelberg_options<S: StrategyType>(
+    init_guess: InitGuess<S>, leaders: Vec<usize>, options: &PySolverOptions,
+    leader_max_iters: u64, leader_tol: f64, leader_method: &str,
+) -> PyResult<StackelbergOptions<S>> {
+    Ok(StackelbergOptions {
+        leaders,
+        solver_options: expand_options(init_guess, options),
+        leader_max_iters,
+        leader_tol,
+        leader_method: expand_method(parse_method(leader_method)?, options),
+    })
+}
+
+// create python class containers for planner results
+
+#[pyclass(name = "PlannerResult")]
+pub struct PyPlannerResult {
+    #[pyo3(get)]
+    strategies: PyObject,
+    payoffs: Array1<f64>,
+    #[pyo3(get)]
+    welfare: f64,
+    #[pyo3(get)]
+    termination_reason: String,
+    #[pyo3(get)]
+    converged: bool,
+}
+
+impl PyPlannerResult {
+    fn from_result<S, P>(py: Python, res: PlannerResult<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        PyPlannerResult {
+            converged: res.converged(),
+            termination_reason: res.termination_reason.text().to_string(),
+            strategies: wrap(res.strategies).into_py(py),
+            payoffs: res.payoffs,
+            welfare: res.welfare,
+        }
+    }
+}
+
+#[pymethods]
+impl PyPlannerResult {
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.payoffs.clone().into_pyarray(py)
+    }
+
+    fn __str__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "PlannerResult:\nstopped: {}\nwelfare = {}\npayoffs = {}\nstrategies =\n{}",
+            self.termination_reason, self.welfare, self.payoffs, self.strategies.as_ref(py).str()?
+        ))
+    }
+}
+
+#[pyclass(name = "OutcomeSummary")]
+#[derive(Clone)]
+pub struct PyOutcomeSummary(OutcomeSummary);
+
+#[pymethods]
+impl PyOutcomeSummary {
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.0.payoffs.clone().into_pyarray(py)
+    }
+
+    #[getter]
+    fn welfare(&self) -> f64 {
+        self.0.welfare
+    }
+
+    #[getter]
+    fn safety_spending<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.0.safety_spending.clone().into_pyarray(py)
+    }
+
+    #[getter]
+    fn total_safety_spending(&self) -> f64 {
+        self.0.total_safety_spending()
+    }
+
+    #[getter]
+    fn disaster_proba<'py>(&self, py: Python<'py>) -> Option<&'py PyArray1<f64>> {
+        self.0.disaster_proba.clone().map(|x| x.into_pyarray(py))
+    }
+
+    fn __str__(&self) -> String {
+        format!("OutcomeSummary: {}", self.0)
+    }
+}
+
+#[pyclass(name = "PlannerReport")]
+pub struct PyPlannerReport {
+    #[pyo3(get)]
+    planner_strategies: PyObject,
+    #[pyo3(get)]
+    nash_strategies: PyObject,
+    #[pyo3(get)]
+    planner: PyOutcomeSummary,
+    #[pyo3(get)]
+    nash: PyOutcomeSummary,
+    #[pyo3(get)]
+    price_of_anarchy: f64,
+    #[pyo3(get)]
+    welfare_loss: f64,
+}
+
+impl PyPlannerReport {
+    fn from_report<S, P>(py: Python, report: PlannerReport<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        PyPlannerReport {
+            price_of_anarchy: report.price_of_anarchy(),
+            welfare_loss: report.welfare_loss(),
+            planner_strategies: wrap(report.planner_strategies).into_py(py),
+            nash_strategies: wrap(report.nash_strategies).into_py(py),
+            planner: PyOutcomeSummary(report.planner),
+            nash: PyOutcomeSummary(report.nash),
+        }
+    }
+}
+
+#[pymethods]
+impl PyPlannerReport {
+    fn __str__(&self) -> String {
+        format!(
+            "PlannerReport:\nplanner: {}\nnash: {}\nprice_of_anarchy = {}\nwelfare_loss = {}",
+            self.planner.0, self.nash.0, self.price_of_anarchy, self.welfare_loss
+        )
+    }
+}
+
+fn expand_planner_options<S: StrategyType>(
+    init_guess: InitGuess<S>, options: &PySolverOptions, weights: Option<PyReadonlyArray1<f64>>, method: &str,
+) -> PyResult<PlannerOptions<S>> {
+    let solver_options = expand_options(init_guess, options);
+    Ok(PlannerOptions {
+        init_guess: solver_options.init_guess,
+        weights: weights.map(|w| w.as_array().to_owned()),
+        method: expand_method(parse_method(method)?, options),
+    })
+}
+
+// create python class container for NewtonResult
+
+#[pyclass(name = "NewtonResult")]
+pub struct PyNewtonResult {
+    #[pyo3(get)]
+    strategies: PyObject,
+    payoffs: Array1<f64>,
+    #[pyo3(get)]
+    converged: bool,
+    #[pyo3(get)]
+    iterations: u64,
+    #[pyo3(get)]
+    residuals: Vec<f64>,
+    #[pyo3(get)]
+    second_order: Vec<bool>,
+}
+
+impl PyNewtonResult {
+    fn from_result<S, P>(py: Python, res: NewtonResult<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        PyNewtonResult {
+            converged: res.converged,
+            iterations: res.iterations,
+            residuals: res.residuals,
+            second_order: res.second_order,
+            strategies: wrap(res.strategies).into_py(py),
+            payoffs: res.payoffs,
+        }
+    }
+}
+
+#[pymethods]
+impl PyNewtonResult {
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.payoffs.clone().into_pyarray(py)
+    }
+
+    fn is_local_equilibrium(&self) -> bool {
+        self.converged && self.second_order.iter().all(|&b| b)
+    }
+
+    fn __str__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "NewtonResult:\nconverged: {}\niterations: {}\nsecond_order: {:?}\npayoffs = {}\nstrategies =\n{}",
+            self.converged, self.iterations, self.second_order, self.payoffs, self.strategies.as_ref(py).str()?
+        ))
+    }
+}
+
+const DEFAULT_NEWTON: (u64, f64) = (100, 1e-6);
+
+fn expand_newton_options<S: StrategyType>(
+    init_guess: InitGuess<S>, max_iters: u64, tol: f64, jacobian: &str, fixed_players: Option<Vec<usize>>,
+) -> PyResult<NewtonOptions<S>> {
+    let jacobian = match jacobian {
+        "fd" => NewtonJacobian::FiniteDifferences,
+        "broyden" => NewtonJacobian::Broyden,
+        _ => return Err(PyException::new_err("jacobian must be one of \"fd\" or \"broyden\"")),
+    };
+    Ok(NewtonOptions {
+        init_guess,
+        max_iters,
+        tol,
+        jacobian,
+        fixed_players: fixed_players.unwrap_or_default(),
+        ..NewtonOptions::random_init(0)
+    })
+}
+
+// create python class containers for infinite-horizon results
+
+#[pyclass(name = "StationaryResult")]
+pub struct PyStationaryResult {
+    #[pyo3(get)]
+    strategies: PyObject,
+    payoffs: Array1<f64>,
+    #[pyo3(get)]
+    converged: bool,
+    #[pyo3(get)]
+    iterations: u64,
+}
+
+impl PyStationaryResult {
+    fn from_result<S, P>(py: Python, res: StationaryResult<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        PyStationaryResult {
+            converged: res.converged,
+            iterations: res.iterations,
+            strategies: wrap(res.strategies).into_py(py),
+            payoffs: res.payoffs,
+        }
+    }
+}
+
+#[pymethods]
+impl PyStationaryResult {
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.payoffs.clone().into_pyarray(py)
+    }
+
+    fn __str__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "StationaryResult:\nconverged: {}\niterations: {}\npayoffs = {}\nstrategies =\n{}",
+            self.converged, self.iterations, self.payoffs, self.strategies.as_ref(py).str()?
+        ))
+    }
+}
+
+#[pyclass(name = "HorizonResult")]
+pub struct PyHorizonResult {
+    #[pyo3(get)]
+    result: Py<PySolveResult>,
+    #[pyo3(get)]
+    horizons: Vec<usize>,
+    #[pyo3(get)]
+    changes: Vec<f64>,
+    #[pyo3(get)]
+    converged: bool,
+}
+
+impl PyHorizonResult {
+    fn from_result<S, P>(py: Python, res: HorizonResult<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        PyHorizonResult {
+            horizons: res.horizons,
+            changes: res.changes,
+            converged: res.converged,
+            result: Py::new(py, PySolveResult::from_result(py, res.result, wrap)).unwrap(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyHorizonResult {
+    fn __str__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "HorizonResult:\nconverged: {}\nhorizons: {:?}\nchanges: {:?}\nresult =\n{}",
+            self.converged, self.horizons, self.changes, self.result.as_ref(py).str()?
+        ))
+    }
+}
+
+const DEFAULT_HORIZON: (usize, f64) = (100, 1e-4);
+
+fn expand_horizon_options<S: StrategyType>(
+    init_guess: InitGuess<S>, k: usize, options: &PySolverOptions, step: Option<usize>, max_t: usize, tol: f64,
+) -> HorizonOptions<S> {
+    let t = init_guess.t();
+    HorizonOptions {
+        solver_options: expand_options(init_guess, options),
+        k,
+        step: step.unwrap_or(t),
+        max_t,
+        tol,
+    }
+}
+
+// create python class container for BestResponseResult
+
+#[pyclass(name = "BestResponseResult")]
+pub struct PyBestResponseResult {
+    response: Array2<f64>,
+    #[pyo3(get)]
+    strategies: PyObject,
+    #[pyo3(get)]
+    payoff: f64,
+    #[pyo3(get)]
+    gain: f64,
+    #[pyo3(get)]
+    termination_reason: String,
+}
+
+impl PyBestResponseResult {
+    fn from_result<S, P>(py: Python, res: BestResponseResult<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        PyBestResponseResult {
+            response: res.response,
+            strategies: wrap(res.strategies).into_py(py),
+            payoff: res.payoff,
+            gain: res.gain,
+            termination_reason: res.termination_reason.text().to_string(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyBestResponseResult {
+    #[getter]
+    fn response<'py>(&self, py: Python<'py>) -> &'py PyArray<f64, Ix2> {
+        self.response.clone().into_pyarray(py)
+    }
+
+    fn __str__(&self) -> String {
+        format!(
+            "BestResponseResult:\nstopped: {}\npayoff = {}\ngain = {}\nresponse =\n{}",
+            self.termination_reason, self.payoff, self.gain, self.response
+        )
+    }
+}
+
+fn expand_best_response_options(options: &PySolverOptions, n_starts: usize, seed: u64) -> BestResponseOptions {
+    BestResponseOptions {
+        method: expand_method(options.method, options),
+        constraints: options.constraints.clone(),
+        n_starts,
+        seed,
+        ..BestResponseOptions::default()
+    }
+}
+
+fn extract_param_axes(axes: Vec<(Option<usize>, usize, PyReadonlyArray1<f64>)>) -> Vec<ParamAxis> {
+    axes.into_iter().map(|(t, m, values)| ParamAxis::new(t, m, values.as_array().to_owned())).collect()
+}
+
+// create python class container for Sensitivity
+
+#[pyclass(name = "Sensitivity")]
+pub struct PySensitivity(Sensitivity);
+
+#[pymethods]
+impl PySensitivity {
+    #[getter]
+    fn param(&self) -> String {
+        self.0.param.clone()
+    }
+
+    #[getter]
+    fn value(&self) -> f64 {
+        self.0.value
+    }
+
+    #[getter]
+    fn derivative<'py>(&self, py: Python<'py>) -> &'py PyArray<f64, Ix3> {
+        self.0.derivative.clone().into_pyarray(py)
+    }
+
+    #[getter]
+    fn method(&self) -> &'static str {
+        match self.0.method {
+            SensitivityMethod::ImplicitFunction => "ift",
+            SensitivityMethod::FiniteDifference => "fd",
+        }
+    }
+
+    fn __str__(&self) -> String {
+        format!("Sensitivity: {}", self.0)
+    }
+}
+
+fn expand_sensitivity_options<S: StrategyType>(
+    t: usize, options: &PySolverOptions, method: &str, fallback: bool,
+) -> PyResult<SensitivityOptions<S>> {
+    let method = match method {
+        "ift" => SensitivityMethod::ImplicitFunction,
+        "fd" => SensitivityMethod::FiniteDifference,
+        _ => return Err(PyException::new_err("method must be either \"ift\" or \"fd\"")),
+    };
+    Ok(SensitivityOptions {
+        method,
+        fallback,
+        solver_options: expand_options(InitGuess::random(t), options),
+        ..SensitivityOptions::new(t)
+    })
+}
+
+// create python class container for ContinuationResult
+
+#[pyclass(name = "ContinuationResult")]
+pub struct PyContinuationResult {
+    // one result per requested point
+    #[pyo3(get)]
+    results: Vec<Py<PySolveResult>>,
+    // every point visited along the branch, for plotting
+    positions: Array1<f64>,
+    #[pyo3(get)]
+    strategies: Vec<PyObject>,
+    payoffs: Array2<f64>,
+    #[pyo3(get)]
+    converged: Vec<bool>,
+    #[pyo3(get)]
+    folds: Vec<f64>,
+}
+
+impl PyContinuationResult {
+    fn from_result<S, P>(py: Python, res: ContinuationResult<S>, wrap: fn(S) -> P) -> PyResult<Self>
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        let n = res.trace.first().map_or(0, |p| p.payoffs.len());
+        Ok(PyContinuationResult {
+            positions: Array1::from(res.positions()),
+            payoffs: Array2::from_shape_fn((res.trace.len(), n), |(k, i)| res.trace[k].payoffs[i]),
+            converged: res.trace.iter().map(|p| p.converged).collect(),
+            folds: res.folds(),
+            strategies: res.trace.into_iter().map(|p| wrap(p.strategies).into_py(py)).collect(),
+            results: res.results.into_iter()
+                .map(|r| Py::new(py, PySolveResult::from_result(py, r, wrap)))
+                .collect::<PyResult<_>>()?,
+        })
+    }
+}
+
+#[pymethods]
+impl PyContinuationResult {
+    #[getter]
+    fn positions<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.positions.clone().into_pyarray(py)
+    }
+
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray<f64, Ix2> {
+        self.payoffs.clone().into_pyarray(py)
+    }
+
+    fn __str__(&self) -> String {
+        format!(
+            "ContinuationResult:\n{} requested points, {} points on the branch\nfolds at {:?}",
+            self.results.len(), self.positions.len(), self.folds
         )
     }
+}
+
+const DEFAULT_CONTINUATION: (f64, f64) = (0.5, 1. / 64.);
+
+fn expand_continuation_options<S: StrategyType>(
+    init_guess: InitGuess<S>, options: &PySolverOptions, max_jump: f64, min_step: f64,
+) -> ContinuationOptions<S> {
+    ContinuationOptions {
+        solver_options: expand_options(init_guess, options),
+        max_jump,
+        min_step,
+        ..ContinuationOptions::new(0)
+    }
+}
+
+// create python class container for MarkovResult
+
+#[pyclass(name = "MarkovResult")]
+pub struct PyMarkovResult {
+    coefs: Array4<f64>,
+    #[pyo3(get)]
+    path: PyObject,
+    #[pyo3(get)]
+    converged: bool,
+    #[pyo3(get)]
+    iterations: u64,
+    max_changes: Vec<f64>,
+    #[pyo3(get)]
+    n_unconverged: usize,
+    payoffs: Array1<f64>,
+}
+
+impl PyMarkovResult {
+    fn from_result<S, P>(py: Python, res: MarkovResult<S>, wrap: fn(S) -> P) -> Self
+    where S: StrategyType, P: IntoPy<PyObject>
+    {
+        PyMarkovResult {
+            coefs: res.strategies.coefs().to_owned(),
+            path: wrap(res.path).into_py(py),
+            converged: res.converged,
+            iterations: res.iterations,
+            max_changes: res.max_changes,
+            n_unconverged: res.n_unconverged,
+            payoffs: res.payoffs,
+        }
+    }
+}
+
+#[pymethods]
+impl PyMarkovResult {
+    // policy coefficients, indexed as [t, i, param, coef] with coef 0 the intercept
+    #[getter]
+    fn coefs<'py>(&self, py: Python<'py>) -> &'py PyArray<f64, Ix4> {
+        self.coefs.clone().into_pyarray(py)
+    }
+
+    #[getter]
+    fn max_changes<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        PyArray1::from_slice(py, &self.max_changes)
+    }
+
+    #[getter]
+    fn payoffs<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.payoffs.clone().into_pyarray(py)
+    }
+
+    fn __str__(&self, py: Python) -> PyResult<String> {
+        let status = if self.converged { "Converged" } else { "Did not converge" };
+        Ok(format!(
+            "MarkovResult:\n{} after {} iterations\npayoffs = {}\npath =\n{}",
+            status, self.iterations, self.payoffs, self.path.as_ref(py).str()?
+        ))
+    }
+}
+
+// defaults for max_iters, tol, sample_spread
+const DEFAULT_MARKOV: (u64, f64, f64) = (50, 1e-4, 0.1);
+
+type ExpDiscounter_ = ExponentialDiscounter<DefaultPayoff_, DefaultPayoff_>;
+
+// create python class container "Aggregator" for ExponentialDiscounter
+#[derive(Clone)]
+#[pyclass(name = "Aggregator")]
+pub struct PyExponentialDiscounter(ExpDiscounter_);
+
+impl PyContainer for PyExponentialDiscounter {
+    type Item = ExpDiscounter_;
+    fn get(&self) -> &Self::Item {
+        &self.0
+    }
+}
+
+fn extract_init<'a, S, P>(init: &'a PyAny) -> PyResult<InitGuess<S>>
+where S: StrategyType, P: PyContainer<Item = S> + FromPyObject<'a>
+{
+    match init.extract::<P>() {
+        Ok(s) => Ok(InitGuess::Fixed(s.get().clone())),
+        Err(_) => match init.extract::<usize>() {
+            Ok(n) => Ok(InitGuess::random(n)),
+            Err(_) => Err(PyException::new_err("init must be either a strategy object or a positive integer"))
+        }
+    }
+}
+
+// same as extract_init, but a random init guess is drawn using the given seed
+fn extract_seeded_init<'a, S, P>(init: &'a PyAny, seed: Option<u64>) -> PyResult<InitGuess<S>>
+where S: StrategyType, P: PyContainer<Item = S> + FromPyObject<'a>
+{
+    let init_guess = extract_init::<S, P>(init)?;
+    Ok(match (init_guess, seed) {
+        (InitGuess::Random { t, .. }, Some(seed)) => InitGuess::seeded(t, seed),
+        (init_guess, _) => init_guess,
+    })
+}
+
+fn extract_terminal(terminal: Option<&PyAny>) -> PyResult<Option<TerminalValue>> {
+    let terminal = match terminal {
+        Some(terminal) if !terminal.is_none() => terminal,
+        _ => return Ok(None),
+    };
+    if let Ok(name) = terminal.extract::<&str>() {
+        return match name {
+            "stationary" => Ok(Some(TerminalValue::Stationary)),
+            _ => Err(PyException::new_err("terminal must be None, \"stationary\" or an array of values")),
+        };
+    }
+    let values = terminal.extract::<PyReadonlyArray1<f64>>()?;
+    Ok(Some(TerminalValue::Fixed(values.as_array().to_owned())))
+}
+
+
+#[pymethods]
+impl PyExponentialDiscounter {
+    // terminal is None, "stationary" or an array of each player's value after the last period
+    #[new]
+    #[args(terminal = "None")]
+    fn new(state: PyDefaultPayoff, gammas: PyReadonlyArray1<f64>, terminal: Option<&PyAny>) -> PyResult<Self> {
+        let discounter = match ExponentialDiscounter::new(state.0, gammas.as_array().to_owned()) {
+            Ok(discounter) => discounter,
+            Err(e) => panic!("Error when constructing aggregator: {}", e),
+        };
+        Ok(PyExponentialDiscounter(match extract_terminal(terminal)? {
+            Some(terminal) => discounter.with_terminal_value(terminal).map_err(PyException::new_err)?,
+            None => discounter,
+        }))
+    }
 
     #[staticmethod]
     fn expand_from<'py>(
@@ -709,13 +2341,199 @@ impl PyExponentialDiscounter {
         self.0.u(&strategies.0).into_pyarray(py)
     }
 
-    #[args(options = "&DEFAULT_OPTIONS")]
-    fn solve(&self, init: &PyAny, options: &PySolverOptions) -> PyResult<PyStrategies> {
-        let init_guess: InitGuess<Strategies> = extract_init::<_, PyStrategies>(init)?;
-        let solver_options = expand_options(init_guess, &options);
-        let res = solve(&self.0, &solver_options);
+    // progress is called as progress(iteration, max_change, elapsed_seconds, index) after each iteration;
+    // on timeout or KeyboardInterrupt, the profile found so far is returned;
+    // seed makes a random init guess reproducible
+    #[args(options = "&DEFAULT_OPTIONS", progress = "None", timeout = "None", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        progress: Option<PyObject>, timeout: Option<f64>, seed: Option<u64>, fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<PySolveResult> {
+        let init_guess: InitGuess<Strategies> = extract_seeded_init::<_, PyStrategies>(init, seed)?;
+        let mut solver_options = expand_options(init_guess, &options);
+        solver_options.fixed_players = fixed_players.unwrap_or_default();
+        let cancel = set_solve_control(&mut solver_options, progress, timeout)?;
+        let res = run_interruptible(py, &cancel, || solve(&self.0, &solver_options));
         match res {
-            Ok(res) => Ok(PyStrategies(res)),
+            Ok(res) => Ok(PySolveResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        n_starts = "DEFAULT_MULTISTART.0",
+        seed = "DEFAULT_MULTISTART.1",
+        init_sigma = "DEFAULT_MULTISTART.2",
+        cluster_tol = "DEFAULT_MULTISTART.3",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_multistart(
+        &self, py: Python, t: usize, options: &PySolverOptions,
+        n_starts: usize, seed: u64, init_sigma: f64, cluster_tol: f64,
+    ) -> PyResult<PyMultiStartResult> {
+        let ms_options = expand_multistart_options(t, n_starts, seed, init_sigma, cluster_tol, options);
+        match solve_multistart(&self.0, &ms_options) {
+            Ok(res) => Ok(PyMultiStartResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        leader_max_iters = "DEFAULT_STACKELBERG.0",
+        leader_tol = "DEFAULT_STACKELBERG.1",
+        leader_method = "\"neldermead\"",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_stackelberg(
+        &self, py: Python, init: &PyAny, leaders: Vec<usize>, options: &PySolverOptions,
+        leader_max_iters: u64, leader_tol: f64, leader_method: &str,
+    ) -> PyResult<PyStackelbergResult> {
+        let init_guess = extract_init::<_, PyStrategies>(init)?;
+        let st_options = expand_stackelberg_options(
+            init_guess, leaders, options, leader_max_iters, leader_tol, leader_method
+        )?;
+        match solve_stackelberg(&self.0, &st_options) {
+            Ok(res) => PyStackelbergResult::from_result(py, res, PyStrategies),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(options = "&DEFAULT_OPTIONS", weights = "None", method = "\"lbfgs\"")]
+    fn solve_planner(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        weights: Option<PyReadonlyArray1<f64>>, method: &str,
+    ) -> PyResult<PyPlannerResult> {
+        let init_guess = extract_init::<_, PyStrategies>(init)?;
+        let planner_options = expand_planner_options(init_guess, options, weights, method)?;
+        match solve_planner(&self.0, &planner_options) {
+            Ok(res) => Ok(PyPlannerResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // the planner uses method, while the Nash solution uses options.method
+    #[args(options = "&DEFAULT_OPTIONS", weights = "None", method = "\"lbfgs\"")]
+    fn planner_report(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        weights: Option<PyReadonlyArray1<f64>>, method: &str,
+    ) -> PyResult<PyPlannerReport> {
+        let init_guess = extract_init::<_, PyStrategies>(init)?;
+        let solver_options = expand_options(init_guess.clone(), options);
+        let planner_options = expand_planner_options(init_guess, options, weights, method)?;
+        match planner_report(&self.0, &planner_options, &solver_options) {
+            Ok(report) => Ok(PyPlannerReport::from_report(py, report, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // time-invariant equilibrium of the infinitely repeated game; payoffs are u / (1 - gamma)
+    #[args(options = "&DEFAULT_OPTIONS", seed = "None")]
+    fn solve_stationary(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions, seed: Option<u64>,
+    ) -> PyResult<PyStationaryResult> {
+        let init_guess = extract_seeded_init::<_, PyStrategies>(init, seed)?;
+        match solve_stationary(&self.0, &expand_options(init_guess, options)) {
+            Ok(res) => Ok(PyStationaryResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves all players' first-order conditions at once; jacobian is "fd" or "broyden"
+    #[args(max_iters = "DEFAULT_NEWTON.0", tol = "DEFAULT_NEWTON.1", jacobian = "\"fd\"", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_newton(
+        &self, py: Python, init: &PyAny, max_iters: u64, tol: f64, jacobian: &str, seed: Option<u64>,
+        fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<PyNewtonResult> {
+        let init_guess = extract_seeded_init::<_, PyStrategies>(init, seed)?;
+        let newton_options = expand_newton_options(init_guess, max_iters, tol, jacobian, fixed_players)?;
+        match solve_newton(&self.0, &newton_options) {
+            Ok(res) => Ok(PyNewtonResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves with horizons extended by step (default: the initial horizon) until the first k periods stop changing
+    #[args(options = "&DEFAULT_OPTIONS", step = "None", max_t = "DEFAULT_HORIZON.0", tol = "DEFAULT_HORIZON.1", seed = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_horizon(
+        &self, py: Python, init: &PyAny, k: usize, options: &PySolverOptions,
+        step: Option<usize>, max_t: usize, tol: f64, seed: Option<u64>,
+    ) -> PyResult<PyHorizonResult> {
+        let init_guess = extract_seeded_init::<_, PyStrategies>(init, seed)?;
+        let horizon_options = expand_horizon_options(init_guess, k, options, step, max_t, tol);
+        match solve_extending_horizon(&self.0, &horizon_options) {
+            Ok(res) => Ok(PyHorizonResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // searches from i's strategy in strategies, plus n_starts - 1 random draws
+    #[args(options = "&DEFAULT_OPTIONS", n_starts = "1", seed = "0")]
+    fn best_response(
+        &self, py: Python, i: usize, strategies: &PyStrategies, options: &PySolverOptions, n_starts: usize, seed: u64,
+    ) -> PyResult<PyBestResponseResult> {
+        let br_options = expand_best_response_options(options, n_starts, seed);
+        match best_response(&self.0, i, &strategies.0, &br_options) {
+            Ok(res) => Ok(PyBestResponseResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // axes is a list of (t, m, values), where t = None varies param m in every period at once;
+    // the result has one dimension per axis, in the same order
+    fn payoff_grid<'py>(
+        &self, py: Python<'py>, i: usize, strategies: &PyStrategies, axes: Vec<(Option<usize>, usize, PyReadonlyArray1<f64>)>,
+    ) -> PyResult<&'py PyArrayDyn<f64>> {
+        let param_axes = extract_param_axes(axes);
+        match payoff_grid(&self.0, i, &strategies.0, &param_axes) {
+            Ok(grid) => Ok(grid.into_pyarray(py)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // params are named by component field, e.g. "risk_func.theta[0]" or "gammas[1]"
+    #[args(options = "&DEFAULT_OPTIONS", method = "\"ift\"", fallback = "true")]
+    fn sensitivities(
+        &self, strategies: &PyStrategies, params: Vec<&str>, options: &PySolverOptions,
+        method: &str, fallback: bool,
+    ) -> PyResult<Vec<PySensitivity>> {
+        let se_options = expand_sensitivity_options(strategies.0.t(), options, method, fallback)?;
+        match sensitivities(&self.0, &strategies.0, &params, &se_options) {
+            Ok(res) => Ok(res.into_iter().map(PySensitivity).collect()),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves at each of the values of param in turn, starting each from the previous solution
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        max_jump = "DEFAULT_CONTINUATION.0",
+        min_step = "DEFAULT_CONTINUATION.1",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_path(
+        &self, py: Python, init: &PyAny, param: &str, values: Vec<f64>, options: &PySolverOptions,
+        max_jump: f64, min_step: f64,
+    ) -> PyResult<PyContinuationResult> {
+        let init_guess = extract_init::<_, PyStrategies>(init)?;
+        let co_options = expand_continuation_options(init_guess, options, max_jump, min_step);
+        match solve_path(&self.0, param, &values, &co_options) {
+            Ok(res) => PyContinuationResult::from_result(py, res, PyStrategies),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(options = "&DEFAULT_OPTIONS", n_starts = "VerifyOptions::default().n_starts", fixed_players = "None", seed = "None")]
+    fn check(&self, py: Python, strategies: &PyStrategies, options: &PySolverOptions, n_starts: usize,
+        fixed_players: Option<Vec<usize>>, seed: Option<u64>,
+    ) -> PyResult<PyEquilibriumCheck> {
+        let verify_options = expand_verify_options(options, n_starts, fixed_players.unwrap_or_default(), seed);
+        match verify_equilibrium(&self.0, &strategies.0, &verify_options) {
+            Ok(check) => Ok(PyEquilibriumCheck::from_check(py, check, PyStrategies)),
             Err(e) => Err(PyException::new_err(format!("{}", e))),
         }
     }
@@ -736,9 +2554,15 @@ impl PyContainer for PyInvestExpDiscounter {
 
 #[pymethods]
 impl PyInvestExpDiscounter {
+    // terminal is None, "stationary" or an array of each player's value after the last period
     #[new]
-    fn new(state0: PyInvestPayoff, gammas: PyReadonlyArray1<f64>) -> Self {
-        PyInvestExpDiscounter(InvestExpDiscounter::new(state0.0, gammas.as_array().to_owned()).unwrap())
+    #[args(terminal = "None")]
+    fn new(state0: PyInvestPayoff, gammas: PyReadonlyArray1<f64>, terminal: Option<&PyAny>) -> PyResult<Self> {
+        let discounter = InvestExpDiscounter::new(state0.0, gammas.as_array().to_owned()).unwrap();
+        Ok(PyInvestExpDiscounter(match extract_terminal(terminal)? {
+            Some(terminal) => discounter.with_terminal_value(terminal).map_err(PyException::new_err)?,
+            None => discounter,
+        }))
     }
 
     #[staticmethod]
@@ -769,25 +2593,224 @@ impl PyInvestExpDiscounter {
         self.0.u(&strategies.0).into_pyarray(py)
     }
 
-    #[args(options = "&DEFAULT_OPTIONS")]
-    fn solve(&self, init: &PyAny, options: &PySolverOptions) -> PyResult<PyInvestStrategies> {
-        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
-        let solver_options = expand_options(init_guess, &options);
-        let res = solve(&self.0, &solver_options);
+    // progress is called as progress(iteration, max_change, elapsed_seconds, index) after each iteration;
+    // on timeout or KeyboardInterrupt, the profile found so far is returned;
+    // seed makes a random init guess reproducible
+    #[args(options = "&DEFAULT_OPTIONS", progress = "None", timeout = "None", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        progress: Option<PyObject>, timeout: Option<f64>, seed: Option<u64>, fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<PySolveResult> {
+        let init_guess = extract_seeded_init::<_, PyInvestStrategies>(init, seed)?;
+        let mut solver_options = expand_options(init_guess, &options);
+        solver_options.fixed_players = fixed_players.unwrap_or_default();
+        let cancel = set_solve_control(&mut solver_options, progress, timeout)?;
+        let res = run_interruptible(py, &cancel, || solve(&self.0, &solver_options));
         match res {
-            Ok(res) => Ok(PyInvestStrategies(res)),
+            Ok(res) => Ok(PySolveResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        n_starts = "DEFAULT_MULTISTART.0",
+        seed = "DEFAULT_MULTISTART.1",
+        init_sigma = "DEFAULT_MULTISTART.2",
+        cluster_tol = "DEFAULT_MULTISTART.3",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_multistart(
+        &self, py: Python, t: usize, options: &PySolverOptions,
+        n_starts: usize, seed: u64, init_sigma: f64, cluster_tol: f64,
+    ) -> PyResult<PyMultiStartResult> {
+        let ms_options = expand_multistart_options(t, n_starts, seed, init_sigma, cluster_tol, options);
+        match solve_multistart(&self.0, &ms_options) {
+            Ok(res) => Ok(PyMultiStartResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        leader_max_iters = "DEFAULT_STACKELBERG.0",
+        leader_tol = "DEFAULT_STACKELBERG.1",
+        leader_method = "\"neldermead\"",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_stackelberg(
+        &self, py: Python, init: &PyAny, leaders: Vec<usize>, options: &PySolverOptions,
+        leader_max_iters: u64, leader_tol: f64, leader_method: &str,
+    ) -> PyResult<PyStackelbergResult> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let st_options = expand_stackelberg_options(
+            init_guess, leaders, options, leader_max_iters, leader_tol, leader_method
+        )?;
+        match solve_stackelberg(&self.0, &st_options) {
+            Ok(res) => PyStackelbergResult::from_result(py, res, PyInvestStrategies),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(options = "&DEFAULT_OPTIONS", weights = "None", method = "\"lbfgs\"")]
+    fn solve_planner(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        weights: Option<PyReadonlyArray1<f64>>, method: &str,
+    ) -> PyResult<PyPlannerResult> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let planner_options = expand_planner_options(init_guess, options, weights, method)?;
+        match solve_planner(&self.0, &planner_options) {
+            Ok(res) => Ok(PyPlannerResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // the planner uses method, while the Nash solution uses options.method
+    #[args(options = "&DEFAULT_OPTIONS", weights = "None", method = "\"lbfgs\"")]
+    fn planner_report(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        weights: Option<PyReadonlyArray1<f64>>, method: &str,
+    ) -> PyResult<PyPlannerReport> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let solver_options = expand_options(init_guess.clone(), options);
+        let planner_options = expand_planner_options(init_guess, options, weights, method)?;
+        match planner_report(&self.0, &planner_options, &solver_options) {
+            Ok(report) => Ok(PyPlannerReport::from_report(py, report, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves all players' first-order conditions at once; jacobian is "fd" or "broyden"
+    #[args(max_iters = "DEFAULT_NEWTON.0", tol = "DEFAULT_NEWTON.1", jacobian = "\"fd\"", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_newton(
+        &self, py: Python, init: &PyAny, max_iters: u64, tol: f64, jacobian: &str, seed: Option<u64>,
+        fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<PyNewtonResult> {
+        let init_guess = extract_seeded_init::<_, PyInvestStrategies>(init, seed)?;
+        let newton_options = expand_newton_options(init_guess, max_iters, tol, jacobian, fixed_players)?;
+        match solve_newton(&self.0, &newton_options) {
+            Ok(res) => Ok(PyNewtonResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves with horizons extended by step (default: the initial horizon) until the first k periods stop changing
+    #[args(options = "&DEFAULT_OPTIONS", step = "None", max_t = "DEFAULT_HORIZON.0", tol = "DEFAULT_HORIZON.1", seed = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_horizon(
+        &self, py: Python, init: &PyAny, k: usize, options: &PySolverOptions,
+        step: Option<usize>, max_t: usize, tol: f64, seed: Option<u64>,
+    ) -> PyResult<PyHorizonResult> {
+        let init_guess = extract_seeded_init::<_, PyInvestStrategies>(init, seed)?;
+        let horizon_options = expand_horizon_options(init_guess, k, options, step, max_t, tol);
+        match solve_extending_horizon(&self.0, &horizon_options) {
+            Ok(res) => Ok(PyHorizonResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // searches from i's strategy in strategies, plus n_starts - 1 random draws
+    #[args(options = "&DEFAULT_OPTIONS", n_starts = "1", seed = "0")]
+    fn best_response(
+        &self, py: Python, i: usize, strategies: &PyInvestStrategies, options: &PySolverOptions, n_starts: usize, seed: u64,
+    ) -> PyResult<PyBestResponseResult> {
+        let br_options = expand_best_response_options(options, n_starts, seed);
+        match best_response(&self.0, i, &strategies.0, &br_options) {
+            Ok(res) => Ok(PyBestResponseResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // axes is a list of (t, m, values), where t = None varies param m in every period at once;
+    // the result has one dimension per axis, in the same order
+    fn payoff_grid<'py>(
+        &self, py: Python<'py>, i: usize, strategies: &PyInvestStrategies, axes: Vec<(Option<usize>, usize, PyReadonlyArray1<f64>)>,
+    ) -> PyResult<&'py PyArrayDyn<f64>> {
+        let param_axes = extract_param_axes(axes);
+        match payoff_grid(&self.0, i, &strategies.0, &param_axes) {
+            Ok(grid) => Ok(grid.into_pyarray(py)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // params are named by component field, e.g. "risk_func.theta[0]" or "gammas[1]"
+    #[args(options = "&DEFAULT_OPTIONS", method = "\"ift\"", fallback = "true")]
+    fn sensitivities(
+        &self, strategies: &PyInvestStrategies, params: Vec<&str>, options: &PySolverOptions,
+        method: &str, fallback: bool,
+    ) -> PyResult<Vec<PySensitivity>> {
+        let se_options = expand_sensitivity_options(strategies.0.t(), options, method, fallback)?;
+        match sensitivities(&self.0, &strategies.0, &params, &se_options) {
+            Ok(res) => Ok(res.into_iter().map(PySensitivity).collect()),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves at each of the values of param in turn, starting each from the previous solution
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        max_jump = "DEFAULT_CONTINUATION.0",
+        min_step = "DEFAULT_CONTINUATION.1",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_path(
+        &self, py: Python, init: &PyAny, param: &str, values: Vec<f64>, options: &PySolverOptions,
+        max_jump: f64, min_step: f64,
+    ) -> PyResult<PyContinuationResult> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let co_options = expand_continuation_options(init_guess, options, max_jump, min_step);
+        match solve_path(&self.0, param, &values, &co_options) {
+            Ok(res) => PyContinuationResult::from_result(py, res, PyInvestStrategies),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(options = "&DEFAULT_OPTIONS", n_starts = "VerifyOptions::default().n_starts", fixed_players = "None", seed = "None")]
+    fn check(&self, py: Python, strategies: &PyInvestStrategies, options: &PySolverOptions, n_starts: usize,
+        fixed_players: Option<Vec<usize>>, seed: Option<u64>,
+    ) -> PyResult<PyEquilibriumCheck> {
+        let verify_options = expand_verify_options(options, n_starts, fixed_players.unwrap_or_default(), seed);
+        match verify_equilibrium(&self.0, &strategies.0, &verify_options) {
+            Ok(check) => Ok(PyEquilibriumCheck::from_check(py, check, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        max_iters = "DEFAULT_MARKOV.0",
+        tol = "DEFAULT_MARKOV.1",
+        sample_spread = "DEFAULT_MARKOV.2",
+    )]
+    fn solve_markov(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        max_iters: u64, tol: f64, sample_spread: f64,
+    ) -> PyResult<PyMarkovResult> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let markov_options = MarkovOptions {
+            solver_options: expand_options(init_guess, options),
+            max_iters,
+            tol,
+            sample_spread,
+            ..MarkovOptions::new(0)
+        };
+        match solve_markov(&self.0, &markov_options) {
+            Ok(res) => Ok(PyMarkovResult::from_result(py, res, PyInvestStrategies)),
             Err(e) => Err(PyException::new_err(format!("{}", e))),
         }
     }
 }
 
+// the CSF is always a no-win variant, see AnyCSF::no_win
 type MaybeNoWinPayoff_<A, C> = DefaultPayoff<
     A,
     DefaultProd,
-    WinnerOnlyRisk,
-    MaybeNoWinCSF,
-    LinearReward,
-    ConstantDisasterCost,
+    AnyRiskFunc,
+    AnyCSF,
+    AnyRewardFunc,
+    AnyDisasterCost,
     C,
 >;
 
@@ -795,10 +2818,10 @@ type EndOnWinAggregator_ = EndsOnContestWin<
     Actions,
     Strategies,
     DefaultProd,
-    WinnerOnlyRisk,
-    MaybeNoWinCSF,
-    LinearReward,
-    ConstantDisasterCost,
+    AnyRiskFunc,
+    AnyCSF,
+    AnyRewardFunc,
+    AnyDisasterCost,
     FixedUnitCost,
     MaybeNoWinPayoff_<Actions, FixedUnitCost>,
     ExponentialDiscounter<MaybeNoWinPayoff_<Actions, FixedUnitCost>, MaybeNoWinPayoff_<Actions, FixedUnitCost>>,
@@ -810,12 +2833,18 @@ pub struct PyEndOnWinAggregator(EndOnWinAggregator_);
 #[pymethods]
 impl PyEndOnWinAggregator {
     #[new]
-    fn new(child: PyExponentialDiscounter) -> Self {
-        // replace CSF with MaybeNoWinCSF
+    fn new(child: PyExponentialDiscounter) -> PyResult<Self> {
+        // payoffs stop once someone wins, so a continuation value after the last period isn't supported
+        if child.0.terminal.is_some() {
+            return Err(PyException::new_err(
+                "End-on-win aggregators don't support terminal values; create the child without one"
+            ));
+        }
+        // replace CSF with its no-win variant
         let payoff_func = DefaultPayoff::new(
             child.0.state.prod_func,
             child.0.state.risk_func,
-            MaybeNoWinCSF::default(),
+            child.0.state.csf.no_win(),
             child.0.state.reward_func,
             child.0.state.disaster_cost,
             child.0.state.cost_func,
@@ -823,7 +2852,7 @@ impl PyEndOnWinAggregator {
         let new_child = ExponentialDiscounter::new(
             payoff_func, child.0.gammas
         ).unwrap();
-        PyEndOnWinAggregator(EndOnWinAggregator_::new(new_child))
+        Ok(PyEndOnWinAggregator(EndOnWinAggregator_::new(new_child)))
     }
 
     fn u_i(&self, i: usize, strategies: &PyStrategies) -> f64 {
@@ -838,13 +2867,187 @@ impl PyEndOnWinAggregator {
         self.0.probas(&strategies.0).into_pyarray(py)
     }
 
-    #[args(options = "&DEFAULT_OPTIONS")]
-    fn solve(&self, init: &PyAny, options: &PySolverOptions) -> PyResult<PyStrategies> {
-        let init_guess = extract_init::<_, PyStrategies>(init)?;
-        let solver_options = expand_options(init_guess, &options);
-        let res = solve(&self.0, &solver_options);
+    // progress is called as progress(iteration, max_change, elapsed_seconds, index) after each iteration;
+    // on timeout or KeyboardInterrupt, the profile found so far is returned;
+    // seed makes a random init guess reproducible
+    #[args(options = "&DEFAULT_OPTIONS", progress = "None", timeout = "None", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        progress: Option<PyObject>, timeout: Option<f64>, seed: Option<u64>, fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<PySolveResult> {
+        let init_guess = extract_seeded_init::<_, PyStrategies>(init, seed)?;
+        let mut solver_options = expand_options(init_guess, &options);
+        solver_options.fixed_players = fixed_players.unwrap_or_default();
+        let cancel = set_solve_control(&mut solver_options, progress, timeout)?;
+        let res = run_interruptible(py, &cancel, || solve(&self.0, &solver_options));
         match res {
-            Ok(res) => Ok(PyStrategies(res)),
+            Ok(res) => Ok(PySolveResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        n_starts = "DEFAULT_MULTISTART.0",
+        seed = "DEFAULT_MULTISTART.1",
+        init_sigma = "DEFAULT_MULTISTART.2",
+        cluster_tol = "DEFAULT_MULTISTART.3",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_multistart(
+        &self, py: Python, t: usize, options: &PySolverOptions,
+        n_starts: usize, seed: u64, init_sigma: f64, cluster_tol: f64,
+    ) -> PyResult<PyMultiStartResult> {
+        let ms_options = expand_multistart_options(t, n_starts, seed, init_sigma, cluster_tol, options);
+        match solve_multistart(&self.0, &ms_options) {
+            Ok(res) => Ok(PyMultiStartResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        leader_max_iters = "DEFAULT_STACKELBERG.0",
+        leader_tol = "DEFAULT_STACKELBERG.1",
+        leader_method = "\"neldermead\"",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_stackelberg(
+        &self, py: Python, init: &PyAny, leaders: Vec<usize>, options: &PySolverOptions,
+        leader_max_iters: u64, leader_tol: f64, leader_method: &str,
+    ) -> PyResult<PyStackelbergResult> {
+        let init_guess = extract_init::<_, PyStrategies>(init)?;
+        let st_options = expand_stackelberg_options(
+            init_guess, leaders, options, leader_max_iters, leader_tol, leader_method
+        )?;
+        match solve_stackelberg(&self.0, &st_options) {
+            Ok(res) => PyStackelbergResult::from_result(py, res, PyStrategies),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(options = "&DEFAULT_OPTIONS", weights = "None", method = "\"lbfgs\"")]
+    fn solve_planner(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        weights: Option<PyReadonlyArray1<f64>>, method: &str,
+    ) -> PyResult<PyPlannerResult> {
+        let init_guess = extract_init::<_, PyStrategies>(init)?;
+        let planner_options = expand_planner_options(init_guess, options, weights, method)?;
+        match solve_planner(&self.0, &planner_options) {
+            Ok(res) => Ok(PyPlannerResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // the planner uses method, while the Nash solution uses options.method
+    #[args(options = "&DEFAULT_OPTIONS", weights = "None", method = "\"lbfgs\"")]
+    fn planner_report(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        weights: Option<PyReadonlyArray1<f64>>, method: &str,
+    ) -> PyResult<PyPlannerReport> {
+        let init_guess = extract_init::<_, PyStrategies>(init)?;
+        let solver_options = expand_options(init_guess.clone(), options);
+        let planner_options = expand_planner_options(init_guess, options, weights, method)?;
+        match planner_report(&self.0, &planner_options, &solver_options) {
+            Ok(report) => Ok(PyPlannerReport::from_report(py, report, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves all players' first-order conditions at once; jacobian is "fd" or "broyden"
+    #[args(max_iters = "DEFAULT_NEWTON.0", tol = "DEFAULT_NEWTON.1", jacobian = "\"fd\"", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_newton(
+        &self, py: Python, init: &PyAny, max_iters: u64, tol: f64, jacobian: &str, seed: Option<u64>,
+        fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<PyNewtonResult> {
+        let init_guess = extract_seeded_init::<_, PyStrategies>(init, seed)?;
+        let newton_options = expand_newton_options(init_guess, max_iters, tol, jacobian, fixed_players)?;
+        match solve_newton(&self.0, &newton_options) {
+            Ok(res) => Ok(PyNewtonResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves with horizons extended by step (default: the initial horizon) until the first k periods stop changing
+    #[args(options = "&DEFAULT_OPTIONS", step = "None", max_t = "DEFAULT_HORIZON.0", tol = "DEFAULT_HORIZON.1", seed = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_horizon(
+        &self, py: Python, init: &PyAny, k: usize, options: &PySolverOptions,
+        step: Option<usize>, max_t: usize, tol: f64, seed: Option<u64>,
+    ) -> PyResult<PyHorizonResult> {
+        let init_guess = extract_seeded_init::<_, PyStrategies>(init, seed)?;
+        let horizon_options = expand_horizon_options(init_guess, k, options, step, max_t, tol);
+        match solve_extending_horizon(&self.0, &horizon_options) {
+            Ok(res) => Ok(PyHorizonResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // searches from i's strategy in strategies, plus n_starts - 1 random draws
+    #[args(options = "&DEFAULT_OPTIONS", n_starts = "1", seed = "0")]
+    fn best_response(
+        &self, py: Python, i: usize, strategies: &PyStrategies, options: &PySolverOptions, n_starts: usize, seed: u64,
+    ) -> PyResult<PyBestResponseResult> {
+        let br_options = expand_best_response_options(options, n_starts, seed);
+        match best_response(&self.0, i, &strategies.0, &br_options) {
+            Ok(res) => Ok(PyBestResponseResult::from_result(py, res, PyStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // axes is a list of (t, m, values), where t = None varies param m in every period at once;
+    // the result has one dimension per axis, in the same order
+    fn payoff_grid<'py>(
+        &self, py: Python<'py>, i: usize, strategies: &PyStrategies, axes: Vec<(Option<usize>, usize, PyReadonlyArray1<f64>)>,
+    ) -> PyResult<&'py PyArrayDyn<f64>> {
+        let param_axes = extract_param_axes(axes);
+        match payoff_grid(&self.0, i, &strategies.0, &param_axes) {
+            Ok(grid) => Ok(grid.into_pyarray(py)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // params are named by component field, e.g. "risk_func.theta[0]" or "gammas[1]"
+    #[args(options = "&DEFAULT_OPTIONS", method = "\"ift\"", fallback = "true")]
+    fn sensitivities(
+        &self, strategies: &PyStrategies, params: Vec<&str>, options: &PySolverOptions,
+        method: &str, fallback: bool,
+    ) -> PyResult<Vec<PySensitivity>> {
+        let se_options = expand_sensitivity_options(strategies.0.t(), options, method, fallback)?;
+        match sensitivities(&self.0, &strategies.0, &params, &se_options) {
+            Ok(res) => Ok(res.into_iter().map(PySensitivity).collect()),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves at each of the values of param in turn, starting each from the previous solution
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        max_jump = "DEFAULT_CONTINUATION.0",
+        min_step = "DEFAULT_CONTINUATION.1",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_path(
+        &self, py: Python, init: &PyAny, param: &str, values: Vec<f64>, options: &PySolverOptions,
+        max_jump: f64, min_step: f64,
+    ) -> PyResult<PyContinuationResult> {
+        let init_guess = extract_init::<_, PyStrategies>(init)?;
+        let co_options = expand_continuation_options(init_guess, options, max_jump, min_step);
+        match solve_path(&self.0, param, &values, &co_options) {
+            Ok(res) => PyContinuationResult::from_result(py, res, PyStrategies),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(options = "&DEFAULT_OPTIONS", n_starts = "VerifyOptions::default().n_starts", fixed_players = "None", seed = "None")]
+    fn check(&self, py: Python, strategies: &PyStrategies, options: &PySolverOptions, n_starts: usize,
+        fixed_players: Option<Vec<usize>>, seed: Option<u64>,
+    ) -> PyResult<PyEquilibriumCheck> {
+        let verify_options = expand_verify_options(options, n_starts, fixed_players.unwrap_or_default(), seed);
+        match verify_equilibrium(&self.0, &strategies.0, &verify_options) {
+            Ok(check) => Ok(PyEquilibriumCheck::from_check(py, check, PyStrategies)),
             Err(e) => Err(PyException::new_err(format!("{}", e))),
         }
     }
@@ -854,10 +3057,10 @@ type InvestEndOnWinAggregator_ = EndsOnContestWin<
     InvestActions,
     InvestStrategies,
     DefaultProd,
-    WinnerOnlyRisk,
-    MaybeNoWinCSF,
-    LinearReward,
-    ConstantDisasterCost,
+    AnyRiskFunc,
+    AnyCSF,
+    AnyRewardFunc,
+    AnyDisasterCost,
     FixedInvestCost,
     MaybeNoWinPayoff_<InvestActions, FixedInvestCost>,
     InvestExpDiscounter<MaybeNoWinPayoff_<InvestActions, FixedInvestCost>>,
@@ -869,12 +3072,18 @@ pub struct PyInvestEndOnWinAggregator(InvestEndOnWinAggregator_);
 #[pymethods]
 impl PyInvestEndOnWinAggregator {
     #[new]
-    fn new(child: PyInvestExpDiscounter) -> Self {
-        // replace CSF with MaybeNoWinCSF
+    fn new(child: PyInvestExpDiscounter) -> PyResult<Self> {
+        // payoffs stop once someone wins, so a continuation value after the last period isn't supported
+        if child.0.terminal.is_some() {
+            return Err(PyException::new_err(
+                "End-on-win aggregators don't support terminal values; create the child without one"
+            ));
+        }
+        // replace CSF with its no-win variant
         let payoff_func = DefaultPayoff::new(
             child.0.state0.prod_func,
             child.0.state0.risk_func,
-            MaybeNoWinCSF::default(),
+            child.0.state0.csf.no_win(),
             child.0.state0.reward_func,
             child.0.state0.disaster_cost,
             child.0.state0.cost_func,
@@ -882,7 +3091,7 @@ impl PyInvestEndOnWinAggregator {
         let new_child = InvestExpDiscounter::new(
             payoff_func, child.0.gammas
         ).unwrap();
-        PyInvestEndOnWinAggregator(InvestEndOnWinAggregator_::new(new_child))
+        Ok(PyInvestEndOnWinAggregator(InvestEndOnWinAggregator_::new(new_child)))
     }
 
     fn u_i(&self, i: usize, strategies: &PyInvestStrategies) -> f64 {
@@ -897,13 +3106,176 @@ impl PyInvestEndOnWinAggregator {
         self.0.probas(&strategies.0).into_pyarray(py)
     }
 
-    #[args(options = "&DEFAULT_OPTIONS")]
-    fn solve(&self, init: &PyAny, options: &PySolverOptions) -> PyResult<PyInvestStrategies> {
-        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
-        let solver_options = expand_options(init_guess, &options);
-        let res = solve(&self.0, &solver_options);
+    // progress is called as progress(iteration, max_change, elapsed_seconds, index) after each iteration;
+    // on timeout or KeyboardInterrupt, the profile found so far is returned;
+    // seed makes a random init guess reproducible
+    #[args(options = "&DEFAULT_OPTIONS", progress = "None", timeout = "None", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        progress: Option<PyObject>, timeout: Option<f64>, seed: Option<u64>, fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<PySolveResult> {
+        let init_guess = extract_seeded_init::<_, PyInvestStrategies>(init, seed)?;
+        let mut solver_options = expand_options(init_guess, &options);
+        solver_options.fixed_players = fixed_players.unwrap_or_default();
+        let cancel = set_solve_control(&mut solver_options, progress, timeout)?;
+        let res = run_interruptible(py, &cancel, || solve(&self.0, &solver_options));
         match res {
-            Ok(res) => Ok(PyInvestStrategies(res)),
+            Ok(res) => Ok(PySolveResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        n_starts = "DEFAULT_MULTISTART.0",
+        seed = "DEFAULT_MULTISTART.1",
+        init_sigma = "DEFAULT_MULTISTART.2",
+        cluster_tol = "DEFAULT_MULTISTART.3",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_multistart(
+        &self, py: Python, t: usize, options: &PySolverOptions,
+        n_starts: usize, seed: u64, init_sigma: f64, cluster_tol: f64,
+    ) -> PyResult<PyMultiStartResult> {
+        let ms_options = expand_multistart_options(t, n_starts, seed, init_sigma, cluster_tol, options);
+        match solve_multistart(&self.0, &ms_options) {
+            Ok(res) => Ok(PyMultiStartResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        leader_max_iters = "DEFAULT_STACKELBERG.0",
+        leader_tol = "DEFAULT_STACKELBERG.1",
+        leader_method = "\"neldermead\"",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_stackelberg(
+        &self, py: Python, init: &PyAny, leaders: Vec<usize>, options: &PySolverOptions,
+        leader_max_iters: u64, leader_tol: f64, leader_method: &str,
+    ) -> PyResult<PyStackelbergResult> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let st_options = expand_stackelberg_options(
+            init_guess, leaders, options, leader_max_iters, leader_tol, leader_method
+        )?;
+        match solve_stackelberg(&self.0, &st_options) {
+            Ok(res) => PyStackelbergResult::from_result(py, res, PyInvestStrategies),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    #[args(options = "&DEFAULT_OPTIONS", weights = "None", method = "\"lbfgs\"")]
+    fn solve_planner(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        weights: Option<PyReadonlyArray1<f64>>, method: &str,
+    ) -> PyResult<PyPlannerResult> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let planner_options = expand_planner_options(init_guess, options, weights, method)?;
+        match solve_planner(&self.0, &planner_options) {
+            Ok(res) => Ok(PyPlannerResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // the planner uses method, while the Nash solution uses options.method
+    #[args(options = "&DEFAULT_OPTIONS", weights = "None", method = "\"lbfgs\"")]
+    fn planner_report(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions,
+        weights: Option<PyReadonlyArray1<f64>>, method: &str,
+    ) -> PyResult<PyPlannerReport> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let solver_options = expand_options(init_guess.clone(), options);
+        let planner_options = expand_planner_options(init_guess, options, weights, method)?;
+        match planner_report(&self.0, &planner_options, &solver_options) {
+            Ok(report) => Ok(PyPlannerReport::from_report(py, report, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves all players' first-order conditions at once; jacobian is "fd" or "broyden"
+    #[args(max_iters = "DEFAULT_NEWTON.0", tol = "DEFAULT_NEWTON.1", jacobian = "\"fd\"", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_newton(
+        &self, py: Python, init: &PyAny, max_iters: u64, tol: f64, jacobian: &str, seed: Option<u64>,
+        fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<PyNewtonResult> {
+        let init_guess = extract_seeded_init::<_, PyInvestStrategies>(init, seed)?;
+        let newton_options = expand_newton_options(init_guess, max_iters, tol, jacobian, fixed_players)?;
+        match solve_newton(&self.0, &newton_options) {
+            Ok(res) => Ok(PyNewtonResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves with horizons extended by step (default: the initial horizon) until the first k periods stop changing
+    #[args(options = "&DEFAULT_OPTIONS", step = "None", max_t = "DEFAULT_HORIZON.0", tol = "DEFAULT_HORIZON.1", seed = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_horizon(
+        &self, py: Python, init: &PyAny, k: usize, options: &PySolverOptions,
+        step: Option<usize>, max_t: usize, tol: f64, seed: Option<u64>,
+    ) -> PyResult<PyHorizonResult> {
+        let init_guess = extract_seeded_init::<_, PyInvestStrategies>(init, seed)?;
+        let horizon_options = expand_horizon_options(init_guess, k, options, step, max_t, tol);
+        match solve_extending_horizon(&self.0, &horizon_options) {
+            Ok(res) => Ok(PyHorizonResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // searches from i's strategy in strategies, plus n_starts - 1 random draws
+    #[args(options = "&DEFAULT_OPTIONS", n_starts = "1", seed = "0")]
+    fn best_response(
+        &self, py: Python, i: usize, strategies: &PyInvestStrategies, options: &PySolverOptions, n_starts: usize, seed: u64,
+    ) -> PyResult<PyBestResponseResult> {
+        let br_options = expand_best_response_options(options, n_starts, seed);
+        match best_response(&self.0, i, &strategies.0, &br_options) {
+            Ok(res) => Ok(PyBestResponseResult::from_result(py, res, PyInvestStrategies)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // axes is a list of (t, m, values), where t = None varies param m in every period at once;
+    // the result has one dimension per axis, in the same order
+    fn payoff_grid<'py>(
+        &self, py: Python<'py>, i: usize, strategies: &PyInvestStrategies, axes: Vec<(Option<usize>, usize, PyReadonlyArray1<f64>)>,
+    ) -> PyResult<&'py PyArrayDyn<f64>> {
+        let param_axes = extract_param_axes(axes);
+        match payoff_grid(&self.0, i, &strategies.0, &param_axes) {
+            Ok(grid) => Ok(grid.into_pyarray(py)),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // params are named by component field, e.g. "risk_func.theta[0]" or "gammas[1]"
+    #[args(options = "&DEFAULT_OPTIONS", method = "\"ift\"", fallback = "true")]
+    fn sensitivities(
+        &self, strategies: &PyInvestStrategies, params: Vec<&str>, options: &PySolverOptions,
+        method: &str, fallback: bool,
+    ) -> PyResult<Vec<PySensitivity>> {
+        let se_options = expand_sensitivity_options(strategies.0.t(), options, method, fallback)?;
+        match sensitivities(&self.0, &strategies.0, &params, &se_options) {
+            Ok(res) => Ok(res.into_iter().map(PySensitivity).collect()),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
+
+    // solves at each of the values of param in turn, starting each from the previous solution
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        max_jump = "DEFAULT_CONTINUATION.0",
+        min_step = "DEFAULT_CONTINUATION.1",
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn solve_path(
+        &self, py: Python, init: &PyAny, param: &str, values: Vec<f64>, options: &PySolverOptions,
+        max_jump: f64, min_step: f64,
+    ) -> PyResult<PyContinuationResult> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let co_options = expand_continuation_options(init_guess, options, max_jump, min_step);
+        match solve_path(&self.0, param, &values, &co_options) {
+            Ok(res) => PyContinuationResult::from_result(py, res, PyInvestStrategies),
             Err(e) => Err(PyException::new_err(format!("{}", e))),
         }
     }
@@ -923,20 +3295,45 @@ impl PyScenario {
         }
     }
 
-    #[args(options = "&DEFAULT_OPTIONS")]
-    fn solve<'py>(&self, py: Python<'py>, init: &PyAny, options: &PySolverOptions) -> PyResult<&'py PyList> {
-        let init_guess = extract_init::<_, PyStrategies>(init)?;
-        let solver_options = expand_options(init_guess, &options);
-        match self.0.solve(&solver_options) {
+    // see the aggregators' solve; the timeout applies to the whole scenario,
+    // and each aggregator gets its own seed derived from seed
+    #[args(options = "&DEFAULT_OPTIONS", progress = "None", timeout = "None", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve<'py>(
+        &self, py: Python<'py>, init: &PyAny, options: &PySolverOptions,
+        progress: Option<PyObject>, timeout: Option<f64>, seed: Option<u64>, fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<&'py PyList> {
+        let init_guess = extract_seeded_init::<_, PyStrategies>(init, seed)?;
+        let mut solver_options = expand_options(init_guess, &options);
+        solver_options.fixed_players = fixed_players.unwrap_or_default();
+        let cancel = set_solve_control(&mut solver_options, progress, timeout)?;
+        match run_interruptible(py, &cancel, || self.0.solve(&solver_options)) {
             Ok(res) => {
-                let iter = res.into_iter().map(|s|
-                    PyCell::new(py, PyStrategies(s)).unwrap()
+                let iter = res.into_iter().map(|r|
+                    PyCell::new(py, PySolveResult::from_result(py, r, PyStrategies)).unwrap()
                 );
                 Ok(PyList::new(py, iter))
             },
             Err(e) => Err(PyException::new_err(format!("{}", e)))
         }
     }
+
+    // solves the aggregators in order, starting each from the previous solution
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        max_jump = "DEFAULT_CONTINUATION.0",
+        min_step = "DEFAULT_CONTINUATION.1",
+    )]
+    fn solve_continuation(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions, max_jump: f64, min_step: f64,
+    ) -> PyResult<PyContinuationResult> {
+        let init_guess = extract_init::<_, PyStrategies>(init)?;
+        let co_options = expand_continuation_options(init_guess, options, max_jump, min_step);
+        match self.0.solve_continuation(&co_options) {
+            Ok(res) => PyContinuationResult::from_result(py, res, PyStrategies),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
 }
 
 #[pyclass(name = "InvestScenario")]
@@ -953,18 +3350,43 @@ impl PyInvestScenario {
         }
     }
 
-    #[args(options = "&DEFAULT_OPTIONS")]
-    fn solve<'py>(&self, py: Python<'py>, init: &PyAny, options: &PySolverOptions) -> PyResult<&'py PyList> {
-        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
-        let solver_options = expand_options(init_guess, &options);
-        match self.0.solve(&solver_options) {
+    // see the aggregators' solve; the timeout applies to the whole scenario,
+    // and each aggregator gets its own seed derived from seed
+    #[args(options = "&DEFAULT_OPTIONS", progress = "None", timeout = "None", seed = "None", fixed_players = "None")]
+    #[allow(clippy::too_many_arguments)]
+    fn solve<'py>(
+        &self, py: Python<'py>, init: &PyAny, options: &PySolverOptions,
+        progress: Option<PyObject>, timeout: Option<f64>, seed: Option<u64>, fixed_players: Option<Vec<usize>>,
+    ) -> PyResult<&'py PyList> {
+        let init_guess = extract_seeded_init::<_, PyInvestStrategies>(init, seed)?;
+        let mut solver_options = expand_options(init_guess, &options);
+        solver_options.fixed_players = fixed_players.unwrap_or_default();
+        let cancel = set_solve_control(&mut solver_options, progress, timeout)?;
+        match run_interruptible(py, &cancel, || self.0.solve(&solver_options)) {
             Ok(res) => {
-                let iter = res.into_iter().map(|s|
-                    PyCell::new(py, PyInvestStrategies(s)).unwrap()
+                let iter = res.into_iter().map(|r|
+                    PyCell::new(py, PySolveResult::from_result(py, r, PyInvestStrategies)).unwrap()
                 );
                 Ok(PyList::new(py, iter))
             },
             Err(e) => Err(PyException::new_err(format!("{}", e)))
         }
     }
+
+    // solves the aggregators in order, starting each from the previous solution
+    #[args(
+        options = "&DEFAULT_OPTIONS",
+        max_jump = "DEFAULT_CONTINUATION.0",
+        min_step = "DEFAULT_CONTINUATION.1",
+    )]
+    fn solve_continuation(
+        &self, py: Python, init: &PyAny, options: &PySolverOptions, max_jump: f64, min_step: f64,
+    ) -> PyResult<PyContinuationResult> {
+        let init_guess = extract_init::<_, PyInvestStrategies>(init)?;
+        let co_options = expand_continuation_options(init_guess, options, max_jump, min_step);
+        match self.0.solve_continuation(&co_options) {
+            Ok(res) => PyContinuationResult::from_result(py, res, PyInvestStrategies),
+            Err(e) => Err(PyException::new_err(format!("{}", e))),
+        }
+    }
 }
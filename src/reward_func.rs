@@ -1,17 +1,23 @@
-use numpy::ndarray::{Array, ArrayView, Ix1};
+use numpy::ndarray::{Array, ArrayView, Ix1, Ix2};
 use std::fmt;
 
+use crate::params::{Params, index_mut, parse_index, unknown_param};
+use crate::utils::fd_jacobian;
+
 pub trait RewardFunc: Clone + Send + Sync {
-    fn win_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64;
-    fn lose_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64;
+    // reward to player i if player j wins the contest;
+    // the reward types below index their parameter vectors by the winner j, so j gets the win terms
+    // of j and every other player gets the lose terms of j (MatrixReward is indexed [i, j] directly)
+    fn reward_ij(&self, i: usize, j: usize, p: ArrayView<f64, Ix1>) -> f64;
+    // rewards to player i for each possible winner
     fn reward(&self, i: usize, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
-        Array::from_iter((0..p.len()).map(|j| {
-            if j == i {
-                self.win_i(j, p)
-            } else {
-                self.lose_i(j, p)
-            }
-        }))
+        Array::from_iter((0..p.len()).map(|j| self.reward_ij(i, j, p)))
+    }
+
+    // jacobian of reward(i, p) w.r.t. p, indexed as [j, k] = d reward(i, p)_j / d p_k
+    // defaults to finite differences; override when an analytic form is available
+    fn dreward(&self, i: usize, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        fd_jacobian(|p_| self.reward(i, p_), p)
     }
 
     fn n(&self) -> usize;
@@ -48,15 +54,29 @@ impl LinearReward {
             Array::zeros(n),
         ).unwrap()
     }
-}
 
-impl RewardFunc for LinearReward {
-    fn win_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
+    pub fn win_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
         self.win_a[i] + self.win_b[i] * p[i]
     }
-    fn lose_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
+    pub fn lose_i(&self, i: usize, p: ArrayView<f64, Ix1>) -> f64 {
         self.lose_a[i] + self.lose_b[i] * p[i]
     }
+}
+
+impl RewardFunc for LinearReward {
+    fn reward_ij(&self, i: usize, j: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        if j == i {
+            self.win_i(j, p)
+        } else {
+            self.lose_i(j, p)
+        }
+    }
+
+    fn dreward(&self, i: usize, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        Array::from_diag(&Array::from_iter((0..p.len()).map(|j| {
+            if j == i { self.win_b[j] } else { self.lose_b[j] }
+        })))
+    }
 
     fn n(&self) -> usize {
         self.n
@@ -71,3 +91,303 @@ impl fmt::Display for LinearReward {
         )
     }
 }
+
+fn validate_lengths(arrays: &[&Array<f64, Ix1>]) -> Result<usize, &'static str> {
+    let n = arrays[0].len();
+    if arrays.iter().any(|x| x.len() != n) {
+        return Err("When creating reward function: All input arrays must have the same length");
+    }
+    Ok(n)
+}
+
+// Box-Cox transform of 1 + p, ((1 + p)^gamma - 1) / gamma, which is ln(1 + p) at gamma = 0;
+// shifting by one keeps it finite at p = 0 and makes it equal to p at gamma = 1
+fn box_cox(p: f64, gamma: f64) -> f64 {
+    if gamma == 0. {
+        p.ln_1p()
+    } else {
+        ((1. + p).powf(gamma) - 1.) / gamma
+    }
+}
+
+// rewards with diminishing returns in the winner's capability when gamma < 1:
+// when j wins, win_a_j + win_b_j f(p_j) for j and lose_a_j + lose_b_j f(p_j) for everyone else,
+// where f(p) = ((1 + p)^gamma - 1) / gamma, or ln(1 + p) when gamma = 0; gamma = 1 gives LinearReward
+#[derive(Clone, Debug)]
+pub struct PowerReward {
+    pub win_a: Array<f64, Ix1>,
+    pub win_b: Array<f64, Ix1>,
+    pub lose_a: Array<f64, Ix1>,
+    pub lose_b: Array<f64, Ix1>,
+    pub gamma: f64,
+}
+
+impl PowerReward {
+    pub fn new(
+        win_a: Array<f64, Ix1>,
+        win_b: Array<f64, Ix1>,
+        lose_a: Array<f64, Ix1>,
+        lose_b: Array<f64, Ix1>,
+        gamma: f64,
+    ) -> Result<Self, &'static str> {
+        validate_lengths(&[&win_a, &win_b, &lose_a, &lose_b])?;
+        validate_gamma(gamma)?;
+        Ok(PowerReward { win_a, win_b, lose_a, lose_b, gamma })
+    }
+}
+
+fn validate_gamma(gamma: f64) -> Result<(), &'static str> {
+    if !gamma.is_finite() {
+        return Err("When creating PowerReward: gamma must be finite");
+    }
+    Ok(())
+}
+
+impl RewardFunc for PowerReward {
+    fn reward_ij(&self, i: usize, j: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        if j == i {
+            self.win_a[j] + self.win_b[j] * box_cox(p[j], self.gamma)
+        } else {
+            self.lose_a[j] + self.lose_b[j] * box_cox(p[j], self.gamma)
+        }
+    }
+
+    fn dreward(&self, i: usize, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        Array::from_diag(&Array::from_iter((0..p.len()).map(|j| {
+            let df = (1. + p[j]).powf(self.gamma - 1.);
+            if j == i { self.win_b[j] * df } else { self.lose_b[j] * df }
+        })))
+    }
+
+    fn n(&self) -> usize {
+        self.win_a.len()
+    }
+}
+
+// losers are worse off the more capable the winner is relative to them:
+// when j wins, win_a_j + win_b_j p_j for j and lose_a_j - lose_c_j (1 + p_j) / (1 + p_i) for each other player i,
+// where capabilities are shifted by one so the ratio stays finite at p_i = 0
+#[derive(Clone, Debug)]
+pub struct RelativeReward {
+    pub win_a: Array<f64, Ix1>,
+    pub win_b: Array<f64, Ix1>,
+    pub lose_a: Array<f64, Ix1>,
+    pub lose_c: Array<f64, Ix1>,
+}
+
+impl RelativeReward {
+    pub fn new(
+        win_a: Array<f64, Ix1>,
+        win_b: Array<f64, Ix1>,
+        lose_a: Array<f64, Ix1>,
+        lose_c: Array<f64, Ix1>,
+    ) -> Result<Self, &'static str> {
+        validate_lengths(&[&win_a, &win_b, &lose_a, &lose_c])?;
+        Ok(RelativeReward { win_a, win_b, lose_a, lose_c })
+    }
+}
+
+impl RewardFunc for RelativeReward {
+    fn reward_ij(&self, i: usize, j: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        if j == i {
+            self.win_a[j] + self.win_b[j] * p[j]
+        } else {
+            self.lose_a[j] - self.lose_c[j] * (1. + p[j]) / (1. + p[i])
+        }
+    }
+
+    fn dreward(&self, i: usize, p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        let mut out = Array::zeros((p.len(), p.len()));
+        for j in 0..p.len() {
+            if j == i {
+                out[[i, i]] = self.win_b[i];
+            } else {
+                out[[j, j]] = -self.lose_c[j] / (1. + p[i]);
+                out[[j, i]] = self.lose_c[j] * (1. + p[j]) / (1. + p[i]).powi(2);
+            }
+        }
+        out
+    }
+
+    fn n(&self) -> usize {
+        self.win_a.len()
+    }
+}
+
+// any reward matrix that is linear in the winner's capability: rho_ij = a[i, j] + b[i, j] p_j,
+// the reward to player i when j wins
+#[derive(Clone, Debug)]
+pub struct MatrixReward {
+    pub a: Array<f64, Ix2>,
+    pub b: Array<f64, Ix2>,
+}
+
+impl MatrixReward {
+    pub fn new(a: Array<f64, Ix2>, b: Array<f64, Ix2>) -> Result<Self, &'static str> {
+        if a.nrows() != a.ncols() || a.dim() != b.dim() {
+            return Err("When creating MatrixReward: a and b must be square matrices of the same shape");
+        }
+        Ok(MatrixReward { a, b })
+    }
+
+    // rewards that don't depend on capability
+    pub fn constant(a: Array<f64, Ix2>) -> Result<Self, &'static str> {
+        let b = Array::zeros(a.dim());
+        Self::new(a, b)
+    }
+}
+
+impl RewardFunc for MatrixReward {
+    fn reward_ij(&self, i: usize, j: usize, p: ArrayView<f64, Ix1>) -> f64 {
+        self.a[[i, j]] + self.b[[i, j]] * p[j]
+    }
+
+    fn reward(&self, i: usize, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        &self.a.row(i) + &(&self.b.row(i) * &p)
+    }
+
+    fn dreward(&self, i: usize, _p: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
+        Array::from_diag(&self.b.row(i))
+    }
+
+    fn n(&self) -> usize {
+        self.a.nrows()
+    }
+}
+
+impl Params for LinearReward {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "win_a" => index_mut(&mut self.win_a, index, name),
+            "win_b" => index_mut(&mut self.win_b, index, name),
+            "lose_a" => index_mut(&mut self.lose_a, index, name),
+            "lose_b" => index_mut(&mut self.lose_b, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+impl Params for PowerReward {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "win_a" => index_mut(&mut self.win_a, index, name),
+            "win_b" => index_mut(&mut self.win_b, index, name),
+            "lose_a" => index_mut(&mut self.lose_a, index, name),
+            "lose_b" => index_mut(&mut self.lose_b, index, name),
+            "gamma" if index.is_none() => Ok(&mut self.gamma),
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_gamma(self.gamma)?;
+        Ok(())
+    }
+}
+
+impl Params for RelativeReward {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "win_a" => index_mut(&mut self.win_a, index, name),
+            "win_b" => index_mut(&mut self.win_b, index, name),
+            "lose_a" => index_mut(&mut self.lose_a, index, name),
+            "lose_c" => index_mut(&mut self.lose_c, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+// parameter names only take a single index, so the matrix entries aren't exposed
+impl Params for MatrixReward {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        Err(unknown_param(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use numpy::ndarray::Array2;
+
+    use super::*;
+    use crate::utils::{fd_jacobian, isapprox_iters};
+
+    fn linear() -> LinearReward {
+        LinearReward::new(
+            Array::from_vec(vec![1., 2., 0.5]),
+            Array::from_vec(vec![0.1, 0.3, 0.2]),
+            Array::from_vec(vec![-0.2, 0., 0.4]),
+            Array::from_vec(vec![0.05, -0.1, 0.]),
+        ).unwrap()
+    }
+
+    #[test]
+    fn power_and_matrix_match_linear() {
+        let lin = linear();
+        let pow = PowerReward::new(
+            lin.win_a.clone(), lin.win_b.clone(), lin.lose_a.clone(), lin.lose_b.clone(), 1.
+        ).unwrap();
+        let a = Array2::from_shape_fn((3, 3), |(i, j)| if i == j { lin.win_a[j] } else { lin.lose_a[j] });
+        let b = Array2::from_shape_fn((3, 3), |(i, j)| if i == j { lin.win_b[j] } else { lin.lose_b[j] });
+        let mat = MatrixReward::new(a, b).unwrap();
+        let p = Array::from_vec(vec![3., 0.5, 7.]);
+        for i in 0..3 {
+            let expected = lin.reward(i, p.view());
+            let expected_d = lin.dreward(i, p.view());
+            assert!(isapprox_iters(pow.reward(i, p.view()).into_iter(), expected.iter().cloned(), 1e-12, 1e-12));
+            assert!(isapprox_iters(mat.reward(i, p.view()).into_iter(), expected.iter().cloned(), 1e-12, 1e-12));
+            assert!(isapprox_iters(pow.dreward(i, p.view()).into_iter(), expected_d.iter().cloned(), 1e-12, 1e-12));
+            assert!(isapprox_iters(mat.dreward(i, p.view()).into_iter(), expected_d.iter().cloned(), 1e-12, 1e-12));
+        }
+    }
+
+    #[test]
+    fn dreward_matches_finite_differences() {
+        let lin = linear();
+        let rewards = [
+            PowerReward::new(lin.win_a.clone(), lin.win_b.clone(), lin.lose_a.clone(), lin.lose_b.clone(), 0.).unwrap(),
+            PowerReward::new(lin.win_a.clone(), lin.win_b.clone(), lin.lose_a.clone(), lin.lose_b.clone(), -0.5).unwrap(),
+        ];
+        let relative = RelativeReward::new(
+            lin.win_a.clone(), lin.win_b.clone(), lin.lose_a.clone(), Array::from_vec(vec![0.3, 0.1, 0.2])
+        ).unwrap();
+        let p = Array::from_vec(vec![3., 0.5, 7.]);
+        for i in 0..3 {
+            for r in rewards.iter() {
+                let fd = fd_jacobian(|p_| r.reward(i, p_), p.view());
+                assert!(isapprox_iters(r.dreward(i, p.view()).into_iter(), fd.into_iter(), 1e-6, 1e-8));
+            }
+            let fd = fd_jacobian(|p_| relative.reward(i, p_), p.view());
+            assert!(isapprox_iters(relative.dreward(i, p.view()).into_iter(), fd.into_iter(), 1e-6, 1e-8));
+        }
+    }
+
+    #[test]
+    fn rewards_finite_at_zero_capability() {
+        let lin = linear();
+        let p = Array::zeros(3);
+        let log = PowerReward::new(lin.win_a.clone(), lin.win_b.clone(), lin.lose_a.clone(), lin.lose_b.clone(), 0.).unwrap();
+        let neg = PowerReward::new(lin.win_a.clone(), lin.win_b.clone(), lin.lose_a.clone(), lin.lose_b.clone(), -2.).unwrap();
+        let relative = RelativeReward::new(
+            lin.win_a.clone(), lin.win_b.clone(), lin.lose_a.clone(), Array::ones(3)
+        ).unwrap();
+        for i in 0..3 {
+            assert!(log.reward(i, p.view()).iter().chain(log.dreward(i, p.view()).iter()).all(|x| x.is_finite()));
+            assert!(neg.reward(i, p.view()).iter().chain(neg.dreward(i, p.view()).iter()).all(|x| x.is_finite()));
+            assert!(relative.reward(i, p.view()).iter().chain(relative.dreward(i, p.view()).iter()).all(|x| x.is_finite()));
+        }
+    }
+
+    #[test]
+    fn set_param_rejects_infinite_gamma() {
+        let lin = linear();
+        let mut pow = PowerReward::new(lin.win_a, lin.win_b, lin.lose_a, lin.lose_b, 0.5).unwrap();
+        assert!(pow.set_param("gamma", f64::INFINITY).is_err());
+        assert!(pow.set_param("gamma", f64::NAN).is_err());
+        assert_eq!(pow.gamma, 0.5);
+        pow.set_param("gamma", -1.).unwrap();
+        assert_eq!(pow.gamma, -1.);
+    }
+}
@@ -1,4 +1,7 @@
-use numpy::ndarray::{ArrayView, Ix1, Array};
+use numpy::ndarray::{ArrayView, Ix1, Ix2, Array};
+
+use crate::params::{Params, index_mut, parse_index, unknown_param};
+use crate::utils::fd_jacobian;
 
 pub trait RiskFunc: Clone + Send + Sync {
     // sigma_i is proba(safe | i wins)
@@ -7,6 +10,15 @@ pub trait RiskFunc: Clone + Send + Sync {
         Array::from_iter((0..s.len()).map(|i| self.sigma_i(i, s, p)))
     }
 
+    // jacobians of sigma w.r.t. s and p, indexed as [j, k] = d sigma_j / d s_k
+    // defaults to finite differences; override when an analytic form is available
+    fn dsigma(&self, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        (
+            fd_jacobian(|s_| self.sigma(s_, p), s),
+            fd_jacobian(|p_| self.sigma(s, p_), p),
+        )
+    }
+
     fn n(&self) -> usize;
 }
 
@@ -17,8 +29,12 @@ pub struct WinnerOnlyRisk {
 
 impl RiskFunc for WinnerOnlyRisk {
     fn sigma_i(&self, i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> f64 {
-        let s_ = s[i] * p[i].powf(-self.theta[i]);
-        s_ / (1.0 + s_)
+        safety(self.theta[i], s[i], p[i]).0
+    }
+
+    fn dsigma(&self, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        let (_, ds, dp) = own_safety(&self.theta, s, p);
+        (Array::from_diag(&ds), Array::from_diag(&dp))
     }
 
     fn n(&self) -> usize {
@@ -32,4 +48,256 @@ impl WinnerOnlyRisk {
             theta: Array::from_elem(n, theta),
         }
     }
-}
\ No newline at end of file
+}
+
+impl Params for WinnerOnlyRisk {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "theta" => index_mut(&mut self.theta, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+// a single player's safety, s p^-theta / (1 + s p^-theta), with its derivatives w.r.t. s and p
+fn safety(theta: f64, s: f64, p: f64) -> (f64, f64, f64) {
+    let p_pow = p.powf(-theta);
+    // at p = 0 the formula is inf / inf (or NaN when s = 0 too), so use its limit instead:
+    // the player is perfectly safe if s > 0 and perfectly unsafe if s = 0
+    if p_pow.is_infinite() {
+        return (if s > 0. { 1. } else { 0. }, 0., 0.);
+    }
+    let s_ = s * p_pow;
+    let dsafety_ds_ = 1.0 / (1.0 + s_).powi(2);
+    (s_ / (1.0 + s_), dsafety_ds_ * p_pow, -dsafety_ds_ * theta * s_ / p)
+}
+
+// each player's own safety as in WinnerOnlyRisk, with its derivatives w.r.t. s_j and p_j
+fn own_safety(
+    theta: &Array<f64, Ix1>, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>
+) -> (Array<f64, Ix1>, Array<f64, Ix1>, Array<f64, Ix1>) {
+    let n = s.len();
+    let mut safety_ = Array::zeros(n);
+    let mut ds = Array::zeros(n);
+    let mut dp = Array::zeros(n);
+    for j in 0..n {
+        (safety_[j], ds[j], dp[j]) = safety(theta[j], s[j], p[j]);
+    }
+    (safety_, ds, dp)
+}
+
+// the disaster is avoided only if every player is safe, whoever wins:
+// sigma_i = prod_j safety_j for all i
+#[derive(Clone, Debug)]
+pub struct ProductRisk {
+    pub theta: Array<f64, Ix1>,
+}
+
+impl ProductRisk {
+    pub fn new(n: usize, theta: f64) -> Self {
+        ProductRisk {
+            theta: Array::from_elem(n, theta),
+        }
+    }
+}
+
+impl RiskFunc for ProductRisk {
+    fn sigma_i(&self, _i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> f64 {
+        own_safety(&self.theta, s, p).0.product()
+    }
+
+    fn sigma(&self, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        Array::from_elem(s.len(), own_safety(&self.theta, s, p).0.product())
+    }
+
+    fn dsigma(&self, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        let n = s.len();
+        let (safety, ds_k, dp_k) = own_safety(&self.theta, s, p);
+        // product of everyone else's safety, computed directly in case some safety is 0
+        let others = Array::from_shape_fn(n, |k| {
+            safety.iter().enumerate().filter(|&(j, _)| j != k).map(|(_, x)| x).product::<f64>()
+        });
+        let ds = &others * &ds_k;
+        let dp = &others * &dp_k;
+        (
+            ds.broadcast((n, n)).unwrap().to_owned(),
+            dp.broadcast((n, n)).unwrap().to_owned(),
+        )
+    }
+
+    fn n(&self) -> usize {
+        self.theta.len()
+    }
+}
+
+// the disaster is avoided only if the least safe player is safe:
+// sigma_i = min_j safety_j for all i
+// (the jacobian is taken w.r.t. the least safe player, ignoring ties)
+#[derive(Clone, Debug)]
+pub struct WeakestLinkRisk {
+    pub theta: Array<f64, Ix1>,
+}
+
+impl WeakestLinkRisk {
+    pub fn new(n: usize, theta: f64) -> Self {
+        WeakestLinkRisk {
+            theta: Array::from_elem(n, theta),
+        }
+    }
+}
+
+fn argmin(x: &Array<f64, Ix1>) -> usize {
+    x.iter().enumerate().fold(0, |k, (j, &x_j)| if x_j < x[k] { j } else { k })
+}
+
+impl RiskFunc for WeakestLinkRisk {
+    fn sigma_i(&self, _i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> f64 {
+        own_safety(&self.theta, s, p).0.fold(f64::INFINITY, |acc, &x| acc.min(x))
+    }
+
+    fn dsigma(&self, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        let n = s.len();
+        let (safety, ds_k, dp_k) = own_safety(&self.theta, s, p);
+        let k = argmin(&safety);
+        let mut ds = Array::zeros((n, n));
+        let mut dp = Array::zeros((n, n));
+        ds.column_mut(k).fill(ds_k[k]);
+        dp.column_mut(k).fill(dp_k[k]);
+        (ds, dp)
+    }
+
+    fn n(&self) -> usize {
+        self.theta.len()
+    }
+}
+
+// safety if i wins is a weighted average of everyone's safety, sigma_i = sum_j w[i, j] safety_j,
+// where each row of w sums to 1; w = identity gives WinnerOnlyRisk
+#[derive(Clone, Debug)]
+pub struct WeightedRisk {
+    pub theta: Array<f64, Ix1>,
+    pub w: Array<f64, Ix2>,
+}
+
+impl WeightedRisk {
+    pub fn new(theta: Array<f64, Ix1>, w: Array<f64, Ix2>) -> Result<Self, &'static str> {
+        let n = theta.len();
+        if w.dim() != (n, n) {
+            return Err("When creating new WeightedRisk: w must have shape (n, n)");
+        }
+        if w.iter().any(|&w_ij| w_ij.is_nan() || w_ij < 0.) {
+            return Err("When creating new WeightedRisk: w must be non-negative");
+        }
+        if w.rows().into_iter().any(|row| (row.sum() - 1.).abs() > 1e-9) {
+            return Err("When creating new WeightedRisk: each row of w must sum to 1");
+        }
+        Ok(WeightedRisk { theta, w })
+    }
+
+    // each player puts weight own_weight on their own safety and splits the rest evenly among the others
+    pub fn uniform(n: usize, theta: f64, own_weight: f64) -> Result<Self, &'static str> {
+        let other_weight = if n > 1 { (1. - own_weight) / (n - 1) as f64 } else { 0. };
+        let w = Array::from_shape_fn((n, n), |(i, j)| if i == j { own_weight } else { other_weight });
+        Self::new(Array::from_elem(n, theta), w)
+    }
+}
+
+impl RiskFunc for WeightedRisk {
+    fn sigma_i(&self, i: usize, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> f64 {
+        self.w.row(i).dot(&own_safety(&self.theta, s, p).0)
+    }
+
+    fn sigma(&self, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> Array<f64, Ix1> {
+        self.w.dot(&own_safety(&self.theta, s, p).0)
+    }
+
+    fn dsigma(&self, s: ArrayView<f64, Ix1>, p: ArrayView<f64, Ix1>) -> (Array<f64, Ix2>, Array<f64, Ix2>) {
+        let (_, ds_k, dp_k) = own_safety(&self.theta, s, p);
+        (&self.w * &ds_k, &self.w * &dp_k)
+    }
+
+    fn n(&self) -> usize {
+        self.theta.len()
+    }
+}
+
+impl Params for ProductRisk {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "theta" => index_mut(&mut self.theta, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+impl Params for WeakestLinkRisk {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "theta" => index_mut(&mut self.theta, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+// the weights aren't exposed as params, since changing one would break the row sums
+impl Params for WeightedRisk {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (field, index) = parse_index(name)?;
+        match field {
+            "theta" => index_mut(&mut self.theta, index, name),
+            _ => Err(unknown_param(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::assert_jacobian_matches_fd;
+
+    #[test]
+    fn dsigma_matches_finite_differences() {
+        // distinct safeties, so the weakest link is well defined
+        let s = Array::from_vec(vec![0.8, 1.7, 0.3]);
+        let p = Array::from_vec(vec![1.5, 0.4, 2.2]);
+        let risk = WinnerOnlyRisk::new(3, 0.6);
+        let (ds, dp) = risk.dsigma(s.view(), p.view());
+        assert_jacobian_matches_fd("WinnerOnlyRisk ds", &ds, |s_| risk.sigma(s_, p.view()), s.view());
+        assert_jacobian_matches_fd("WinnerOnlyRisk dp", &dp, |p_| risk.sigma(s.view(), p_), p.view());
+        let risk = ProductRisk::new(3, 0.6);
+        let (ds, dp) = risk.dsigma(s.view(), p.view());
+        assert_jacobian_matches_fd("ProductRisk ds", &ds, |s_| risk.sigma(s_, p.view()), s.view());
+        assert_jacobian_matches_fd("ProductRisk dp", &dp, |p_| risk.sigma(s.view(), p_), p.view());
+        let risk = WeakestLinkRisk::new(3, 0.6);
+        let (ds, dp) = risk.dsigma(s.view(), p.view());
+        assert_jacobian_matches_fd("WeakestLinkRisk ds", &ds, |s_| risk.sigma(s_, p.view()), s.view());
+        assert_jacobian_matches_fd("WeakestLinkRisk dp", &dp, |p_| risk.sigma(s.view(), p_), p.view());
+        let risk = WeightedRisk::uniform(3, 0.6, 0.5).unwrap();
+        let (ds, dp) = risk.dsigma(s.view(), p.view());
+        assert_jacobian_matches_fd("WeightedRisk ds", &ds, |s_| risk.sigma(s_, p.view()), s.view());
+        assert_jacobian_matches_fd("WeightedRisk dp", &dp, |p_| risk.sigma(s.view(), p_), p.view());
+    }
+
+    #[test]
+    fn safety_finite_at_zero_capability() {
+        let s = Array::from_vec(vec![0., 1.7, 0.]);
+        let p = Array::from_vec(vec![0., 0., 2.2]);
+        let winner_only = WinnerOnlyRisk::new(3, 0.6);
+        let sigma = winner_only.sigma(s.view(), p.view());
+        assert_eq!(sigma[0], 0.);
+        assert_eq!(sigma[1], 1.);
+        assert_eq!(sigma[2], 0.);
+        let (ds, dp) = winner_only.dsigma(s.view(), p.view());
+        assert!(ds.iter().chain(dp.iter()).all(|x| x.is_finite()));
+
+        // with identity weights, WeightedRisk is WinnerOnlyRisk, including at p = 0
+        let weighted = WeightedRisk::uniform(3, 0.6, 1.).unwrap();
+        assert_eq!(weighted.sigma(s.view(), p.view()), sigma);
+        for risk in [ProductRisk::new(3, 0.6).sigma(s.view(), p.view()), WeakestLinkRisk::new(3, 0.6).sigma(s.view(), p.view())] {
+            assert!(risk.iter().all(|x| x.is_finite()));
+        }
+    }
+}
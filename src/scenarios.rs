@@ -1,10 +1,12 @@
 use std::marker::PhantomData;
+use std::time::Instant;
 
 use rayon::prelude::*;
 
 use crate::strategies::{ActionType, StrategyType};
 use crate::states::PayoffAggregator;
-use crate::solve::{solve, SolverOptions};
+use crate::solve::{solve, Progress, ProgressCallback, SolveResult, SolverOptions};
+use crate::continuation::{solve_sequence, ContinuationOptions, ContinuationResult};
 
 pub struct Scenario<A, S, T>
 where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
@@ -38,7 +40,22 @@ where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
         self.n
     }
 
-    pub fn solve(&self, options: &SolverOptions<S>) -> Result<Vec<S>, argmin::core::Error> {
-        self.aggs.par_iter().map(|agg| solve(agg, options)).collect()
+    // the timeout applies to the whole scenario, and progress reports which aggregator they're for;
+    // a seeded random init guess gets a sub-seed for each aggregator, so results don't depend on scheduling
+    pub fn solve(&self, options: &SolverOptions<S>) -> Result<Vec<SolveResult<S>>, argmin::core::Error> {
+        let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+        self.aggs.par_iter().enumerate().map(|(k, agg)| {
+            let progress = options.progress.clone().map(|progress| ProgressCallback::new(move |p: &Progress| {
+                progress.call(&Progress { index: Some(k), ..p.clone() })
+            }));
+            let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            let init_guess = options.init_guess.with_sub_seed(k);
+            solve(agg, &SolverOptions { init_guess, progress, timeout, ..options.clone() })
+        }).collect()
+    }
+
+    // solves the aggregators in order, warm-starting each from the previous solution
+    pub fn solve_continuation(&self, options: &ContinuationOptions<S>) -> Result<ContinuationResult<S>, argmin::core::Error> {
+        solve_sequence(&self.aggs, options)
     }
 }
@@ -0,0 +1,166 @@
+use std::fmt;
+
+use numpy::ndarray::{Array, Axis, Ix1, Ix2, Ix3, s};
+
+use crate::params::Params;
+use crate::solve::{InitGuess, SolverOptions, solve};
+use crate::states::PayoffAggregator;
+use crate::strategies::*;
+use crate::utils::{fd_jacobian, fd_step, solve_linear};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SensitivityMethod {
+    // implicit function theorem applied to the stacked first-order conditions
+    ImplicitFunction,
+    // re-solve the equilibrium with the parameter perturbed up and down
+    FiniteDifference,
+}
+
+#[derive(Clone, Debug)]
+pub struct SensitivityOptions<S: StrategyType> {
+    pub method: SensitivityMethod,
+    // whether to re-solve when the first-order conditions can't be inverted
+    pub fallback: bool,
+    // strategy values below this are treated as corner solutions, which stay put
+    // when the parameter changes
+    pub corner_tol: f64,
+    // relative size of the parameter step when re-solving; this needs to be well above
+    // the solver tolerance for the differences to be meaningful
+    pub resolve_step: f64,
+    // options for re-solving; init_guess is replaced by the equilibrium being perturbed,
+    // and fixed_players are held fixed by either method
+    pub solver_options: SolverOptions<S>,
+}
+
+impl<S: StrategyType> SensitivityOptions<S> {
+    pub fn new(t: usize) -> Self {
+        SensitivityOptions {
+            method: SensitivityMethod::ImplicitFunction,
+            fallback: true,
+            corner_tol: 1e-6,
+            resolve_step: 1e-3,
+            solver_options: SolverOptions::random_init(t),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sensitivity {
+    pub param: String,
+    pub value: f64,
+    // derivative of each strategy value w.r.t. the parameter, indexed as [t, i, m]
+    pub derivative: Array<f64, Ix3>,
+    // the method actually used, which differs from the one requested if the fallback was needed
+    pub method: SensitivityMethod,
+}
+
+impl fmt::Display for Sensitivity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "d strategies / d {} at {} ({:?}):", self.param, self.value, self.method)?;
+        write!(f, "{:.4}", self.derivative)
+    }
+}
+
+// every player's gradient w.r.t. their own params, flattened in [t, i, m] order;
+// this is zero at an interior equilibrium
+pub(crate) fn stacked_foc<A, S, T>(agg: &T, strategies: &S) -> Array<f64, Ix1>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    let mut foc = Array::zeros(strategies.data().dim());
+    for i in 0..strategies.n() {
+        foc.slice_mut(s![.., i, ..]).assign(&agg.grad_i(i, strategies));
+    }
+    Array::from_iter(foc.iter().cloned())
+}
+
+pub(crate) fn strategies_from_flat<S: StrategyType>(x: &Array<f64, Ix1>, shape: (usize, usize, usize)) -> S {
+    S::from_array_unchecked(x.clone().into_shape(shape).unwrap())
+}
+
+fn resolve_derivative<A, S, T>(
+    agg: &T, strategies: &S, param: &str, value: f64, options: &SensitivityOptions<S>
+) -> Result<Array<f64, Ix3>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S> + Params
+{
+    let h = options.resolve_step * value.abs().max(1.);
+    let solver_options = SolverOptions {
+        init_guess: InitGuess::Fixed(strategies.clone()),
+        trace: false,
+        ..options.solver_options.clone()
+    };
+    let mut solved = Vec::with_capacity(2);
+    for x in [value + h, value - h] {
+        let agg_ = agg.with_param(param, x).map_err(argmin::core::Error::msg)?;
+        solved.push(solve(&agg_, &solver_options)?.strategies);
+    }
+    Ok((&solved[0].data() - &solved[1].data()) / (2. * h))
+}
+
+// derivatives of the equilibrium strategies w.r.t. each of the named parameters
+pub fn sensitivities<A, S, T>(
+    agg: &T, strategies: &S, params: &[&str], options: &SensitivityOptions<S>
+) -> Result<Vec<Sensitivity>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S> + Params
+{
+    if let Some(&i) = options.solver_options.fixed_players.iter().find(|&&i| i >= agg.n()) {
+        return Err(argmin::core::Error::msg(format!("Fixed player {} out of range for {} players", i, agg.n())));
+    }
+    let shape = strategies.data().dim();
+    let x = Array::from_iter(strategies.data().iter().cloned());
+    // only interior values are pinned down by first-order conditions, and fixed players
+    // keep their strategies whatever the parameter, so their derivatives are zero
+    let fixed = &options.solver_options.fixed_players;
+    let player = |k: usize| (k / shape.2) % shape.1;
+    let active = (0..x.len())
+        .filter(|&k| x[k] >= options.corner_tol && !fixed.contains(&player(k)))
+        .collect::<Vec<_>>();
+    let jacobian: Option<Array<f64, Ix2>> = match options.method {
+        SensitivityMethod::ImplicitFunction => {
+            let full = fd_jacobian(
+                |y| stacked_foc(agg, &strategies_from_flat::<S>(&y.to_owned(), shape)),
+                x.view(),
+            );
+            Some(full.select(Axis(0), &active).select(Axis(1), &active))
+        },
+        SensitivityMethod::FiniteDifference => None,
+    };
+
+    params.iter().map(|&param| {
+        let value = agg.get_param(param).map_err(argmin::core::Error::msg)?;
+        let ift = jacobian.as_ref().and_then(|jacobian| {
+            let h = fd_step(value);
+            let up = agg.with_param(param, value + h).map(|agg_| stacked_foc(&agg_, strategies));
+            let down = agg.with_param(param, value - h).map(|agg_| stacked_foc(&agg_, strategies));
+            let dfoc = match (up, down) {
+                (Ok(up), Ok(down)) => (up - down) / (2. * h),
+                _ => return None,
+            };
+            let rhs = Array::from_iter(active.iter().map(|&k| -dfoc[k]));
+            let dx = solve_linear(jacobian.view(), rhs.view())?;
+            let mut derivative = Array::zeros(x.len());
+            for (&k, d) in active.iter().zip(dx.iter()) {
+                derivative[k] = *d;
+            }
+            Some(derivative.into_shape(shape).unwrap())
+        });
+        let (derivative, method) = match ift {
+            Some(derivative) => (derivative, SensitivityMethod::ImplicitFunction),
+            None if options.method == SensitivityMethod::FiniteDifference || options.fallback => (
+                resolve_derivative(agg, strategies, param, value, options)?,
+                SensitivityMethod::FiniteDifference,
+            ),
+            None => return Err(argmin::core::Error::msg(format!(
+                "Could not apply the implicit function theorem for parameter {}", param
+            ))),
+        };
+        Ok(Sensitivity { param: param.to_string(), value, derivative, method })
+    }).collect()
+}
+
+pub fn sensitivity<A, S, T>(
+    agg: &T, strategies: &S, param: &str, options: &SensitivityOptions<S>
+) -> Result<Sensitivity, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S> + Params
+{
+    Ok(sensitivities(agg, strategies, &[param], options)?.remove(0))
+}
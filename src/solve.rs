@@ -1,36 +1,223 @@
-use numpy::ndarray::{Array, ArrayView, Ix2, s};
-use argmin::core::{CostFunction, Executor};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use numpy::ndarray::{Array, Ix1, Ix2, s};
+use argmin::core::{CostFunction, Executor, Gradient, TerminationReason};
+use argmin::solver::linesearch::MoreThuenteLineSearch;
 use argmin::solver::neldermead::NelderMead;
+use argmin::solver::particleswarm::ParticleSwarm;
+use argmin::solver::quasinewton::LBFGS;
+use argmin::solver::simulatedannealing::{Anneal, SimulatedAnnealing};
+use ndarray_rand::rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
 use rayon::prelude::*;
 
-use crate::states::PayoffAggregator;
+use crate::constraints::{DISTANCE_WEIGHT, LinearConstraint, constrains, project, project_jacobian};
+use crate::states::{PayoffAggregator, PlayerEvaluator};
 use crate::strategies::*;
-use crate::utils::isapprox_iters;
+use crate::utils::{fd_step, isapprox_iters, solve_linear, sub_seed};
 
-const INIT_MU: f64 = -1.;
-const INIT_SIGMA: f64 = 0.1;
+pub(crate) const INIT_MU: f64 = -1.;
+pub(crate) const INIT_SIGMA: f64 = 0.1;
 
 #[derive(Clone, Debug)]
 pub enum InitGuess<S: StrategyType> {
-    Random(usize),
+    // t periods drawn from a lognormal distribution with parameters mu and sigma;
+    // without a seed, a different guess is drawn every time
+    Random { t: usize, seed: Option<u64>, mu: f64, sigma: f64 },
     Fixed(S),
 }
 
 impl<S: StrategyType> InitGuess<S> {
-    fn to_fixed(&self, n: usize) -> S {
+    pub fn random(t: usize) -> Self {
+        InitGuess::Random { t, seed: None, mu: INIT_MU, sigma: INIT_SIGMA }
+    }
+
+    pub fn seeded(t: usize, seed: u64) -> Self {
+        InitGuess::Random { t, seed: Some(seed), mu: INIT_MU, sigma: INIT_SIGMA }
+    }
+
+    pub fn t(&self) -> usize {
+        match self {
+            InitGuess::Random { t, .. } => *t,
+            InitGuess::Fixed(x) => x.t(),
+        }
+    }
+
+    // copy of this guess with its k-th sub-seed, so that each of several solves draws a different
+    // but reproducible guess
+    pub(crate) fn with_sub_seed(&self, k: usize) -> Self {
         match self {
-            InitGuess::Random(t) => S::random(*t, n, INIT_MU, INIT_SIGMA).unwrap(),
-            InitGuess::Fixed(x) => x.clone(),
+            InitGuess::Random { t, seed: Some(seed), mu, sigma } => InitGuess::Random {
+                t: *t, seed: Some(sub_seed(*seed, k)), mu: *mu, sigma: *sigma,
+            },
+            _ => self.clone(),
         }
     }
+
+    pub(crate) fn to_fixed(&self, n: usize) -> Result<S, argmin::core::Error> {
+        match self {
+            InitGuess::Random { t, seed: Some(seed), mu, sigma } => {
+                let mut rng = StdRng::seed_from_u64(*seed);
+                S::random_using(*t, n, *mu, *sigma, &mut rng).map_err(argmin::core::Error::msg)
+            },
+            InitGuess::Random { t, seed: None, mu, sigma } => {
+                S::random(*t, n, *mu, *sigma).map_err(argmin::core::Error::msg)
+            },
+            InitGuess::Fixed(x) => Ok(x.clone()),
+        }
+    }
+}
+
+// which optimizer to use when finding each player's best response, with its settings
+#[derive(Clone, Debug)]
+pub enum BestResponseMethod {
+    // derivative-free; works for any payoff aggregator
+    NelderMead(NMOptions),
+    // quasi-Newton using PayoffAggregator::grad_i, or finite differences of the payoff
+    LBFGS(LBFGSOptions),
+    // global searches, which cope better with flat regions of the payoff;
+    // argmin's particle swarm draws from the thread RNG and can't be seeded,
+    // so its results aren't reproducible even when the initial guess is seeded
+    ParticleSwarm(PSOOptions),
+    SimulatedAnnealing(AnnealOptions),
+}
+
+impl Default for BestResponseMethod {
+    fn default() -> Self {
+        BestResponseMethod::NelderMead(NMOptions::default())
+    }
 }
 
+// shared flag for stopping a solve from another thread
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// reported after each outer iteration
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub iteration: u64,
+    pub max_change: f64,
+    pub elapsed: Duration,
+    // position of the aggregator when solving a scenario
+    pub index: Option<usize>,
+}
+
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&Progress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new<F: Fn(&Progress) + Send + Sync + 'static>(f: F) -> Self {
+        ProgressCallback(Arc::new(f))
+    }
+    pub fn call(&self, progress: &Progress) {
+        (self.0)(progress)
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProgressCallback")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Converged,
+    MaxItersReached,
+    Timeout,
+    Cancelled,
+}
+
+// when a solve should stop before converging
+#[derive(Clone, Copy, Default)]
+struct Interrupt<'a> {
+    cancel: Option<&'a CancelToken>,
+    deadline: Option<Instant>,
+}
+
+impl Interrupt<'_> {
+    fn check(&self) -> Option<StopReason> {
+        if self.cancel.is_some_and(|c| c.is_cancelled()) {
+            Some(StopReason::Cancelled)
+        } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            Some(StopReason::Timeout)
+        } else {
+            None
+        }
+    }
+}
+
+// time left before a deadline shared by several solves, to use as the timeout of the next one
+pub(crate) fn remaining(deadline: Option<Instant>) -> Option<Duration> {
+    deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+// how each outer iteration combines the players' best responses into the next profile
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UpdateScheme {
+    // every player responds to the previous profile, and the responses replace it outright
+    #[default]
+    Jacobi,
+    // moves this fraction of the way from the previous profile to the responses, in log space
+    Damped(f64),
+    // players respond one at a time, each to a profile including the earlier players' responses
+    GaussSeidel,
+    // Anderson acceleration of the Jacobi update, extrapolating from this many previous iterates
+    Anderson(usize),
+}
+
+// whether to exploit players being interchangeable, by solving for a single representative
+// strategy that every player copies; the result is then checked for profitable deviations,
+// and if there are any, the game is solved again without symmetry
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Symmetry {
+    #[default]
+    Off,
+    // use the symmetric path if swapping any two players' strategies just swaps their payoffs
+    Detect,
+    // use the symmetric path without checking
+    Assume,
+}
+
+// largest gain from deviating from a symmetric solution, relative to the payoff, that is put down
+// to the tolerance of the best-response searches
+const SYMMETRY_TOL: f64 = 1e-6;
+
 #[derive(Clone, Debug)]
 pub struct SolverOptions<S: StrategyType> {
     pub init_guess: InitGuess<S>,
     pub max_iters: u64,
     pub tol: f64,
-    pub nm_options: NMOptions,
+    pub method: BestResponseMethod,
+    pub update: UpdateScheme,
+    pub symmetry: Symmetry,
+    // players who don't respond, keeping their strategies from the initial guess
+    pub fixed_players: Vec<usize>,
+    // whether to record every intermediate profile in SolveResult::trace
+    pub trace: bool,
+    // linear constraints on each player's strategy, respected by every best response
+    pub constraints: Vec<LinearConstraint>,
+    // called after every outer iteration
+    pub progress: Option<ProgressCallback>,
+    // wall-clock limit, after which the profile found so far is returned
+    pub timeout: Option<Duration>,
+    pub cancel: Option<CancelToken>,
 }
 
 impl<S: StrategyType> SolverOptions<S> {
@@ -39,16 +226,32 @@ impl<S: StrategyType> SolverOptions<S> {
             init_guess: InitGuess::Fixed(init_guess),
             max_iters: 200,
             tol: 1e-6,
-            nm_options: NMOptions::default(),
+            method: BestResponseMethod::default(),
+            update: UpdateScheme::default(),
+            symmetry: Symmetry::default(),
+            fixed_players: Vec::new(),
+            trace: false,
+            constraints: Vec::new(),
+            progress: None,
+            timeout: None,
+            cancel: None,
         }
     }
 
     pub fn random_init(t: usize) -> Self {
         SolverOptions {
-            init_guess: InitGuess::Random(t),
+            init_guess: InitGuess::random(t),
             max_iters: 200,
             tol: 1e-6,
-            nm_options: NMOptions::default(),
+            method: BestResponseMethod::default(),
+            update: UpdateScheme::default(),
+            symmetry: Symmetry::default(),
+            fixed_players: Vec::new(),
+            trace: false,
+            constraints: Vec::new(),
+            progress: None,
+            timeout: None,
+            cancel: None,
         }
     }
 }
@@ -70,10 +273,236 @@ impl Default for NMOptions {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct LBFGSOptions {
+    // number of past updates used to approximate the inverse hessian
+    pub m: usize,
+    pub max_iters: u64,
+    pub tol_grad: f64,
+    pub tol_cost: f64,
+    // whether to use finite differences of the payoff instead of PayoffAggregator::grad_i
+    pub finite_differences: bool,
+}
+
+impl Default for LBFGSOptions {
+    fn default() -> Self {
+        LBFGSOptions {
+            m: 7,
+            max_iters: 200,
+            tol_grad: 1e-8,
+            tol_cost: 1e-12,
+            finite_differences: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PSOOptions {
+    pub n_particles: usize,
+    pub max_iters: u64,
+    // particles are placed within this distance of the starting point, in log space
+    pub radius: f64,
+}
+
+impl Default for PSOOptions {
+    fn default() -> Self {
+        PSOOptions {
+            n_particles: 40,
+            max_iters: 100,
+            radius: 2.,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnnealOptions {
+    pub init_temp: f64,
+    pub max_iters: u64,
+    // largest move in each log param at the initial temperature; moves shrink as the temperature falls
+    pub step_size: f64,
+    // stop after this many iterations without finding a new best point
+    pub stall_best: u64,
+    // without a seed, results differ from run to run
+    pub seed: Option<u64>,
+}
+
+impl Default for AnnealOptions {
+    fn default() -> Self {
+        AnnealOptions {
+            init_temp: 0.1,
+            max_iters: 2000,
+            step_size: 0.5,
+            stall_best: 500,
+            seed: None,
+        }
+    }
+}
+
+// central finite differences of the cost, for objectives whose gradient is unreliable or unavailable
+struct FiniteDiff<O>(O);
+
+impl<O: CostFunction<Param = Vec<f64>, Output = f64>> CostFunction for FiniteDiff<O> {
+    type Param = Vec<f64>;
+    type Output = f64;
+
+    fn cost(&self, params: &Self::Param) -> Result<Self::Output, argmin::core::Error> {
+        self.0.cost(params)
+    }
+}
+
+impl<O: CostFunction<Param = Vec<f64>, Output = f64>> Gradient for FiniteDiff<O> {
+    type Param = Vec<f64>;
+    type Gradient = Vec<f64>;
+
+    fn gradient(&self, params: &Self::Param) -> Result<Self::Gradient, argmin::core::Error> {
+        let mut x = params.clone();
+        (0..params.len()).map(|k| {
+            let h = fd_step(params[k]);
+            x[k] = params[k] + h;
+            let up = self.0.cost(&x)?;
+            x[k] = params[k] - h;
+            let down = self.0.cost(&x)?;
+            x[k] = params[k];
+            Ok((up - down) / (2. * h))
+        }).collect()
+    }
+}
+
+// argmin's NelderMead panics if a cost fails while it evaluates the initial simplex, so those
+// evaluations give an infinite cost instead; an interrupt then surfaces at the next evaluation,
+// since a cancelled or timed-out solve stays that way
+struct SimplexInit<O> {
+    obj: O,
+    unchecked: Cell<usize>,
+}
+
+impl<O: CostFunction<Param = Vec<f64>, Output = f64>> CostFunction for SimplexInit<O> {
+    type Param = Vec<f64>;
+    type Output = f64;
+
+    fn cost(&self, params: &Self::Param) -> Result<Self::Output, argmin::core::Error> {
+        match self.unchecked.get() {
+            0 => self.obj.cost(params),
+            k => {
+                self.unchecked.set(k - 1);
+                Ok(self.obj.cost(params).unwrap_or(f64::INFINITY))
+            },
+        }
+    }
+}
+
+// random moves in log space for simulated annealing, shrinking with the temperature
+struct Annealing<O> {
+    obj: O,
+    step_size: f64,
+    init_temp: f64,
+    rng: RefCell<Xoshiro256PlusPlus>,
+}
+
+impl<O: CostFunction<Param = Vec<f64>, Output = f64>> CostFunction for Annealing<O> {
+    type Param = Vec<f64>;
+    type Output = f64;
+
+    fn cost(&self, params: &Self::Param) -> Result<Self::Output, argmin::core::Error> {
+        self.obj.cost(params)
+    }
+}
+
+impl<O> Anneal for Annealing<O> {
+    type Param = Vec<f64>;
+    type Output = Vec<f64>;
+    type Float = f64;
+
+    fn anneal(&self, params: &Self::Param, temp: f64) -> Result<Self::Output, argmin::core::Error> {
+        let scale = self.step_size * temp / self.init_temp;
+        let mut rng = self.rng.borrow_mut();
+        Ok(params.iter().map(|x| x + scale * rng.gen_range(-1.0..=1.0)).collect())
+    }
+}
+
+fn new_rng(seed: Option<u64>) -> Xoshiro256PlusPlus {
+    match seed {
+        Some(seed) => Xoshiro256PlusPlus::seed_from_u64(seed),
+        None => Xoshiro256PlusPlus::from_entropy(),
+    }
+}
+
+fn minimize_lbfgs<O>(
+    obj: O, init_param: Vec<f64>, options: &LBFGSOptions,
+) -> Result<(Vec<f64>, TerminationReason), argmin::core::Error>
+where O: CostFunction<Param = Vec<f64>, Output = f64> + Gradient<Param = Vec<f64>, Gradient = Vec<f64>>
+{
+    let solver = LBFGS::new(MoreThuenteLineSearch::new(), options.m)
+        .with_tolerance_grad(options.tol_grad)?
+        .with_tolerance_cost(options.tol_cost)?;
+    let res = Executor::new(obj, solver)
+        .configure(|state| state.param(init_param).max_iters(options.max_iters))
+        .run()?;
+    Ok((res.state.best_param.unwrap(), res.state.termination_reason))
+}
+
+// minimizes obj over log strategy values, starting from init_param
+pub(crate) fn minimize<O>(
+    obj: O, init_param: Vec<f64>, method: &BestResponseMethod,
+) -> Result<(Vec<f64>, TerminationReason), argmin::core::Error>
+where O: CostFunction<Param = Vec<f64>, Output = f64> + Gradient<Param = Vec<f64>, Gradient = Vec<f64>>
+{
+    match method {
+        BestResponseMethod::NelderMead(options) => {
+            let simplex = create_simplex(&init_param, options.init_simplex_size);
+            let obj = SimplexInit { obj, unchecked: Cell::new(simplex.len()) };
+            let solver = NelderMead::new(simplex).with_sd_tolerance(options.tol)?;
+            let res = Executor::new(obj, solver)
+                .configure(|state| state.max_iters(options.max_iters))
+                .run()?;
+            Ok((res.state.best_param.unwrap(), res.state.termination_reason))
+        },
+        BestResponseMethod::LBFGS(options) if options.finite_differences => {
+            minimize_lbfgs(FiniteDiff(obj), init_param, options)
+        },
+        BestResponseMethod::LBFGS(options) => minimize_lbfgs(obj, init_param, options),
+        BestResponseMethod::ParticleSwarm(options) => {
+            let init_cost = obj.cost(&init_param)?;
+            let bounds = (
+                init_param.iter().map(|x| x - options.radius).collect(),
+                init_param.iter().map(|x| x + options.radius).collect(),
+            );
+            let solver = ParticleSwarm::new(bounds, options.n_particles);
+            let res = Executor::new(obj, solver)
+                .configure(|state| state.max_iters(options.max_iters))
+                .run()?;
+            let reason = res.state.termination_reason;
+            // the particles start at random, so they may not improve on the starting point
+            match res.state.best_individual {
+                Some(best) if best.cost <= init_cost => Ok((best.position, reason)),
+                _ => Ok((init_param, reason)),
+            }
+        },
+        BestResponseMethod::SimulatedAnnealing(options) => {
+            let obj = Annealing {
+                obj,
+                step_size: options.step_size,
+                init_temp: options.init_temp,
+                rng: RefCell::new(new_rng(options.seed.map(|seed| sub_seed(seed, 1)))),
+            };
+            let solver = SimulatedAnnealing::new_with_rng(options.init_temp, new_rng(options.seed))?
+                .with_stall_best(options.stall_best);
+            let res = Executor::new(obj, solver)
+                .configure(|state| state.param(init_param).max_iters(options.max_iters))
+                .run()?;
+            Ok((res.state.best_param.unwrap(), res.state.termination_reason))
+        },
+    }
+}
+
 struct PlayerObjective<'a, A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>>{
     pub payoff_aggregator: &'a T,
     pub i: usize,
     pub base_strategies: &'a S,
+    pub constraints: &'a [LinearConstraint],
+    pub interrupt: Interrupt<'a>,
+    // reuses work between evaluations, since only player i's strategy changes
+    pub evaluator: RefCell<Box<dyn PlayerEvaluator + 'a>>,
 }
 
 // implement traits needed for argmin
@@ -83,61 +512,219 @@ impl<A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>> CostFun
     type Output = f64;
 
     fn cost(&self, params: &Self::Param) -> Result<Self::Output, argmin::core::Error> {
+        let x = self.values_from(params)?;
+        let z = self.projected(&x)?;
+        let distance = (&x - &z).mapv(|d| d * d).sum();
+        Ok(DISTANCE_WEIGHT * distance - self.evaluator.borrow_mut().u_i(z.view()))
+    }
+}
+
+impl<A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>> Gradient for PlayerObjective<'_, A, S, T> {
+    type Param = Vec<f64>;
+    type Gradient = Vec<f64>;
+
+    fn gradient(&self, params: &Self::Param) -> Result<Self::Gradient, argmin::core::Error> {
+        let x = self.values_from(params)?;
+        let z = self.projected(&x)?;
+        let grad = self.payoff_aggregator.grad_i(self.i, &self.strategies_with(&z));
+        let grad = if constrains(self.i, self.constraints) {
+            // chain rule through the projection, for both the payoff and the distance term
+            let jacobian = project_jacobian(x.view(), self.i, self.constraints);
+            let g = Array::from_iter(grad.iter().cloned());
+            let d = Array::from_iter((&x - &z).iter().cloned());
+            let grad = jacobian.t().dot(&g) - 2. * DISTANCE_WEIGHT * (&d - &jacobian.t().dot(&d));
+            grad.into_shape(x.dim())?
+        } else {
+            grad
+        };
+        // params are logs of strategy values, so scale by exp(params)
+        Ok(grad.iter().zip(params.iter()).map(|(g, x)| -g * x.exp()).collect())
+    }
+}
+
+impl<A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>> PlayerObjective<'_, A, S, T> {
+    fn strategies_with(&self, x: &Array<f64, Ix2>) -> S {
         let mut strategies = self.base_strategies.clone();
-        strategies.data_mut().slice_mut(s![.., self.i, ..]).assign(
-            &Array::from_shape_vec(
-                (self.base_strategies.t(), S::nparams()),
-                params.iter().map(|x| x.exp()).collect(),
-            )?
-        );
-        Ok(-self.payoff_aggregator.u_i(self.i, &strategies))
+        strategies.data_mut().slice_mut(s![.., self.i, ..]).assign(x);
+        strategies
+    }
+
+    // player i's strategy values
+    fn values_from(&self, params: &[f64]) -> Result<Array<f64, Ix2>, argmin::core::Error> {
+        // checked on every evaluation so that long best-response searches stop promptly
+        if let Some(reason) = self.interrupt.check() {
+            return Err(argmin::core::Error::msg(format!("Solve stopped early: {:?}", reason)));
+        }
+        Ok(Array::from_shape_vec(
+            (self.base_strategies.t(), S::nparams()),
+            params.iter().map(|x| x.exp()).collect(),
+        )?)
+    }
+
+    fn projected(&self, x: &Array<f64, Ix2>) -> Result<Array<f64, Ix2>, argmin::core::Error> {
+        project(x.view(), self.i, self.constraints).map_err(argmin::core::Error::msg)
     }
 }
 
-fn create_simplex(init_guess: ArrayView<f64, Ix2>, init_simplex_size: f64) -> Vec<Vec<f64>> {
-    let mut simplex = Vec::new();
-    let base: Vec<f64> = init_guess.iter().map(|x| x.ln()).collect();
+fn create_simplex(base: &[f64], init_simplex_size: f64) -> Vec<Vec<f64>> {
+    let mut simplex = Vec::with_capacity(base.len() + 1);
     for i in 0..base.len() {
-        let mut x = base.clone();
+        let mut x = base.to_vec();
         x[i] += init_simplex_size;
         simplex.push(x);
     }
-    simplex.push(base);
+    simplex.push(base.to_vec());
     simplex
 }
 
-fn solve_for_i<A, S, T>(i: usize, strat: &S, agg: &T, options: &NMOptions) -> Result<Array<f64, Ix2>, argmin::core::Error>
+// settings for a single best-response search
+#[derive(Clone, Copy)]
+struct BestResponse<'a> {
+    method: &'a BestResponseMethod,
+    constraints: &'a [LinearConstraint],
+    interrupt: Interrupt<'a>,
+}
+
+fn solve_for_i<A, S, T>(
+    i: usize, strat: &S, agg: &T, br: BestResponse,
+) -> Result<(Array<f64, Ix2>, TerminationReason), argmin::core::Error>
 where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
 {
-    let init_simplex = create_simplex(
-        strat.data().slice(s![.., i, ..]),
-        options.init_simplex_size
-    );
+    let BestResponse { method, constraints, interrupt } = br;
     let obj = PlayerObjective {
         payoff_aggregator: agg,
         i,
         base_strategies: strat,
+        constraints,
+        interrupt,
+        evaluator: RefCell::new(agg.evaluator_i(i, strat)),
     };
-    let solver = NelderMead::new(init_simplex).with_sd_tolerance(options.tol).unwrap();
-    let res = Executor::new(obj, solver)
-        .configure(|state| state.max_iters(options.max_iters))
-        .run()?;
-    Ok(Array::from_shape_vec(
+    let init_param: Vec<f64> = strat.data().slice(s![.., i, ..]).iter().map(|x| x.ln()).collect();
+    let (best_param, termination_reason) = match minimize(obj, init_param, method) {
+        Ok(res) => res,
+        // projecting onto the constraints leaves kinks in the objective, which can stop
+        // the line search; the derivative-free method copes with those
+        Err(_) if matches!(method, BestResponseMethod::LBFGS(_))
+            && constrains(i, constraints) && interrupt.check().is_none() => return solve_for_i(
+            i, strat, agg, BestResponse { method: &BestResponseMethod::default(), ..br }
+        ),
+        Err(e) => return Err(e),
+    };
+    let best_param = Array::from_shape_vec(
         (strat.t(), S::nparams()),
-        res.state.best_param.unwrap().iter().map(|x| x.exp()).collect(),
-    )?)
+        best_param.iter().map(|x| x.exp()).collect(),
+    )?;
+    // payoffs were evaluated at the projection of the params, so that's the best response
+    let best_param = project(best_param.view(), i, constraints).map_err(argmin::core::Error::msg)?;
+    Ok((best_param, termination_reason))
 }
 
-fn update_strat<A, S, T>(strat: &mut S, agg: &T, nm_options: &NMOptions) -> Result<(), argmin::core::Error>
+// player i's best response to strat, searching from i's strategy in strat
+pub(crate) fn respond<A, S, T>(
+    i: usize, strat: &S, agg: &T, method: &BestResponseMethod, constraints: &[LinearConstraint],
+) -> Result<(Array<f64, Ix2>, TerminationReason), argmin::core::Error>
 where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
 {
-    let new_data = (0..strat.n()).into_par_iter().map(|i| {
-        solve_for_i(i, strat, agg, nm_options)
+    solve_for_i(i, strat, agg, BestResponse { method, constraints, interrupt: Interrupt::default() })
+}
+
+fn update_strat<A, S, T>(
+    strat: &mut S, agg: &T, options: &SolverOptions<S>, interrupt: Interrupt, symmetric: bool
+) -> Result<Vec<TerminationReason>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    let br = BestResponse {
+        method: &options.method,
+        constraints: &options.constraints,
+        interrupt,
+    };
+    // player 0 stands in for everyone
+    if symmetric {
+        let (x, reason) = solve_for_i(0, strat, agg, br)?;
+        for i in 0..strat.n() {
+            strat.data_mut().slice_mut(s![.., i, ..]).assign(&x);
+        }
+        return Ok(vec![reason; strat.n()]);
+    }
+    // frozen players never search, so their termination reason stays NotTerminated
+    let active = (0..strat.n()).filter(|i| !options.fixed_players.contains(i)).collect::<Vec<_>>();
+    let mut termination_reasons = vec![TerminationReason::NotTerminated; strat.n()];
+    if options.update == UpdateScheme::GaussSeidel {
+        for &i in active.iter() {
+            let (x, reason) = solve_for_i(i, strat, agg, br)?;
+            strat.data_mut().slice_mut(s![.., i, ..]).assign(&x);
+            termination_reasons[i] = reason;
+        }
+        return Ok(termination_reasons);
+    }
+    let new_data = active.par_iter().map(|&i| {
+        solve_for_i(i, strat, agg, br)
     }).collect::<Result<Vec<_>,_>>()?;
-    for (i, x) in new_data.iter().enumerate() {
-        strat.data_mut().slice_mut(s![.., i, ..]).assign(x);
+    for (&i, (x, reason)) in active.iter().zip(new_data) {
+        strat.data_mut().slice_mut(s![.., i, ..]).assign(&x);
+        termination_reasons[i] = reason;
+    }
+    Ok(termination_reasons)
+}
+
+// puts the frozen players' strategies back as they are in init, undoing any projection or averaging
+fn restore_fixed<S: StrategyType>(strat: &mut S, init: &S, fixed_players: &[usize]) {
+    for &i in fixed_players.iter() {
+        strat.data_mut().slice_mut(s![.., i, ..]).assign(&init.data().slice(s![.., i, ..]));
+    }
+}
+
+fn log_params<S: StrategyType>(strat: &S) -> Array<f64, Ix1> {
+    Array::from_iter(strat.data().iter().map(|x| x.ln()))
+}
+
+// extrapolates the next profile from the last few iterates y and their best responses g
+// (type II Anderson acceleration, in log space)
+struct Anderson {
+    m: usize,
+    ys: VecDeque<Array<f64, Ix1>>,
+    gs: VecDeque<Array<f64, Ix1>>,
+}
+
+impl Anderson {
+    fn new(m: usize) -> Self {
+        Anderson { m, ys: VecDeque::new(), gs: VecDeque::new() }
+    }
+
+    fn next(&mut self, y: Array<f64, Ix1>, g: Array<f64, Ix1>) -> Array<f64, Ix1> {
+        self.ys.push_back(y);
+        self.gs.push_back(g.clone());
+        if self.ys.len() > self.m + 1 {
+            self.ys.pop_front();
+            self.gs.pop_front();
+        }
+        let k = self.ys.len() - 1;
+        if k == 0 {
+            return g;
+        }
+        let f = |j: usize| &self.gs[j] - &self.ys[j];
+        let df = (0..k).map(|j| f(j + 1) - f(j)).collect::<Vec<_>>();
+        let dg = (0..k).map(|j| &self.gs[j + 1] - &self.gs[j]).collect::<Vec<_>>();
+        // least squares for the mixing weights, via the (slightly regularized) normal equations
+        let f_k = f(k);
+        let mut a = Array::from_shape_fn((k, k), |(p, q)| df[p].dot(&df[q]));
+        let scale = a.diag().fold(0., |acc: f64, x| acc.max(*x));
+        a.diag_mut().mapv_inplace(|x| x + 1e-10 * scale);
+        let b = Array::from_iter(df.iter().map(|d| d.dot(&f_k)));
+        let weights = solve_linear(a.view(), b.view());
+        let next = weights.map(|w| {
+            w.iter().zip(dg.iter()).fold(g.clone(), |acc, (w, d)| acc - *w * d)
+        });
+        match next {
+            Some(next) if next.iter().all(|x| x.is_finite()) => next,
+            // start again from a plain update
+            _ => {
+                self.ys.clear();
+                self.gs.clear();
+                g
+            },
+        }
     }
-    Ok(())
 }
 
 fn within_tol<S: StrategyType>(current: &S, last: &S, tol: f64) -> bool {
@@ -148,18 +735,334 @@ fn within_tol<S: StrategyType>(current: &S, last: &S, tol: f64) -> bool {
     )
 }
 
-pub fn solve<A, S, T>(agg: &T, options: &SolverOptions<S>) -> Result<S, argmin::core::Error>
+// largest absolute change in any log strategy value
+fn max_change<S: StrategyType>(current: &S, last: &S) -> f64 {
+    current.data().iter().zip(last.data().iter()).fold(0., |acc, (x, y)| {
+        f64::max(acc, (x.ln() - y.ln()).abs())
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct SolveResult<S: StrategyType> {
+    pub strategies: S,
+    pub converged: bool,
+    // number of outer (best-response) iterations performed
+    pub iterations: u64,
+    // largest change in any log strategy value on each iteration
+    pub max_changes: Vec<f64>,
+    // why each player's best-response search stopped on the last iteration
+    pub termination_reasons: Vec<TerminationReason>,
+    pub payoffs: Array<f64, Ix1>,
+    // every profile visited, starting with the initial guess; empty unless SolverOptions::trace is set
+    pub trace: Vec<S>,
+    pub stop_reason: StopReason,
+    // problems noticed during the solve, e.g. the profile cycling between two points
+    pub warnings: Vec<String>,
+    // whether this is the symmetric solution, found by solving for a single representative player
+    pub symmetric: bool,
+}
+
+impl<S: StrategyType + fmt::Display> fmt::Display for SolveResult<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.converged {
+            writeln!(f, "Converged after {} iterations{}", self.iterations, if self.symmetric { " (symmetric)" } else { "" })?;
+        } else if self.stop_reason != StopReason::MaxItersReached {
+            writeln!(f, "Stopped early ({:?}) after {} iterations", self.stop_reason, self.iterations)?;
+        } else {
+            writeln!(f, "Did not converge after {} iterations", self.iterations)?;
+        }
+        for warning in self.warnings.iter() {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        writeln!(f, "payoffs = {:.4}", self.payoffs)?;
+        write!(f, "{}", self.strategies)
+    }
+}
+
+// moves every player's strategy onto the set satisfying the constraints
+fn project_strat<S: StrategyType>(strat: &mut S, constraints: &[LinearConstraint]) -> Result<(), argmin::core::Error> {
+    for c in constraints.iter() {
+        c.validate(strat.t(), strat.n(), S::nparams()).map_err(argmin::core::Error::msg)?;
+    }
+    for i in 0..strat.n() {
+        let x = project(strat.data().slice(s![.., i, ..]), i, constraints).map_err(argmin::core::Error::msg)?;
+        strat.data_mut().slice_mut(s![.., i, ..]).assign(&x);
+    }
+    Ok(())
+}
+
+// whether swapping any two players' strategies just swaps their payoffs, checked at a few random profiles
+pub fn is_symmetric<A, S, T>(agg: &T, t: usize) -> bool
 where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
 {
-    let mut current_strat = options.init_guess.to_fixed(agg.n());
-    for i in 0..options.max_iters {
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..2).all(|_| {
+        let strategies = match S::random_using(t, agg.n(), INIT_MU, 1., &mut rng) {
+            Ok(strategies) => strategies,
+            Err(_) => return false,
+        };
+        let payoffs = agg.u(&strategies);
+        // swaps with player 0 are enough, since they generate every permutation
+        (1..agg.n()).all(|i| {
+            let mut swapped = strategies.clone();
+            let mut data = swapped.data_mut();
+            for t in 0..data.shape()[0] {
+                for m in 0..S::nparams() {
+                    data.swap([t, 0, m], [t, i, m]);
+                }
+            }
+            let mut expected = payoffs.clone();
+            expected.swap(0, i);
+            isapprox_iters(agg.u(&swapped).into_iter(), expected.into_iter(), 1e-9, 1e-12)
+        })
+    })
+}
+
+pub fn solve<A, S, T>(agg: &T, options: &SolverOptions<S>) -> Result<SolveResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    // copying the representative would move frozen players
+    let symmetric = match options.symmetry {
+        _ if !options.fixed_players.is_empty() => false,
+        Symmetry::Off => false,
+        Symmetry::Assume => true,
+        // constraints on particular players make them different
+        Symmetry::Detect => {
+            options.constraints.iter().all(|c| c.players.is_none())
+                && is_symmetric(agg, options.init_guess.t())
+        },
+    };
+    if !symmetric {
+        return solve_profile(agg, options, false);
+    }
+    let start = Instant::now();
+    let res = solve_profile(agg, options, true)?;
+    if !res.converged {
+        return Ok(res);
+    }
+    // the representative's best response is only checked against everyone else copying it,
+    // so make sure nobody actually gains by deviating
+    let check = verify_equilibrium(agg, &res.strategies, &VerifyOptions {
+        n_starts: 1,
+        method: options.method.clone(),
+        constraints: options.constraints.clone(),
+        ..VerifyOptions::default()
+    })?;
+    let deviator = (0..agg.n()).find(|&i| check.epsilons[i] > SYMMETRY_TOL * res.payoffs[i].abs().max(1.));
+    match deviator {
+        None => Ok(res),
+        Some(i) => {
+            let mut full = solve_profile(agg, &SolverOptions {
+                init_guess: InitGuess::Fixed(res.strategies),
+                timeout: options.timeout.map(|timeout| timeout.saturating_sub(start.elapsed())),
+                ..options.clone()
+            }, false)?;
+            full.warnings.insert(0, format!(
+                "the symmetric solution was not an equilibrium (player {} gains {:.3e} by deviating), \
+                so the game was solved without symmetry", i, check.epsilons[i]
+            ));
+            Ok(full)
+        },
+    }
+}
+
+fn solve_profile<A, S, T>(agg: &T, options: &SolverOptions<S>, symmetric: bool) -> Result<SolveResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    if let Some(&i) = options.fixed_players.iter().find(|&&i| i >= agg.n()) {
+        return Err(argmin::core::Error::msg(format!("Fixed player {} out of range for {} players", i, agg.n())));
+    }
+    let mut current_strat = options.init_guess.to_fixed(agg.n())?;
+    let init_strat = current_strat.clone();
+    if symmetric {
+        let x = current_strat.data().slice(s![.., 0, ..]).to_owned();
+        for i in 1..current_strat.n() {
+            current_strat.data_mut().slice_mut(s![.., i, ..]).assign(&x);
+        }
+    }
+    project_strat(&mut current_strat, &options.constraints)?;
+    restore_fixed(&mut current_strat, &init_strat, &options.fixed_players);
+    let mut trace = Vec::new();
+    if options.trace {
+        trace.push(current_strat.clone());
+    }
+    let mut max_changes = Vec::new();
+    let mut termination_reasons = Vec::new();
+    let start = Instant::now();
+    let interrupt = Interrupt {
+        cancel: options.cancel.as_ref(),
+        deadline: options.timeout.map(|timeout| start + timeout),
+    };
+    let mut stop_reason = StopReason::MaxItersReached;
+    let mut anderson = match options.update {
+        UpdateScheme::Anderson(m) => Some(Anderson::new(m)),
+        _ => None,
+    };
+    let mut before_last: Option<S> = None;
+    let mut warnings = Vec::new();
+    for _ in 0..options.max_iters {
         let last_strat = current_strat.clone();
-        update_strat(&mut current_strat, agg, &options.nm_options)?;
-        if within_tol(&current_strat, &last_strat, options.tol) {
-            println!("Exited on iteration {}", i);
-            return Ok(current_strat);
+        match update_strat(&mut current_strat, agg, options, interrupt, symmetric) {
+            Ok(reasons) => termination_reasons = reasons,
+            // an interrupted iteration leaves the profile as it was after the last complete one
+            Err(e) => match interrupt.check() {
+                Some(reason) => {
+                    current_strat = last_strat;
+                    stop_reason = reason;
+                    break;
+                },
+                None => return Err(e),
+            },
+        }
+        // convergence is judged on the best responses themselves, since damping shrinks every step
+        let converged = within_tol(&current_strat, &last_strat, options.tol);
+        max_changes.push(max_change(&current_strat, &last_strat));
+        if !converged {
+            let next = match (options.update, anderson.as_mut()) {
+                (UpdateScheme::Damped(w), _) => Some(
+                    (1. - w) * log_params(&last_strat) + w * log_params(&current_strat)
+                ),
+                (_, Some(anderson)) => Some(anderson.next(log_params(&last_strat), log_params(&current_strat))),
+                _ => None,
+            };
+            if let Some(next) = next {
+                current_strat = S::from_array_unchecked(next.mapv(f64::exp).into_shape(current_strat.data().dim())?);
+                project_strat(&mut current_strat, &options.constraints)?;
+                restore_fixed(&mut current_strat, &init_strat, &options.fixed_players);
+            }
+        }
+        if options.trace {
+            trace.push(current_strat.clone());
+        }
+        if let Some(progress) = &options.progress {
+            progress.call(&Progress {
+                iteration: max_changes.len() as u64,
+                max_change: *max_changes.last().unwrap(),
+                elapsed: start.elapsed(),
+                index: None,
+            });
+        }
+        if converged {
+            stop_reason = StopReason::Converged;
+            break;
+        }
+        if warnings.is_empty() && before_last.as_ref().is_some_and(|s| within_tol(&current_strat, s, options.tol)) {
+            warnings.push(format!(
+                "the profile is alternating between two points (from iteration {}); \
+                try a damped, Gauss-Seidel or Anderson update",
+                max_changes.len() - 1
+            ));
+        }
+        before_last = Some(last_strat);
+        if let Some(reason) = interrupt.check() {
+            stop_reason = reason;
+            break;
+        }
+    }
+    Ok(SolveResult {
+        payoffs: agg.u(&current_strat),
+        strategies: current_strat,
+        converged: stop_reason == StopReason::Converged,
+        iterations: max_changes.len() as u64,
+        max_changes,
+        termination_reasons,
+        trace,
+        stop_reason,
+        warnings,
+        symmetric,
+    })
+}
+
+
+#[derive(Clone, Debug)]
+pub struct VerifyOptions {
+    // number of best-response searches per player;
+    // the first starts from the profile being checked, the rest from random draws
+    pub n_starts: usize,
+    pub init_mu: f64,
+    pub init_sigma: f64,
+    pub method: BestResponseMethod,
+    // deviations are restricted to those satisfying these constraints
+    pub constraints: Vec<LinearConstraint>,
+    // players who aren't strategic, so can't deviate
+    pub fixed_players: Vec<usize>,
+    // the random starting points are drawn reproducibly from this seed
+    pub seed: u64,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        VerifyOptions {
+            n_starts: 8,
+            init_mu: INIT_MU,
+            init_sigma: 1.0,
+            method: BestResponseMethod::default(),
+            constraints: Vec::new(),
+            fixed_players: Vec::new(),
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EquilibriumCheck<S: StrategyType> {
+    // payoffs from the profile being checked
+    pub payoffs: Array<f64, Ix1>,
+    // largest payoff gain each player can get by deviating unilaterally
+    pub epsilons: Array<f64, Ix1>,
+    // for each player, the profile with that player's best deviation swapped in
+    pub deviations: Vec<S>,
+}
+
+impl<S: StrategyType> EquilibriumCheck<S> {
+    // the profile is an epsilon-Nash equilibrium for this epsilon
+    pub fn epsilon(&self) -> f64 {
+        self.epsilons.fold(0., |acc, x| f64::max(acc, *x))
+    }
+}
+
+pub fn verify_equilibrium<A, S, T>(agg: &T, strategies: &S, options: &VerifyOptions) -> Result<EquilibriumCheck<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    if options.n_starts == 0 {
+        return Err(argmin::core::Error::msg("n_starts must be at least 1"));
+    }
+    let payoffs = agg.u(strategies);
+    let starts = (0..strategies.n()).filter(|i| !options.fixed_players.contains(i)).flat_map(|i| {
+        (0..options.n_starts).map(move |k| (i, k))
+    }).map(|(i, k)| {
+        let mut start = strategies.clone();
+        if k != 0 {
+            let mut rng = StdRng::seed_from_u64(sub_seed(options.seed, i * options.n_starts + k));
+            let draw = S::random_using(strategies.t(), 1, options.init_mu, options.init_sigma, &mut rng)
+                .map_err(argmin::core::Error::msg)?;
+            let draw = project(draw.data().slice(s![.., 0, ..]), i, &options.constraints)
+                .map_err(argmin::core::Error::msg)?;
+            start.data_mut().slice_mut(s![.., i, ..]).assign(&draw);
+        }
+        Ok((i, start))
+    }).collect::<Result<Vec<_>, argmin::core::Error>>()?;
+    let responses = starts.into_par_iter().map(|(i, start)| {
+        let br = BestResponse {
+            method: &options.method,
+            constraints: &options.constraints,
+            interrupt: Interrupt::default(),
+        };
+        let (response, _) = solve_for_i(i, &start, agg, br)?;
+        let mut deviation = start;
+        deviation.data_mut().slice_mut(s![.., i, ..]).assign(&response);
+        let u_i = agg.u_i(i, &deviation);
+        Ok((i, u_i, deviation))
+    }).collect::<Result<Vec<_>, argmin::core::Error>>()?;
+
+    let mut epsilons = Array::zeros(strategies.n());
+    let mut deviations = vec![strategies.clone(); strategies.n()];
+    for (i, u_i, deviation) in responses {
+        let gain = u_i - payoffs[i];
+        if gain > epsilons[i] {
+            epsilons[i] = gain;
+            deviations[i] = deviation;
         }
     }
-    println!("Reached max iterations ({})", options.max_iters);
-    Ok(current_strat)
+    Ok(EquilibriumCheck { payoffs, epsilons, deviations })
 }
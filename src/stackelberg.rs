@@ -0,0 +1,226 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use numpy::ndarray::{Array, Axis, Ix1, Ix2, s};
+
+use crate::constraints::subgame_constraints;
+use crate::solve::{BestResponseMethod, InitGuess, SolveResult, SolverOptions, remaining, solve};
+use crate::states::PayoffAggregator;
+use crate::strategies::*;
+
+// the strategies of just the given players, in that order
+fn select<S: StrategyType>(strategies: &S, players: &[usize]) -> S {
+    S::from_array_unchecked(strategies.data().select(Axis(1), players))
+}
+
+// copy of base with the given players' strategies replaced by those in sub
+fn embed<S: StrategyType>(base: &S, players: &[usize], sub: &S) -> S {
+    let mut strategies = base.clone();
+    for (k, &i) in players.iter().enumerate() {
+        strategies.data_mut().slice_mut(s![.., i, ..]).assign(&sub.data().slice(s![.., k, ..]));
+    }
+    strategies
+}
+
+// fixed players of the game among a subset of players, reindexed to match
+fn subgame_fixed_players(fixed_players: &[usize], players: &[usize]) -> Vec<usize> {
+    (0..players.len()).filter(|&k| fixed_players.contains(&players[k])).collect()
+}
+
+// the game among a subset of players, with everyone else's strategies held fixed at base;
+// strategies for this game only include the players in the subset
+struct SubGame<'a, A, S, T>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    agg: &'a T,
+    base: S,
+    players: &'a [usize],
+    _phantom: PhantomData<A>,
+}
+
+impl<A, S, T> PayoffAggregator<A, S> for SubGame<'_, A, S, T>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    fn n(&self) -> usize {
+        self.players.len()
+    }
+    fn u_i(&self, i: usize, strategies: &S) -> f64 {
+        self.agg.u_i(self.players[i], &embed(&self.base, self.players, strategies))
+    }
+    fn grad_i(&self, i: usize, strategies: &S) -> Array<f64, Ix2> {
+        self.agg.grad_i(self.players[i], &embed(&self.base, self.players, strategies))
+    }
+}
+
+// equilibrium among the followers when everyone else plays as in strategies,
+// starting the followers from their strategies in base
+fn follower_equilibrium<A, S, T>(
+    agg: &T, strategies: &S, followers: &[usize], options: &SolverOptions<S>, deadline: Option<Instant>
+) -> Result<(S, bool), argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    if followers.is_empty() {
+        return Ok((strategies.clone(), true));
+    }
+    let game = SubGame { agg, base: strategies.clone(), players: followers, _phantom: PhantomData };
+    let follower_options = SolverOptions {
+        init_guess: InitGuess::Fixed(select(strategies, followers)),
+        trace: false,
+        constraints: subgame_constraints(&options.constraints, followers),
+        fixed_players: subgame_fixed_players(&options.fixed_players, followers),
+        // the followers respond once per evaluation of a leader's payoff, too often to report
+        progress: None,
+        timeout: remaining(deadline),
+        ..options.clone()
+    };
+    let res = solve(&game, &follower_options)?;
+    Ok((embed(strategies, followers, &res.strategies), res.converged))
+}
+
+// the game among the leaders, where each leader's payoff is evaluated after
+// the followers have reached their equilibrium response
+struct LeaderGame<'a, A, S, T>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    agg: &'a T,
+    base: S,
+    leaders: &'a [usize],
+    followers: &'a [usize],
+    follower_options: &'a SolverOptions<S>,
+    deadline: Option<Instant>,
+    _phantom: PhantomData<A>,
+}
+
+impl<A, S, T> PayoffAggregator<A, S> for LeaderGame<'_, A, S, T>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    fn n(&self) -> usize {
+        self.leaders.len()
+    }
+    fn u_i(&self, i: usize, strategies: &S) -> f64 {
+        let strategies = embed(&self.base, self.leaders, strategies);
+        match follower_equilibrium(self.agg, &strategies, self.followers, self.follower_options, self.deadline) {
+            Ok((strategies, _)) => self.agg.u_i(self.leaders[i], &strategies),
+            // treat plans for which the followers' response can't be found as infinitely bad
+            Err(_) => f64::NEG_INFINITY,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StackelbergOptions<S: StrategyType> {
+    // players who commit to their strategies first; everyone else is a follower
+    pub leaders: Vec<usize>,
+    // options for the Nash solution and the followers' response;
+    // init_guess is the starting profile for the Nash solution, the timeout covers the whole solve,
+    // and progress is reported for the Nash solution and then the leaders' problem
+    pub solver_options: SolverOptions<S>,
+    // options for the leaders' problem, which starts from the Nash solution;
+    // the leaders' payoffs are only known numerically, so derivative-free methods are more reliable
+    pub leader_max_iters: u64,
+    pub leader_tol: f64,
+    pub leader_method: BestResponseMethod,
+}
+
+impl<S: StrategyType> StackelbergOptions<S> {
+    pub fn new(t: usize, leaders: Vec<usize>) -> Self {
+        StackelbergOptions {
+            leaders,
+            solver_options: SolverOptions::random_init(t),
+            leader_max_iters: 100,
+            leader_tol: 1e-4,
+            leader_method: BestResponseMethod::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StackelbergResult<S: StrategyType> {
+    // full profile, with leaders' plans and the followers' equilibrium response
+    pub strategies: S,
+    pub leaders: Vec<usize>,
+    pub followers: Vec<usize>,
+    // whether both the leaders' problem and the final followers' response converged
+    pub converged: bool,
+    pub payoffs: Array<f64, Ix1>,
+    // the simultaneous-move solution the result is compared against
+    pub nash: SolveResult<S>,
+}
+
+impl<S: StrategyType> StackelbergResult<S> {
+    pub fn leader_strategies(&self) -> S {
+        select(&self.strategies, &self.leaders)
+    }
+    pub fn follower_strategies(&self) -> S {
+        select(&self.strategies, &self.followers)
+    }
+    // gain in payoff for each player relative to the Nash solution
+    pub fn first_mover_advantage(&self) -> Array<f64, Ix1> {
+        &self.payoffs - &self.nash.payoffs
+    }
+}
+
+impl<S: StrategyType + fmt::Display> fmt::Display for StackelbergResult<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.converged {
+            writeln!(f, "Converged with leaders {:?}", self.leaders)?;
+        } else {
+            writeln!(f, "Did not converge with leaders {:?}", self.leaders)?;
+        }
+        writeln!(f, "payoffs = {:.4}", self.payoffs)?;
+        writeln!(f, "first mover advantage = {:.4}", self.first_mover_advantage())?;
+        write!(f, "{}", self.strategies)
+    }
+}
+
+pub fn solve_stackelberg<A, S, T>(agg: &T, options: &StackelbergOptions<S>) -> Result<StackelbergResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    let n = agg.n();
+    let leaders = &options.leaders;
+    if leaders.is_empty() {
+        return Err(argmin::core::Error::msg("Stackelberg solve needs at least one leader"));
+    }
+    if leaders.iter().any(|&i| i >= n) {
+        return Err(argmin::core::Error::msg("Leader indices must be less than n"));
+    }
+    if (1..leaders.len()).any(|k| leaders[..k].contains(&leaders[k])) {
+        return Err(argmin::core::Error::msg("Leader indices must be unique"));
+    }
+    let followers = (0..n).filter(|i| !leaders.contains(i)).collect::<Vec<_>>();
+
+    let deadline = options.solver_options.timeout.map(|timeout| Instant::now() + timeout);
+    let nash = solve(agg, &options.solver_options)?;
+    let game = LeaderGame {
+        agg,
+        base: nash.strategies.clone(),
+        leaders,
+        followers: &followers,
+        follower_options: &options.solver_options,
+        deadline,
+        _phantom: PhantomData,
+    };
+    let leader_options = SolverOptions {
+        init_guess: InitGuess::Fixed(select(&nash.strategies, leaders)),
+        max_iters: options.leader_max_iters,
+        tol: options.leader_tol,
+        method: options.leader_method.clone(),
+        trace: false,
+        constraints: subgame_constraints(&options.solver_options.constraints, leaders),
+        fixed_players: subgame_fixed_players(&options.solver_options.fixed_players, leaders),
+        timeout: remaining(deadline),
+        ..options.solver_options.clone()
+    };
+    let leader_res = solve(&game, &leader_options)?;
+    let strategies = embed(&nash.strategies, leaders, &leader_res.strategies);
+    let (strategies, followers_converged) = follower_equilibrium(agg, &strategies, &followers, &options.solver_options, deadline)?;
+    Ok(StackelbergResult {
+        payoffs: agg.u(&strategies),
+        strategies,
+        leaders: leaders.clone(),
+        followers,
+        converged: leader_res.converged && followers_converged,
+        nash,
+    })
+}
@@ -1,16 +1,18 @@
 use std::marker::PhantomData;
 
 use numpy::Ix2;
-use numpy::ndarray::{Array, Ix1};
+use numpy::ndarray::{Array, ArrayView, Axis, Ix1, s};
 
 use crate::cost_func::CostFunc;
 use crate::csf::CSF;
 use crate::disaster_cost::DisasterCost;
+use crate::params::{Params, index_mut, parse_index, split_param, unknown_param};
 use crate::prod_func::ProdFunc;
 use crate::reward_func::RewardFunc;
 use crate::risk_func::RiskFunc;
 use crate::strategies::*;
 use crate::payoff_func::{PayoffFunc, DefaultPayoff};
+use crate::utils::fd_step;
 
 pub trait State<T: PayoffFunc>: Clone + Send + Sync {
     fn n(&self) -> usize;
@@ -41,6 +43,23 @@ impl<T: PayoffFunc> State<T> for HetBeliefs<T> {
     }
 }
 
+impl<T: PayoffFunc + Params> Params for HetBeliefs<T> {
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        let (head, rest) = split_param(name);
+        match parse_index(head)? {
+            ("beliefs", Some(k)) => match self.beliefs.get_mut(k) {
+                Some(belief) => belief.param_mut(rest),
+                None => Err(format!("Index out of bounds for parameter: {}", name)),
+            },
+            _ => Err(unknown_param(name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.beliefs.iter().try_for_each(|belief| belief.validate())
+    }
+}
+
 impl<T: PayoffFunc> HetBeliefs<T> {
     pub fn new(beliefs: Vec<T>) -> Result<HetBeliefs<T>, &'static str> {
         if beliefs.len() == 0 {
@@ -62,6 +81,67 @@ where A: ActionType, S: StrategyType<Act = A>
     type StateType: State<Self::PFunc>;
     fn state0(&self) -> &Self::StateType;
     fn advance_state(&self, _state: &mut Self::StateType, _actions: &A) {}
+
+    // gradient of player i's payoff in the given state w.r.t. i's action params in any earlier period,
+    // through the effect those actions had on the state via advance_state;
+    // None if that effect is not known analytically
+    fn carried_grad_i(&self, _state: &Self::StateType, _i: usize, _actions: &A) -> Option<Array<f64, Ix1>> {
+        None
+    }
+
+    // probability, according to player i, that the game carries on past a period with the given actions
+    fn proba_continue(&self, _state: &Self::StateType, _i: usize, _actions: &A) -> f64 {
+        1.
+    }
+
+    // whether advance_state never changes the state, so that it doesn't need to be copied for each period
+    const FIXED_STATE: bool = false;
+
+    // how the periods after the last one are valued; None means they're worth nothing
+    fn terminal_value(&self) -> Option<&TerminalValue> {
+        None
+    }
+}
+
+// value of the game beyond the final period, so that a finite horizon approximates an infinite one
+#[derive(Clone, Debug)]
+pub enum TerminalValue {
+    // each player's continuation value, as of the period after the last one
+    Fixed(Array<f64, Ix1>),
+    // the last period's actions are repeated forever, starting from the state reached after the last period,
+    // which is held fixed from then on; this is exact when the state never changes
+    Stationary,
+}
+
+impl TerminalValue {
+    fn validate(&self, gammas: &Array<f64, Ix1>) -> Result<(), &'static str> {
+        match self {
+            TerminalValue::Fixed(values) if values.len() != gammas.len() => {
+                Err("Terminal values must have length == n")
+            },
+            TerminalValue::Stationary if gammas.iter().any(|&g| g >= 1.) => {
+                Err("Stationary terminal values require every gamma to be less than 1")
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+// player i's terminal value, discounted back by the t periods played,
+// given the state and actions in the last of those periods
+pub(crate) fn terminal_u_i<A, S, T>(agg: &T, state: &T::StateType, i: usize, actions: &A, gamma: f64, t: usize) -> f64
+where A: ActionType, S: StrategyType<Act = A>, T: StateIterator<A, S> + ?Sized
+{
+    let discount = gamma.powi(t.try_into().unwrap());
+    match agg.terminal_value() {
+        None => 0.,
+        Some(TerminalValue::Fixed(values)) => discount * values[i],
+        Some(TerminalValue::Stationary) => {
+            let mut state = state.clone();
+            agg.advance_state(&mut state, actions);
+            discount / (1. - gamma) * state.belief(i).u_i(i, actions)
+        },
+    }
 }
 
 pub trait PayoffAggregator<A, S>: Send + Sync
@@ -72,6 +152,146 @@ where A: ActionType, S: StrategyType<Act = A>
     fn u(&self, strategies: &S) -> Array<f64, Ix1> {
         Array::from_iter((0..strategies.n()).map(|i| self.u_i(i, strategies)))
     }
+
+    // gradient of u_i w.r.t. player i's strategy params, as a t x nparams array
+    // defaults to finite differences; override when an analytic form is available
+    fn grad_i(&self, i: usize, strategies: &S) -> Array<f64, Ix2> {
+        fd_grad_with(&mut *self.evaluator_i(i, strategies), strategies.data().index_axis(Axis(1), i))
+    }
+
+    // probability of a disaster at some point over the whole game, according to each player's beliefs;
+    // None if the payoff functions have no notion of disaster
+    fn cumulative_disaster_proba(&self, _strategies: &S) -> Option<Array<f64, Ix1>> {
+        None
+    }
+
+    // evaluator for u_i as player i's own strategy varies, with everyone else playing as in base;
+    // the default evaluates the whole game every time, so override when work can be reused
+    fn evaluator_i<'a>(&'a self, i: usize, base: &S) -> Box<dyn PlayerEvaluator + 'a> where A: 'a, S: 'a {
+        Box::new(FullEvaluator { agg: self, i, strategies: base.clone(), _phantom: PhantomData })
+    }
+}
+
+// evaluates player i's payoff for different choices of i's own strategy,
+// given as a t x nparams array; must agree exactly with PayoffAggregator::u_i
+pub trait PlayerEvaluator {
+    fn u_i(&mut self, x: ArrayView<f64, Ix2>) -> f64;
+}
+
+struct FullEvaluator<'a, A, S, T: ?Sized> {
+    agg: &'a T,
+    i: usize,
+    strategies: S,
+    _phantom: PhantomData<A>,
+}
+
+impl<A, S, T> PlayerEvaluator for FullEvaluator<'_, A, S, T>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S> + ?Sized
+{
+    fn u_i(&mut self, x: ArrayView<f64, Ix2>) -> f64 {
+        self.strategies.data_mut().slice_mut(s![.., self.i, ..]).assign(&x);
+        self.agg.u_i(self.i, &self.strategies)
+    }
+}
+
+// keeps the state at the start of each period, together with the payoff accumulated up to then,
+// so that a change to player i's strategy only replays the game from the first period it affects
+pub struct CachedEvaluator<'a, A, S, T>
+where A: ActionType, S: StrategyType<Act = A>, T: StateIterator<A, S>
+{
+    agg: &'a T,
+    i: usize,
+    gamma: f64,
+    // everyone's actions, with player i's from the last evaluation
+    actions: Vec<A>,
+    // state at the start of each period (just the first if T::FIXED_STATE)
+    states: Vec<T::StateType>,
+    // payoff accumulated before each period, and the probability of reaching it
+    values: Vec<f64>,
+    probas: Vec<f64>,
+    // number of periods whose entries are up to date with actions
+    computed: usize,
+}
+
+impl<'a, A, S, T> CachedEvaluator<'a, A, S, T>
+where A: ActionType, S: StrategyType<Act = A>, T: StateIterator<A, S>
+{
+    pub fn new(agg: &'a T, i: usize, gamma: f64, base: &S) -> Self {
+        let actions: Vec<A> = base.data().outer_iter().map(|x| A::from_array_unchecked(x.to_owned())).collect();
+        let nstates = if T::FIXED_STATE { 1 } else { actions.len() };
+        CachedEvaluator {
+            agg, i, gamma,
+            states: vec![agg.state0().clone(); nstates],
+            values: vec![0.; actions.len() + 1],
+            probas: vec![1.; actions.len() + 1],
+            computed: 0,
+            actions,
+        }
+    }
+}
+
+impl<A, S, T> PlayerEvaluator for CachedEvaluator<'_, A, S, T>
+where A: ActionType, S: StrategyType<Act = A>, T: StateIterator<A, S>
+{
+    fn u_i(&mut self, x: ArrayView<f64, Ix2>) -> f64 {
+        let i = self.i;
+        let last = self.actions.len() - 1;
+        let t0 = (0..self.computed)
+            .find(|&t| self.actions[t].data().row(i) != x.row(t))
+            .unwrap_or(self.computed);
+        for t in t0..=last {
+            self.actions[t].data_mut().row_mut(i).assign(&x.row(t));
+            let state = &self.states[if T::FIXED_STATE { 0 } else { t }];
+            let actions = &self.actions[t];
+            // same order of operations as in PayoffAggregator::u_i, so that results match exactly
+            self.values[t + 1] = self.values[t]
+                + self.probas[t] * self.gamma.powi(t.try_into().unwrap()) * state.belief(i).u_i(i, actions);
+            if t != last {
+                self.probas[t + 1] = self.probas[t] * self.agg.proba_continue(state, i, actions);
+                if !T::FIXED_STATE {
+                    let mut next_state = state.clone();
+                    self.agg.advance_state(&mut next_state, actions);
+                    self.states[t + 1] = next_state;
+                }
+            }
+        }
+        self.computed = self.actions.len();
+        let state = &self.states[if T::FIXED_STATE { 0 } else { last }];
+        self.values[last + 1] + terminal_u_i(self.agg, state, i, &self.actions[last], self.gamma, last + 1)
+    }
+}
+
+// finite difference approximation of the gradient of u_i w.r.t. player i's strategy x,
+// perturbing later periods last so that incremental evaluators can reuse earlier ones
+pub fn fd_grad_with(evaluator: &mut dyn PlayerEvaluator, x: ArrayView<f64, Ix2>) -> Array<f64, Ix2> {
+    let mut x_ = x.to_owned();
+    Array::from_shape_fn(x.dim(), |(t, m)| {
+        let x = x_[[t, m]];
+        let h = fd_step(x);
+        x_[[t, m]] = x + h;
+        let up = evaluator.u_i(x_.view());
+        x_[[t, m]] = x - h;
+        let down = evaluator.u_i(x_.view());
+        x_[[t, m]] = x;
+        (up - down) / (2. * h)
+    })
+}
+
+// finite difference approximation of the gradient of u_i w.r.t. player i's strategy params
+pub fn fd_grad_i<S, F>(u_i: F, i: usize, strategies: &S) -> Array<f64, Ix2>
+where S: StrategyType, F: Fn(&S) -> f64
+{
+    let mut strategies_ = strategies.clone();
+    Array::from_shape_fn((strategies.t(), S::nparams()), |(t, m)| {
+        let x = strategies.data()[[t, i, m]];
+        let h = fd_step(x);
+        strategies_.data_mut()[[t, i, m]] = x + h;
+        let up = u_i(&strategies_);
+        strategies_.data_mut()[[t, i, m]] = x - h;
+        let down = u_i(&strategies_);
+        strategies_.data_mut()[[t, i, m]] = x;
+        (up - down) / (2. * h)
+    })
 }
 
 pub trait Discounter {
@@ -95,7 +315,7 @@ where A: ActionType, S: StrategyType<Act = A>, T: StateIterator<A, S> + Discount
                 self.advance_state(state, actions);
             }
         }
-        u
+        u + terminal_u_i(self, state, i, actions_seq.last().unwrap(), gammas[i], strategies.t())
     }
     fn u(&self, strategies: &S) -> Array<f64, Ix1> {
         let actions_seq = strategies.clone().to_actions();
@@ -110,8 +330,67 @@ where A: ActionType, S: StrategyType<Act = A>, T: StateIterator<A, S> + Discount
                 self.advance_state(state, actions);
             }
         }
+        for (i, (u_i, gamma)) in u.iter_mut().zip(gammas.iter()).enumerate() {
+            *u_i += terminal_u_i(self, state, i, actions_seq.last().unwrap(), *gamma, strategies.t());
+        }
         u
     }
+    fn grad_i(&self, i: usize, strategies: &S) -> Array<f64, Ix2> {
+        let actions_seq = strategies.clone().to_actions();
+        let state = &mut self.state0().clone();
+        let gamma = self.gammas()[i];
+        let mut grad = Array::zeros((strategies.t(), S::nparams()));
+        let mut carried = Vec::with_capacity(strategies.t());
+        for (t, actions) in actions_seq.iter().enumerate() {
+            let discount = gamma.powi(t.try_into().unwrap());
+            grad.row_mut(t).assign(&(state.belief(i).du_i(i, actions) * discount));
+            match self.carried_grad_i(state, i, actions) {
+                Some(c) => carried.push(c * discount),
+                None => return fd_grad_with(&mut *self.evaluator_i(i, strategies), strategies.data().index_axis(Axis(1), i)),
+            }
+            if t != strategies.t() - 1 {
+                self.advance_state(state, actions);
+            }
+        }
+        // actions in period t also affect payoffs in all later periods through the state
+        let mut future: Array<f64, Ix1> = Array::zeros(S::nparams());
+        // a stationary terminal value works like one more period, repeating the last actions
+        if let Some(TerminalValue::Stationary) = self.terminal_value() {
+            let last = actions_seq.last().unwrap();
+            let discount = gamma.powi(strategies.t().try_into().unwrap()) / (1. - gamma);
+            self.advance_state(state, last);
+            let mut row = grad.row_mut(strategies.t() - 1);
+            row += &(state.belief(i).du_i(i, last) * discount);
+            match self.carried_grad_i(state, i, last) {
+                Some(c) => future += &(c * discount),
+                None => return fd_grad_with(&mut *self.evaluator_i(i, strategies), strategies.data().index_axis(Axis(1), i)),
+            }
+        }
+        for t in (0..strategies.t()).rev() {
+            let mut row = grad.row_mut(t);
+            row += &future;
+            future += &carried[t];
+        }
+        grad
+    }
+    fn cumulative_disaster_proba(&self, strategies: &S) -> Option<Array<f64, Ix1>> {
+        let actions_seq = strategies.clone().to_actions();
+        let state = &mut self.state0().clone();
+        // probability of getting through every period so far without a disaster
+        let mut probas_safe: Array<f64, Ix1> = Array::ones(self.n());
+        for (t, actions) in actions_seq.iter().enumerate() {
+            for (i, proba_safe) in probas_safe.iter_mut().enumerate() {
+                *proba_safe *= 1. - state.belief(i).proba_disaster(actions)?;
+            }
+            if t != strategies.t() - 1 {
+                self.advance_state(state, actions);
+            }
+        }
+        Some(1. - probas_safe)
+    }
+    fn evaluator_i<'a>(&'a self, i: usize, base: &S) -> Box<dyn PlayerEvaluator + 'a> where A: 'a, S: 'a {
+        Box::new(CachedEvaluator::new(self, i, self.gammas()[i], base))
+    }
 }
 
 #[derive(Clone)]
@@ -120,6 +399,7 @@ where A: ActionType, S: StrategyType<Act = A>, P: PayoffFunc<Act = A>, T: State<
 {
     pub state: T,
     pub gammas: Array<f64, Ix1>,
+    pub terminal: Option<TerminalValue>,
     _phantoms: PhantomData<(A, S, P)>,
 }
 
@@ -130,7 +410,12 @@ where A: ActionType, S: StrategyType<Act = A>, P: PayoffFunc<Act = A>, T: State<
         if state.n() != gammas.len() {
             return Err("When creating new FixedStateDiscounter: gammas must have length == n");
         }
-        Ok(FixedStateDiscounter { state, gammas, _phantoms: PhantomData })
+        Ok(FixedStateDiscounter { state, gammas, terminal: None, _phantoms: PhantomData })
+    }
+
+    pub fn with_terminal_value(self, terminal: TerminalValue) -> Result<Self, &'static str> {
+        terminal.validate(&self.gammas)?;
+        Ok(FixedStateDiscounter { terminal: Some(terminal), ..self })
     }
 }
 
@@ -142,6 +427,36 @@ where A: ActionType, S: StrategyType<Act = A>, P: PayoffFunc<Act = A>, T: State<
     fn state0(&self) -> &T {
         &self.state
     }
+
+    fn carried_grad_i(&self, _state: &T, _i: usize, _actions: &A) -> Option<Array<f64, Ix1>> {
+        // state never changes
+        Some(Array::zeros(A::nparams()))
+    }
+
+    const FIXED_STATE: bool = true;
+
+    fn terminal_value(&self) -> Option<&TerminalValue> {
+        self.terminal.as_ref()
+    }
+}
+
+// parameters other than gammas are looked up in the state
+impl<A, S, P, T> Params for FixedStateDiscounter<A, S, P, T>
+where A: ActionType, S: StrategyType<Act = A>, P: PayoffFunc<Act = A>, T: State<P> + Params
+{
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        match parse_index(name)? {
+            ("gammas", index) => index_mut(&mut self.gammas, index, name),
+            _ => self.state.param_mut(name),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(terminal) = &self.terminal {
+            terminal.validate(&self.gammas)?;
+        }
+        self.state.validate()
+    }
 }
 
 impl<A, S, P, T> Discounter for FixedStateDiscounter<A, S, P, T>
@@ -163,6 +478,7 @@ where A: ActionType,
 {
     pub state0: T,
     pub gammas: Array<f64, Ix1>,
+    pub terminal: Option<TerminalValue>,
     _phantoms: PhantomData<(A, S, P)>,
 }
 
@@ -176,7 +492,12 @@ where A: ActionType,
         if state0.n() != gammas.len() {
             return Err("When creating new DynStateDiscounter: gammas must have length == n");
         }
-        Ok(DynStateDiscounter { state0, gammas, _phantoms: PhantomData })
+        Ok(DynStateDiscounter { state0, gammas, terminal: None, _phantoms: PhantomData })
+    }
+
+    pub fn with_terminal_value(self, terminal: TerminalValue) -> Result<Self, &'static str> {
+        terminal.validate(&self.gammas)?;
+        Ok(DynStateDiscounter { terminal: Some(terminal), ..self })
     }
 }
 
@@ -195,6 +516,36 @@ where A: ActionType,
     fn advance_state(&self, state: &mut T, actions: &A) {
         state.mutate_on_action_inplace(actions);
     }
+
+    fn carried_grad_i(&self, state: &T, i: usize, actions: &A) -> Option<Array<f64, Ix1>> {
+        state.belief(i).du_i_carried(i, actions)
+    }
+
+    fn terminal_value(&self) -> Option<&TerminalValue> {
+        self.terminal.as_ref()
+    }
+}
+
+// parameters other than gammas are looked up in the initial state
+impl<A, S, P, T> Params for DynStateDiscounter<A, S, P, T>
+where A: ActionType,
+      S: StrategyType<Act = A>,
+      P: PayoffFunc<Act = A>,
+      T: State<P> + MutatesOnAction<A> + Params
+{
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        match parse_index(name)? {
+            ("gammas", index) => index_mut(&mut self.gammas, index, name),
+            _ => self.state0.param_mut(name),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(terminal) = &self.terminal {
+            terminal.validate(&self.gammas)?;
+        }
+        self.state0.validate()
+    }
 }
 
 impl<A, S, P, T> Discounter for DynStateDiscounter<A, S, P, T>
@@ -211,6 +562,7 @@ where A: ActionType,
 pub type InvestExpDiscounter<P> = DynStateDiscounter<InvestActions, InvestStrategies, P, P>;
 
 
+#[derive(Clone)]
 pub struct EndsOnContestWin<A, S, T, U, V, W, X, Y, Z, C>
 where A: ActionType,
       S: StrategyType<Act = A>,
@@ -239,6 +591,7 @@ where A: ActionType,
       Z: State<DefaultPayoff<A, T, U, V, W, X, Y>> + MutatesOnAction<A>,
       C: Discounter + StateIterator<A, S, StateType = Z>
 {
+    // the child's terminal value, if any, is not used: payoffs here stop at the last period
     pub fn new(child: C) -> Self {
         EndsOnContestWin { child, _phantoms: PhantomData }
     }
@@ -285,6 +638,17 @@ where A: ActionType,
     fn advance_state(&self, state: &mut Z, actions: &A) {
         state.mutate_on_action_inplace(actions);
     }
+
+    fn carried_grad_i(&self, state: &Z, i: usize, actions: &A) -> Option<Array<f64, Ix1>> {
+        state.belief(i).du_i_carried(i, actions)
+    }
+
+    // the game ends once someone wins the contest
+    fn proba_continue(&self, state: &Z, i: usize, actions: &A) -> f64 {
+        let payoff_func = state.belief(i);
+        let (_, p) = payoff_func.prod_func.f(actions);
+        1. - payoff_func.csf.q(p.view()).iter().sum::<f64>()
+    }
 }
 
 impl<A, S, T, U, V, W, X, Y, Z, C> PayoffAggregator<A, S> for EndsOnContestWin<A, S, T, U, V, W, X, Y, Z, C>
@@ -345,4 +709,259 @@ where A: ActionType,
         }
         u
     }
+    fn grad_i(&self, i: usize, strategies: &S) -> Array<f64, Ix2> {
+        let actions_seq = strategies.clone().to_actions();
+        let mut state = self.child.state0().clone();
+        let gamma = self.child.gammas()[i];
+        let last = strategies.t() - 1;
+        let mut proba = 1.;  // probability that nobody has won yet
+        // per period: proba, discounted payoff and its direct and carried gradients,
+        // plus proba that someone wins and its direct and carried gradients
+        let mut probas = Vec::with_capacity(strategies.t());
+        let mut values = Vec::with_capacity(strategies.t());
+        let mut direct = Vec::with_capacity(strategies.t());
+        let mut carried = Vec::with_capacity(strategies.t());
+        let mut wins = Vec::with_capacity(last);
+        for (t, actions) in actions_seq.iter().enumerate() {
+            let payoff_func = state.belief(i);
+            let discount = gamma.powi(t.try_into().unwrap());
+            probas.push(proba);
+            values.push(discount * payoff_func.u_i(i, actions));
+            direct.push(payoff_func.du_i(i, actions) * discount);
+            match self.carried_grad_i(&state, i, actions) {
+                Some(c) => carried.push(c * discount),
+                None => return fd_grad_with(&mut *self.evaluator_i(i, strategies), strategies.data().index_axis(Axis(1), i)),
+            }
+            if t != last {
+                let (q, dq, dq_carried) = payoff_func.proba_win_with_grad(i, actions);
+                let dq_carried = match dq_carried {
+                    Some(c) => c,
+                    None => return fd_grad_with(&mut *self.evaluator_i(i, strategies), strategies.data().index_axis(Axis(1), i)),
+                };
+                proba *= 1. - q;
+                wins.push((q, dq, dq_carried));
+                self.advance_state(&mut state, actions);
+            }
+        }
+        // work backwards, keeping track of the value of periods after t
+        // with the probability of reaching them taken relative to period t + 1
+        let mut grad = Array::zeros((strategies.t(), S::nparams()));
+        let mut future_value = 0.;
+        let mut future_carried: Array<f64, Ix1> = Array::zeros(S::nparams());
+        for t in (0..strategies.t()).rev() {
+            let mut g = &direct[t] * probas[t] + &future_carried;
+            let mut c = &carried[t] * probas[t];
+            if t != last {
+                let (q, dq, dq_carried) = &wins[t];
+                g = g - dq * (probas[t] * future_value);
+                c = c - dq_carried * (probas[t] * future_value);
+                future_value = values[t] + (1. - q) * future_value;
+            } else {
+                future_value = values[t];
+            }
+            grad.row_mut(t).assign(&g);
+            future_carried += &c;
+        }
+        grad
+    }
+    fn cumulative_disaster_proba(&self, strategies: &S) -> Option<Array<f64, Ix1>> {
+        let actions_seq = strategies.clone().to_actions();
+        let mut state = self.child.state0().clone();
+        let mut probas = vec![1.; self.n()];  // probability that nobody has won yet
+        let mut disaster: Array<f64, Ix1> = Array::zeros(self.n());
+        for (t, actions) in actions_seq.iter().enumerate() {
+            for i in 0..self.n() {
+                let payoff_func = state.belief(i);
+                disaster[i] += probas[i] * payoff_func.proba_disaster(actions)?;
+                let (_, p) = payoff_func.prod_func.f(actions);
+                probas[i] *= 1. - payoff_func.csf.q(p.view()).iter().sum::<f64>();
+            }
+            if t != strategies.t() - 1 {
+                self.advance_state(&mut state, actions);
+            }
+        }
+        Some(disaster)
+    }
+    fn evaluator_i<'a>(&'a self, i: usize, base: &S) -> Box<dyn PlayerEvaluator + 'a> where A: 'a, S: 'a {
+        Box::new(CachedEvaluator::new(self, i, self.child.gammas()[i], base))
+    }
+}
+
+impl<A, S, T, U, V, W, X, Y, Z, C> Params for EndsOnContestWin<A, S, T, U, V, W, X, Y, Z, C>
+where A: ActionType,
+      S: StrategyType<Act = A>,
+      T: ProdFunc<A>,
+      U: RiskFunc,
+      V: CSF,
+      W: RewardFunc,
+      X: DisasterCost,
+      Y: CostFunc<A>,
+      Z: State<DefaultPayoff<A, T, U, V, W, X, Y>> + MutatesOnAction<A>,
+      C: Discounter + StateIterator<A, S, StateType = Z> + Params,
+{
+    fn param_mut(&mut self, name: &str) -> Result<&mut f64, String> {
+        self.child.param_mut(name)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.child.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use numpy::ndarray::Array;
+    use ndarray_rand::rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::cost_func::{FixedInvestCost, FixedUnitCost};
+    use crate::csf::MaybeNoWinCSF;
+    use crate::disaster_cost::ConstantDisasterCost;
+    use crate::prod_func::DefaultProd;
+    use crate::reward_func::LinearReward;
+    use crate::risk_func::WinnerOnlyRisk;
+
+    const N: usize = 3;
+    const NSTEPS: usize = 6;
+    const NTRIALS: usize = 50;
+
+    fn prod_func() -> DefaultProd {
+        DefaultProd::new(
+            Array::from_vec(vec![10., 8., 6.]),
+            Array::from_vec(vec![0.5, 0.5, 0.6]),
+            Array::from_vec(vec![10., 12., 8.]),
+            Array::from_vec(vec![0.5, 0.4, 0.5]),
+        ).unwrap()
+    }
+
+    fn gammas() -> Array<f64, Ix1> {
+        Array::from_vec(vec![0.9, 0.8, 0.7])
+    }
+
+    fn exponential_discounter() -> ExponentialDiscounter<
+        DefaultPayoff<Actions, DefaultProd, WinnerOnlyRisk, MaybeNoWinCSF, LinearReward, ConstantDisasterCost, FixedUnitCost>,
+        DefaultPayoff<Actions, DefaultProd, WinnerOnlyRisk, MaybeNoWinCSF, LinearReward, ConstantDisasterCost, FixedUnitCost>,
+    > {
+        let payoff_func = DefaultPayoff::new(
+            prod_func(),
+            WinnerOnlyRisk::new(N, 0.5),
+            MaybeNoWinCSF::default(),
+            LinearReward::default(N),
+            ConstantDisasterCost::new(N, 1.),
+            FixedUnitCost::from_elem(N, 0.1),
+        ).unwrap();
+        ExponentialDiscounter::new(payoff_func, gammas()).unwrap()
+    }
+
+    fn invest_exp_discounter() -> InvestExpDiscounter<
+        DefaultPayoff<InvestActions, DefaultProd, WinnerOnlyRisk, MaybeNoWinCSF, LinearReward, ConstantDisasterCost, FixedInvestCost>
+    > {
+        let payoff_func = DefaultPayoff::new(
+            prod_func(),
+            WinnerOnlyRisk::new(N, 0.5),
+            MaybeNoWinCSF::default(),
+            LinearReward::default(N),
+            ConstantDisasterCost::new(N, 1.),
+            FixedInvestCost::from_elems(N, 0.1, 0.1),
+        ).unwrap();
+        InvestExpDiscounter::new(payoff_func, gammas()).unwrap()
+    }
+
+    // evaluates a sequence of changes to each player's strategy, some affecting every period
+    // and some only a single period, and checks that the aggregator's evaluator gives exactly
+    // the same payoffs as a full evaluation of the game
+    fn check_incremental<A, S, T>(name: &str, agg: &T, rng: &mut StdRng)
+    where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+    {
+        let base = S::random_using(NSTEPS, N, -1., 0.5, rng).unwrap();
+        for i in 0..N {
+            let mut evaluator = agg.evaluator_i(i, &base);
+            let mut full = FullEvaluator { agg, i, strategies: base.clone(), _phantom: PhantomData };
+            let mut x = base.data().index_axis(Axis(1), i).to_owned();
+            for trial in 0..NTRIALS {
+                if trial % 3 == 0 {
+                    x.assign(&S::random_using(NSTEPS, 1, -1., 0.5, rng).unwrap().data().index_axis(Axis(1), 0));
+                } else {
+                    let t = rng.gen_range(0..NSTEPS);
+                    let m = rng.gen_range(0..S::nparams());
+                    x[[t, m]] *= rng.gen_range(0.5..2.0);
+                }
+                let expected = full.u_i(x.view());
+                let got = evaluator.u_i(x.view());
+                assert_eq!(got.to_bits(), expected.to_bits(), "{}: player {}, trial {}: {} != {}", name, i, trial, got, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_payoffs_match_full_evaluation() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let agg = exponential_discounter();
+        check_incremental("ExponentialDiscounter", &agg, &mut rng);
+        let terminal = agg.clone().with_terminal_value(TerminalValue::Stationary).unwrap();
+        check_incremental("ExponentialDiscounter with stationary terminal value", &terminal, &mut rng);
+        check_incremental("EndsOnContestWin<ExponentialDiscounter>", &EndsOnContestWin::new(agg), &mut rng);
+
+        let agg = invest_exp_discounter();
+        check_incremental("InvestExpDiscounter", &agg, &mut rng);
+        let terminal = agg.clone().with_terminal_value(TerminalValue::Stationary).unwrap();
+        check_incremental("InvestExpDiscounter with stationary terminal value", &terminal, &mut rng);
+        let terminal = agg.clone()
+            .with_terminal_value(TerminalValue::Fixed(Array::from_vec(vec![1., 2., 3.])))
+            .unwrap();
+        check_incremental("InvestExpDiscounter with fixed terminal values", &terminal, &mut rng);
+        check_incremental("EndsOnContestWin<InvestExpDiscounter>", &EndsOnContestWin::new(agg), &mut rng);
+    }
+
+    // compares the analytic gradient of every player's payoff with finite differences
+    fn check_grad<A, S, T>(name: &str, agg: &T, rng: &mut StdRng)
+    where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+    {
+        let strategies = S::random_using(NSTEPS, N, -1., 0.5, rng).unwrap();
+        for i in 0..N {
+            let grad = agg.grad_i(i, &strategies);
+            let fd = fd_grad_i(|s| agg.u_i(i, s), i, &strategies);
+            let scale = fd.fold(0., |acc: f64, x| acc.max(x.abs()));
+            for ((t, m), g) in grad.indexed_iter() {
+                assert!(
+                    (g - fd[[t, m]]).abs() <= 1e-6 * scale.max(1.),
+                    "{}: player {}, d u / d x[{}, {}]: {} != {}", name, i, t, m, g, fd[[t, m]]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn analytic_gradients_match_finite_differences() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let agg = exponential_discounter();
+        check_grad("ExponentialDiscounter", &agg, &mut rng);
+        let terminal = agg.clone().with_terminal_value(TerminalValue::Stationary).unwrap();
+        check_grad("ExponentialDiscounter with stationary terminal value", &terminal, &mut rng);
+        check_grad("EndsOnContestWin<ExponentialDiscounter>", &EndsOnContestWin::new(agg), &mut rng);
+
+        // investments carry over to later periods through the production function
+        let agg = invest_exp_discounter();
+        check_grad("InvestExpDiscounter", &agg, &mut rng);
+        let terminal = agg.clone().with_terminal_value(TerminalValue::Stationary).unwrap();
+        check_grad("InvestExpDiscounter with stationary terminal value", &terminal, &mut rng);
+        check_grad("EndsOnContestWin<InvestExpDiscounter>", &EndsOnContestWin::new(agg), &mut rng);
+    }
+
+    #[test]
+    fn set_param_keeps_terminal_values_valid() {
+        // a stationary terminal value needs gamma < 1, so setting gamma to 1 is rejected and undone
+        let mut agg = exponential_discounter().with_terminal_value(TerminalValue::Stationary).unwrap();
+        assert!(agg.set_param("gammas[0]", 1.).is_err());
+        assert_eq!(agg.gammas[0], 0.9);
+        let mut agg = invest_exp_discounter().with_terminal_value(TerminalValue::Stationary).unwrap();
+        assert!(agg.set_param("gammas[2]", 1.).is_err());
+        assert_eq!(agg.gammas[2], 0.7);
+        // without a terminal value, any gamma is allowed
+        let mut agg = exponential_discounter();
+        agg.set_param("gammas[0]", 1.).unwrap();
+        assert_eq!(agg.gammas[0], 1.);
+    }
 }
@@ -0,0 +1,170 @@
+use std::fmt;
+
+use numpy::ndarray::{Array, Axis, Ix1, concatenate, s};
+
+use crate::multistart::log_distance;
+use crate::payoff_func::PayoffFunc;
+use crate::solve::{InitGuess, SolveResult, SolverOptions, solve};
+use crate::states::{FixedStateDiscounter, PayoffAggregator, State};
+use crate::strategies::*;
+
+#[derive(Clone, Debug)]
+pub struct StationaryResult<S: StrategyType> {
+    // the action profile played in every period, as strategies with t = 1
+    pub strategies: S,
+    // discounted payoffs from playing it forever, u / (1 - gamma)
+    pub payoffs: Array<f64, Ix1>,
+    pub converged: bool,
+    pub iterations: u64,
+}
+
+impl<S: StrategyType> StationaryResult<S> {
+    // the stationary profile played over t periods
+    pub fn repeated(&self, t: usize) -> S {
+        let data = self.strategies.data();
+        S::from_array_unchecked(data.broadcast((t, data.shape()[1], S::nparams())).unwrap().to_owned())
+    }
+}
+
+impl<S: StrategyType + fmt::Display> fmt::Display for StationaryResult<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.converged {
+            writeln!(f, "Stationary profile converged after {} iterations", self.iterations)?;
+        } else {
+            writeln!(f, "Stationary profile did not converge after {} iterations", self.iterations)?;
+        }
+        writeln!(f, "payoffs = {:.4}", self.payoffs)?;
+        write!(f, "{}", self.strategies)
+    }
+}
+
+// first period of an init guess, as a guess for a single period
+fn first_period<S: StrategyType>(init_guess: &InitGuess<S>) -> InitGuess<S> {
+    match init_guess {
+        InitGuess::Random { seed, mu, sigma, .. } => InitGuess::Random { t: 1, seed: *seed, mu: *mu, sigma: *sigma },
+        InitGuess::Fixed(s) => InitGuess::Fixed(S::from_array_unchecked(s.data().slice(s![..1, .., ..]).to_owned())),
+    }
+}
+
+// time-invariant equilibrium of the infinitely repeated game; since the state never changes,
+// each player's payoff is u_i / (1 - gamma_i), so this is the equilibrium of the one-period game
+pub fn solve_stationary<A, S, P, T>(
+    agg: &FixedStateDiscounter<A, S, P, T>, options: &SolverOptions<S>
+) -> Result<StationaryResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, P: PayoffFunc<Act = A>, T: State<P>
+{
+    if agg.gammas.iter().any(|&g| g >= 1.) {
+        return Err(argmin::core::Error::msg("Stationary payoffs require every gamma to be less than 1"));
+    }
+    // terminal values would be counted on top of the stage payoffs, so leave them out
+    let mut stage_game = agg.clone();
+    stage_game.terminal = None;
+    let res = solve(&stage_game, &SolverOptions {
+        init_guess: first_period(&options.init_guess),
+        ..options.clone()
+    })?;
+    Ok(StationaryResult {
+        payoffs: res.payoffs / (1. - &agg.gammas),
+        strategies: res.strategies,
+        converged: res.converged,
+        iterations: res.iterations,
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct HorizonOptions<S: StrategyType> {
+    // options for each solve; init_guess sets the first horizon tried
+    pub solver_options: SolverOptions<S>,
+    // number of leading periods that have to stop changing
+    pub k: usize,
+    // periods added to the horizon each time
+    pub step: usize,
+    pub max_t: usize,
+    // largest change in any log strategy value over the first k periods for them to count as unchanged
+    pub tol: f64,
+}
+
+impl<S: StrategyType> HorizonOptions<S> {
+    pub fn new(t: usize, k: usize) -> Self {
+        HorizonOptions {
+            solver_options: SolverOptions::random_init(t),
+            k,
+            step: t,
+            max_t: 100,
+            tol: 1e-4,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HorizonResult<S: StrategyType> {
+    // solution at the longest horizon tried
+    pub result: SolveResult<S>,
+    pub horizons: Vec<usize>,
+    // largest change in the first k periods each time the horizon was extended
+    pub changes: Vec<f64>,
+    // whether the first k periods stopped changing before max_t was reached
+    pub converged: bool,
+}
+
+impl<S: StrategyType> HorizonResult<S> {
+    pub fn t(&self) -> usize {
+        *self.horizons.last().unwrap()
+    }
+}
+
+impl<S: StrategyType + fmt::Display> fmt::Display for HorizonResult<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.converged {
+            writeln!(f, "Leading periods stable at horizon {}", self.t())?;
+        } else {
+            writeln!(f, "Leading periods still changing at horizon {}", self.t())?;
+        }
+        write!(f, "{}", self.result)
+    }
+}
+
+fn leading<S: StrategyType>(strategies: &S, k: usize) -> S {
+    S::from_array_unchecked(strategies.data().slice(s![..k, .., ..]).to_owned())
+}
+
+// the profile with its last period repeated to fill t periods
+fn extend<S: StrategyType>(strategies: &S, t: usize) -> S {
+    let data = strategies.data();
+    let last = data.index_axis(Axis(0), data.shape()[0] - 1).insert_axis(Axis(0));
+    let extra = last.broadcast((t - data.shape()[0], data.shape()[1], S::nparams())).unwrap();
+    S::from_array_unchecked(concatenate(Axis(0), &[data, extra]).unwrap())
+}
+
+// solves with longer and longer horizons, warm-starting from the previous solution,
+// until the first k periods no longer depend on where the game is cut off
+pub fn solve_extending_horizon<A, S, T>(agg: &T, options: &HorizonOptions<S>) -> Result<HorizonResult<S>, argmin::core::Error>
+where A: ActionType, S: StrategyType<Act = A>, T: PayoffAggregator<A, S>
+{
+    let t0 = options.solver_options.init_guess.t();
+    if options.k == 0 || options.k > t0 {
+        return Err(argmin::core::Error::msg("k must be between 1 and the initial horizon"));
+    }
+    if options.step == 0 {
+        return Err(argmin::core::Error::msg("step must be at least 1"));
+    }
+    let mut result = solve(agg, &options.solver_options)?;
+    let mut horizons = vec![t0];
+    let mut changes = Vec::new();
+    let mut t = t0;
+    while t + options.step <= options.max_t {
+        t += options.step;
+        let next = solve(agg, &SolverOptions {
+            init_guess: InitGuess::Fixed(extend(&result.strategies, t)),
+            ..options.solver_options.clone()
+        })?;
+        let change = log_distance(&leading(&result.strategies, options.k), &leading(&next.strategies, options.k), 1e-8);
+        result = next;
+        horizons.push(t);
+        changes.push(change);
+        if change < options.tol {
+            return Ok(HorizonResult { result, horizons, changes, converged: true });
+        }
+    }
+    Ok(HorizonResult { result, horizons, changes, converged: false })
+}
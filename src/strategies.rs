@@ -1,6 +1,8 @@
 use std::fmt;
 use numpy::ndarray::{Array, ArrayView, Axis, Ix2, Ix3, Ix1, stack, ArrayViewMut, Slice, s};
-use ndarray_rand::{RandomExt, rand_distr::LogNormal};
+use ndarray_rand::{RandomExt, rand::Rng, rand_distr::LogNormal};
+
+use crate::constraints::{LinearConstraint, check_constraints};
 
 pub trait ActionType: Clone + Send + Sync {
     fn data(&self) -> ArrayView<f64, Ix2>;
@@ -19,6 +21,9 @@ pub trait ActionType: Clone + Send + Sync {
 
     fn xs(&self) -> ArrayView<f64, Ix1>;
     fn xp(&self) -> ArrayView<f64, Ix1>;
+
+    // columns of data() that are spent on safety
+    fn safety_params() -> &'static [usize] { &[0] }
 }
 
 // represents actions for n players in a single time period
@@ -69,6 +74,7 @@ pub struct InvestActions(Array<f64, Ix2>);
 
 impl ActionType for InvestActions {
     fn nparams() -> usize { 4 }
+    fn safety_params() -> &'static [usize] { &[0, 2] }
     
     fn data(&self) -> ArrayView<f64, Ix2> {
         self.0.view()
@@ -131,6 +137,14 @@ pub trait MutatesOnAction<A: ActionType>: Clone + Sized {
     }
 }
 
+// states exposing the variables that feedback (Markov) strategies respond to
+pub trait FeedbackState: Clone {
+    // current values of the state variables; these should be positive
+    fn features(&self) -> Array<f64, Ix1>;
+    // copy of this state with the state variables replaced
+    fn with_features(&self, features: ArrayView<f64, Ix1>) -> Self;
+}
+
 
 pub trait StrategyType: Clone + Send + Sync {
     type Act: ActionType;
@@ -151,6 +165,12 @@ pub trait StrategyType: Clone + Send + Sync {
             Ok(Self::from_array_unchecked(data))
         }
     }
+    // same as from_array, but also checks that the strategies satisfy the given constraints
+    fn from_array_constrained(data: Array<f64, Ix3>, constraints: &[LinearConstraint]) -> Result<Self, String> {
+        let strategies = Self::from_array(data)?;
+        check_constraints(&strategies, constraints)?;
+        Ok(strategies)
+    }
     fn from_actions(actions: Vec<Self::Act>) -> Result<Self, String> {
         let data = stack(
             Axis(0),
@@ -170,6 +190,14 @@ pub trait StrategyType: Clone + Send + Sync {
         };
         Ok(Self::from_array_unchecked(Array::random((t, n, Self::nparams()), dist)))
     }
+    // same as random, but drawing from the given rng so that results can be reproduced
+    fn random_using<R: Rng + ?Sized>(t: usize, n: usize, mu: f64, sigma: f64, rng: &mut R) -> Result<Self, String> {
+        let dist = match LogNormal::new(mu, sigma) {
+            Ok(d) => d,
+            Err(e) => return Err(format!("Error when creating LogNormal distribution: {}", e))
+        };
+        Ok(Self::from_array_unchecked(Array::random_using((t, n, Self::nparams()), dist, rng)))
+    }
     
     fn to_actions(self) -> Vec<Self::Act> {
         self.data().outer_iter().map(move |x| {
@@ -1,3 +1,5 @@
+use numpy::ndarray::{Array, ArrayView, Axis, Ix1, Ix2, stack};
+
 pub fn isapprox(a: f64, b: f64, rtol: f64, atol: f64) -> bool
 {
     let maxval = f64::max(a.abs(), b.abs());
@@ -47,3 +49,110 @@ macro_rules! init_rep {
         }
     };
 }
+
+// step size for central finite differences around x
+pub fn fd_step(x: f64) -> f64 {
+    let h = f64::EPSILON.cbrt();
+    if x == 0. { h } else { h * x.abs() }
+}
+
+// one step of the SplitMix64 generator, which scrambles every bit of x
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// seed for the k-th of several random streams derived from seed; unlike seed + k, the streams for
+// different base seeds don't overlap, so sub-seeds can themselves be split again
+pub fn sub_seed(seed: u64, k: usize) -> u64 {
+    splitmix64(splitmix64(seed) ^ k as u64)
+}
+
+// finite difference approximation of the jacobian of f at x, indexed as [j, k] = df_j / dx_k
+pub fn fd_jacobian<F>(f: F, x: ArrayView<f64, Ix1>) -> Array<f64, Ix2>
+where F: Fn(ArrayView<f64, Ix1>) -> Array<f64, Ix1>
+{
+    let mut x_ = x.to_owned();
+    let mut columns = Vec::with_capacity(x.len());
+    for k in 0..x.len() {
+        let h = fd_step(x[k]);
+        x_[k] = x[k] + h;
+        let up = f(x_.view());
+        x_[k] = x[k] - h;
+        let down = f(x_.view());
+        x_[k] = x[k];
+        columns.push((up - down) / (2. * h));
+    }
+    let views = columns.iter().map(|c| c.view()).collect::<Vec<_>>();
+    stack(Axis(1), &views).unwrap()
+}
+
+// asserts that an analytic jacobian matches fd_jacobian(f, x); name identifies the failing case
+#[cfg(test)]
+pub fn assert_jacobian_matches_fd<F>(name: &str, jacobian: &Array<f64, Ix2>, f: F, x: ArrayView<f64, Ix1>)
+where F: Fn(ArrayView<f64, Ix1>) -> Array<f64, Ix1>
+{
+    let fd = fd_jacobian(f, x);
+    assert_eq!(jacobian.dim(), fd.dim(), "{}: jacobian has the wrong shape", name);
+    assert!(
+        isapprox_iters(jacobian.iter().copied(), fd.iter().copied(), 1e-6, 1e-8),
+        "{}: jacobian doesn't match finite differences\nanalytic: {}\nfinite differences: {}", name, jacobian, fd
+    );
+}
+
+// solve a x = b by gaussian elimination with partial pivoting; None if a is singular
+pub fn solve_linear(a: ArrayView<f64, Ix2>, b: ArrayView<f64, Ix1>) -> Option<Array<f64, Ix1>> {
+    let n = b.len();
+    let mut a = a.to_owned();
+    let mut b = b.to_owned();
+    for k in 0..n {
+        let pivot = (k..n).max_by(|&i, &j| a[[i, k]].abs().total_cmp(&a[[j, k]].abs()))?;
+        if a[[pivot, k]].abs() <= f64::EPSILON * a.fold(0., |acc: f64, x| acc.max(x.abs())) {
+            return None;
+        }
+        if pivot != k {
+            for j in 0..n {
+                a.swap([k, j], [pivot, j]);
+            }
+            b.swap(k, pivot);
+        }
+        for i in (k + 1)..n {
+            let factor = a[[i, k]] / a[[k, k]];
+            for j in k..n {
+                a[[i, j]] -= factor * a[[k, j]];
+            }
+            b[i] -= factor * b[k];
+        }
+    }
+    let mut x = Array::zeros(n);
+    for k in (0..n).rev() {
+        let rest = (k + 1..n).map(|j| a[[k, j]] * x[j]).sum::<f64>();
+        x[k] = (b[k] - rest) / a[[k, k]];
+    }
+    Some(x)
+}
+
+// whether the symmetric part of a is negative definite, checked by attempting a cholesky
+// factorization of its negation
+pub fn is_negative_definite(a: ArrayView<f64, Ix2>) -> bool {
+    let n = a.nrows();
+    let mut l = Array::<f64, Ix2>::zeros((n, n));
+    for j in 0..n {
+        for k in 0..=j {
+            let m = -0.5 * (a[[j, k]] + a[[k, j]]);
+            let rest = (0..k).map(|p| l[[j, p]] * l[[k, p]]).sum::<f64>();
+            if j == k {
+                let d = m - rest;
+                if d.is_nan() || d <= 0. {
+                    return false;
+                }
+                l[[j, j]] = d.sqrt();
+            } else {
+                l[[j, k]] = (m - rest) / l[[k, k]];
+            }
+        }
+    }
+    true
+}
@@ -23,7 +23,7 @@ class Tester:
     def solve_agg(self, agg, strat_type = 'strategies', plot = False):
         print(f"Solving for optimal {strat_type}, with {self.n} players and {self.t} time steps...")
         time0 = time()
-        res = agg.solve(self.t)
+        res = agg.solve(self.t).strategies
         time1 = time()
         print(f"Solved in {time1 - time0:.3f} seconds")
         print(f"Optimal {strat_type}:", res, sep = '\n')
@@ -109,7 +109,7 @@ class Tester:
         print(f"Solved in {time1 - time0:.3f} seconds")
         print("Optimal invest strategies:")
         for i, r in enumerate(res):
-            print(f'Problem {i+1}:\n{r}\n')
+            print(f'Problem {i+1}:\n{r.strategies}\n')
 
 
 